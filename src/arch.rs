@@ -0,0 +1,231 @@
+//! CPU architecture detection of executables.
+//!
+//! The driver has to match the browser, not the host: a 32-bit Chrome on
+//! 64-bit Windows needs the `win32` chromedriver. [`binary_architectures`]
+//! reads the architecture from the PE, Mach-O or ELF header of a binary.
+//! Universal (fat) Mach-O binaries report every slice they contain.
+
+use crate::error::WebDriverError;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+/// A CPU architecture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arch {
+    X86,
+    X86_64,
+    Arm,
+    Aarch64,
+}
+
+impl Arch {
+    /// The architecture this crate was compiled for.
+    pub fn host() -> Option<Self> {
+        match std::env::consts::ARCH {
+            "x86" => Some(Arch::X86),
+            "x86_64" => Some(Arch::X86_64),
+            "arm" => Some(Arch::Arm),
+            "aarch64" => Some(Arch::Aarch64),
+            _ => None,
+        }
+    }
+
+    /// The architecture of the machine. Unlike [`Arch::host`] this sees
+    /// through Rosetta 2: an x86_64 build running on Apple Silicon reports
+    /// [`Arch::Aarch64`].
+    pub fn machine() -> Option<Self> {
+        if is_rosetta_translated() {
+            Some(Arch::Aarch64)
+        } else {
+            Self::host()
+        }
+    }
+
+    /// The name used by `std::env::consts::ARCH`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::Arm => "arm",
+            Arch::Aarch64 => "aarch64",
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Enough to cover the PE header of any real executable and the fat header of
+// a universal binary with a handful of slices.
+const HEADER_LEN: u64 = 4096;
+
+/// Reads the architectures of an executable from its header. Returns an
+/// empty list for files that are not PE, Mach-O or ELF binaries, such as
+/// the shell script wrappers some Linux distributions install.
+pub fn binary_architectures(path: &Path) -> Result<Vec<Arch>, WebDriverError> {
+    let mut header = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_LEN).read_to_end(&mut header))
+        .map_err(|e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+
+    Ok(parse_architectures(&header))
+}
+
+/// Whether the current process is an x86_64 build translated by Rosetta 2.
+pub fn is_rosetta_translated() -> bool {
+    if !cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        return false;
+    }
+    std::process::Command::new("sysctl")
+        .args(["-n", "sysctl.proc_translated"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// The architecture to pick from the slices of a binary: the machine's when
+/// the binary contains it, otherwise the first one. An x86_64-only browser on
+/// Apple Silicon runs under Rosetta and therefore needs an x86_64 driver.
+pub fn preferred_arch(architectures: &[Arch]) -> Option<Arch> {
+    Arch::machine()
+        .filter(|host| architectures.contains(host))
+        .or_else(|| architectures.first().copied())
+}
+
+/// Fails with [`WebDriverError::ArchitectureMismatch`] when the binary at
+/// `path` doesn't contain `expected`. Files that aren't recognized as
+/// executables pass; there is nothing to compare.
+pub fn check_architecture(path: &Path, expected: Arch) -> Result<(), WebDriverError> {
+    let found = binary_architectures(path)?;
+    if found.is_empty() || found.contains(&expected) {
+        return Ok(());
+    }
+    Err(WebDriverError::ArchitectureMismatch {
+        expected: expected.to_string(),
+        found: found.iter().map(Arch::as_str).collect::<Vec<_>>().join(", "),
+    })
+}
+
+fn parse_architectures(header: &[u8]) -> Vec<Arch> {
+    match header {
+        [0x7f, b'E', b'L', b'F', ..] => parse_elf(header).into_iter().collect(),
+        [b'M', b'Z', ..] => parse_pe(header).into_iter().collect(),
+        [0xca, 0xfe, 0xba, 0xbe | 0xbf, ..] => parse_fat_mach_o(header),
+        [0xce | 0xcf, 0xfa, 0xed, 0xfe, ..] => mach_o_cpu(read_u32(header, 4, false)).into_iter().collect(),
+        [0xfe, 0xed, 0xfa, 0xce | 0xcf, ..] => mach_o_cpu(read_u32(header, 4, true)).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_elf(header: &[u8]) -> Option<Arch> {
+    // EI_DATA: 1 = little endian, 2 = big endian.
+    let big_endian = *header.get(5)? == 2;
+    match read_u16(header, 18, big_endian)? {
+        0x03 => Some(Arch::X86),
+        0x3e => Some(Arch::X86_64),
+        0x28 => Some(Arch::Arm),
+        0xb7 => Some(Arch::Aarch64),
+        _ => None,
+    }
+}
+
+fn parse_pe(header: &[u8]) -> Option<Arch> {
+    let pe_offset = read_u32(header, 0x3c, false)? as usize;
+    if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+    match read_u16(header, pe_offset + 4, false)? {
+        0x014c => Some(Arch::X86),
+        0x8664 => Some(Arch::X86_64),
+        0x01c0 | 0x01c4 => Some(Arch::Arm),
+        0xaa64 => Some(Arch::Aarch64),
+        _ => None,
+    }
+}
+
+fn parse_fat_mach_o(header: &[u8]) -> Vec<Arch> {
+    // FAT_MAGIC_64 uses 32 byte entries instead of 20.
+    let entry_len = if header[3] == 0xbf { 32 } else { 20 };
+    let count = read_u32(header, 4, true).unwrap_or(0) as usize;
+
+    // Java class files share the magic number; their version is never this small.
+    if count > 32 {
+        return Vec::new();
+    }
+    (0..count)
+        .filter_map(|i| mach_o_cpu(read_u32(header, 8 + i * entry_len, true)))
+        .collect()
+}
+
+fn mach_o_cpu(cpu_type: Option<u32>) -> Option<Arch> {
+    const CPU_ARCH_ABI64: u32 = 0x0100_0000;
+    match cpu_type? {
+        7 => Some(Arch::X86),
+        t if t == 7 | CPU_ARCH_ABI64 => Some(Arch::X86_64),
+        12 => Some(Arch::Arm),
+        t if t == 12 | CPU_ARCH_ABI64 => Some(Arch::Aarch64),
+        _ => None,
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let raw: [u8; 2] = bytes.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if big_endian { u16::from_be_bytes(raw) } else { u16::from_le_bytes(raw) })
+}
+
+fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) })
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_architectures() {
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1, 0];
+        elf.resize(18, 0);
+        elf.extend([0x3e, 0x00]);
+        assert_eq!(parse_architectures(&elf), vec![Arch::X86_64]);
+
+        let mut pe = vec![0u8; 0x80];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        pe[0x44..0x46].copy_from_slice(&0x014cu16.to_le_bytes());
+        assert_eq!(parse_architectures(&pe), vec![Arch::X86]);
+
+        let mut fat = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2];
+        for cpu in [0x0100_0007u32, 0x0100_000c] {
+            fat.extend(cpu.to_be_bytes());
+            fat.extend([0u8; 16]);
+        }
+        assert_eq!(parse_architectures(&fat), vec![Arch::X86_64, Arch::Aarch64]);
+        assert_eq!(preferred_arch(&[]), None);
+
+        assert!(parse_architectures(b"#!/bin/sh\nexec chrome").is_empty());
+    }
+
+    #[test]
+    fn test_current_executable_matches_host() {
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(preferred_arch(&binary_architectures(&exe).unwrap()), Arch::host());
+
+        let host = Arch::host().unwrap();
+        let other = if host == Arch::Arm { Arch::X86 } else { Arch::Arm };
+        assert!(check_architecture(&exe, host).is_ok());
+        assert!(matches!(
+            check_architecture(&exe, other),
+            Err(WebDriverError::ArchitectureMismatch { found, .. }) if found == host.as_str()
+        ));
+    }
+}
@@ -0,0 +1,481 @@
+//! The `webdriver-manager` command-line tool, built with the `cli` feature.
+//!
+//! ```text
+//! webdriver-manager install chrome
+//! webdriver-manager install firefox --version 128
+//! webdriver-manager install chrome --output json
+//! webdriver-manager path chrome
+//! eval "$(webdriver-manager env chrome)"
+//! webdriver-manager resolve chrome --browser-version 126
+//! webdriver-manager list
+//! webdriver-manager clean --keep 2
+//! webdriver-manager doctor
+//! webdriver-manager bundle create drivers.zip --driver chromedriver --metadata
+//! webdriver-manager bundle install drivers.zip
+//! webdriver-manager mirror sync --output-dir /srv/mirror --base-url https://mirror.corp/drivers --version 138
+//! ```
+//!
+//! Settings come from `webdriver-manager.toml` and the `WDM_*` environment
+//! variables (see `webdriver_manager::config`); the flags override them.
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use webdriver_manager::browser::detect_installed_browsers;
+use webdriver_manager::bundle::{create_bundle, install_bundle, BundleItem};
+use webdriver_manager::cache::{Cache, InstalledDriver, PrunePolicy};
+use webdriver_manager::doctor::{Check, CheckStatus, run_checks};
+use webdriver_manager::http::{NetworkPolicy, ProxyConfig};
+use webdriver_manager::mirror::{sync_mirror, MirrorConfig};
+use webdriver_manager::selenium_manager::{self, SeleniumManagerOutput};
+use webdriver_manager::{Browser, Config, SetupOptions, WebDriverError};
+
+#[derive(Debug, Parser)]
+#[command(name = "webdriver-manager", version, about = "Downloads and manages browser drivers")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Installs the driver for a browser and prints its path.
+    Install(InstallCommandArgs),
+    /// Installs the driver if needed and prints only its path.
+    Path(InstallArgs),
+    /// Installs the driver if needed and prints shell `export` lines for it,
+    /// for `eval "$(webdriver-manager env chrome)"`.
+    Env(InstallArgs),
+    /// Prints the driver version and URL an install would use, without
+    /// downloading anything.
+    Resolve(ResolveArgs),
+    /// Lists the installed drivers and the browsers found on the system.
+    List(CacheArgs),
+    /// Removes installed drivers.
+    Clean(CleanArgs),
+    /// Checks browsers, network, cache and installed drivers for problems.
+    Doctor(DoctorArgs),
+    /// Packages installed drivers for machines without network access.
+    #[command(subcommand)]
+    Bundle(BundleCommand),
+    /// Maintains an internal mirror of the chromedriver downloads.
+    #[command(subcommand)]
+    Mirror(MirrorCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum BundleCommand {
+    /// Packages installed drivers, and optionally the cached metadata, into
+    /// a zip archive.
+    Create(BundleCreateArgs),
+    /// Installs the drivers and metadata of a bundle into the cache,
+    /// verifying every checksum.
+    Install(BundleInstallArgs),
+}
+
+#[derive(Debug, Args)]
+struct BundleCreateArgs {
+    /// Path of the bundle to write.
+    output: PathBuf,
+    /// Driver to include, e.g. `chromedriver`; repeatable. Without one every
+    /// installed driver is included.
+    #[arg(long = "driver", value_name = "DRIVER")]
+    drivers: Vec<String>,
+    /// Includes the cached metadata documents, so version lookups work
+    /// offline too.
+    #[arg(long)]
+    metadata: bool,
+    #[command(flatten)]
+    cache: CacheArgs,
+}
+
+#[derive(Debug, Args)]
+struct BundleInstallArgs {
+    /// The bundle to install.
+    bundle: PathBuf,
+    #[command(flatten)]
+    cache: CacheArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum MirrorCommand {
+    /// Downloads the selected chromedriver builds and the Chrome for Testing
+    /// metadata into a directory, with the URLs pointing at the mirror.
+    Sync(MirrorSyncArgs),
+}
+
+#[derive(Debug, Args)]
+struct MirrorSyncArgs {
+    /// Directory the mirror is written to.
+    #[arg(long)]
+    output_dir: PathBuf,
+    /// Public URL the directory is served from.
+    #[arg(long)]
+    base_url: String,
+    /// Version or version prefix to mirror, e.g. `138`; repeatable. Without
+    /// one every version is mirrored.
+    #[arg(long = "version", value_name = "VERSION")]
+    versions: Vec<String>,
+    /// Platform to mirror, e.g. `linux64`; repeatable. Without one every
+    /// platform is mirrored.
+    #[arg(long = "platform", value_name = "PLATFORM")]
+    platforms: Vec<String>,
+    /// Proxy URL for all requests.
+    #[arg(long)]
+    proxy: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct DoctorArgs {
+    /// Cache directory, instead of the configured or user cache.
+    #[arg(long)]
+    install_dir: Option<PathBuf>,
+    /// Skips the network checks.
+    #[arg(long)]
+    offline: bool,
+    /// Proxy URL for all requests.
+    #[arg(long)]
+    proxy: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct InstallCommandArgs {
+    #[command(flatten)]
+    install: InstallArgs,
+    /// Output format; `json` is Selenium Manager's JSON document.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Args)]
+struct ResolveArgs {
+    #[command(flatten)]
+    install: InstallArgs,
+    /// Prints the plan as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct CacheArgs {
+    /// Cache directory, instead of the configured or user cache.
+    #[arg(long)]
+    install_dir: Option<PathBuf>,
+}
+
+impl CacheArgs {
+    fn cache(self) -> Result<Cache, WebDriverError> {
+        match self.install_dir {
+            Some(dir) => Ok(Cache::new(dir)),
+            None => Config::load()?.cache().map_or_else(Cache::user, Ok),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("policy").required(true).args(["keep", "older_than", "all"])))]
+struct CleanArgs {
+    /// Keeps the newest COUNT versions of every driver.
+    #[arg(long, value_name = "COUNT")]
+    keep: Option<usize>,
+    /// Removes versions installed more than DAYS days ago.
+    #[arg(long, value_name = "DAYS")]
+    older_than: Option<u64>,
+    /// Removes every installed driver.
+    #[arg(long)]
+    all: bool,
+    #[command(flatten)]
+    cache: CacheArgs,
+}
+
+#[derive(Debug, Args)]
+struct InstallArgs {
+    /// The browser: chrome, chromium, brave, edge, firefox, safari or ie.
+    browser: String,
+    #[command(flatten)]
+    setup: SetupArgs,
+}
+
+/// Flags overriding the configuration of an install.
+#[derive(Debug, Args)]
+struct SetupArgs {
+    /// Browser version or version spec (`126`, `stable`, `=0.35.0`, `>=125,<127`)
+    /// instead of the installed browser's version.
+    #[arg(long, visible_alias = "browser-version")]
+    version: Option<String>,
+    /// Path to the browser executable, instead of detecting it.
+    #[arg(long)]
+    browser_path: Option<PathBuf>,
+    /// Cache directory the driver is installed into.
+    #[arg(long)]
+    install_dir: Option<PathBuf>,
+    /// Uses cached drivers and metadata only.
+    #[arg(long)]
+    offline: bool,
+    /// Proxy URL for all requests.
+    #[arg(long)]
+    proxy: Option<String>,
+    /// Lockfile pinning the driver.
+    #[arg(long)]
+    lockfile: Option<PathBuf>,
+    /// Resolves the driver again and updates its pin in the lockfile.
+    #[arg(long, requires = "lockfile")]
+    update: bool,
+}
+
+impl SetupArgs {
+    /// The configured setup options for `browser`, with the flags applied.
+    fn setup_options(self, browser: Browser) -> Result<SetupOptions, WebDriverError> {
+        let mut config = Config::load()?;
+        config.register_browser_paths();
+        if let Some(dir) = self.install_dir {
+            config.install_dir = Some(dir);
+        }
+        if let Some(lockfile) = self.lockfile {
+            config.lockfile = Some(lockfile);
+        }
+        let mut options = config.setup_options(browser)?;
+
+        if let Some(version) = self.version {
+            options.version = Some(version);
+        }
+        options.browser_path = self.browser_path;
+        options.update_lockfile = self.update;
+        if self.offline {
+            options.download_options.network = NetworkPolicy::Offline;
+        }
+        if let Some(proxy) = self.proxy {
+            options.download_options.proxy = Some(ProxyConfig::new(proxy));
+        }
+        Ok(options)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<ExitCode, WebDriverError> {
+    match command {
+        Command::Install(args) if args.output == OutputFormat::Json => {
+            let output = match selenium_manager_options(args.install) {
+                Ok((browser, options)) => selenium_manager::resolve(browser, options).await,
+                Err(e) => SeleniumManagerOutput::failure(&e),
+            };
+            println!("{}", output.to_json());
+            return Ok(ExitCode::from(u8::try_from(output.result.code).unwrap_or(1)));
+        }
+        Command::Install(args) => {
+            let args = args.install;
+            let browser: Browser = args.browser.parse()?;
+            let options = args.setup.setup_options(browser)?;
+            let driver_path = webdriver_manager::setup_with(browser, options).await?;
+            println!("{}", driver_path.display());
+        }
+        Command::Path(args) => {
+            let browser: Browser = args.browser.parse()?;
+            let options = args.setup.setup_options(browser)?;
+            let driver_path = webdriver_manager::setup_with(browser, options).await?;
+            println!("{}", driver_path.display());
+        }
+        Command::Env(args) => {
+            let browser: Browser = args.browser.parse()?;
+            let options = args.setup.setup_options(browser)?;
+            let driver_path = webdriver_manager::setup_with(browser, options).await?;
+            print!("{}", env_exports(&driver_path));
+        }
+        Command::Resolve(args) => {
+            let browser: Browser = args.install.browser.parse()?;
+            let options = args.install.setup.setup_options(browser)?;
+            let plan = webdriver_manager::plan_with(browser, options).await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&plan).expect("install plans are serializable"));
+            } else {
+                println!("browser version: {}", plan.browser_version);
+                println!("driver version:  {}", plan.driver_version);
+                println!("url:             {}", plan.url);
+                println!("target:          {}", plan.target_path.display());
+                println!("cached:          {}", plan.cached);
+            }
+        }
+        Command::List(args) => {
+            let cache = args.cache()?;
+            println!("Installed drivers ({}):", cache.root().display());
+            print_builds(&cache.list_installed()?);
+
+            println!("Browsers:");
+            for browser in detect_installed_browsers().await {
+                println!(
+                    "  {:<16} {:<18} {:<20} {}",
+                    browser.name,
+                    browser.channel.as_str(),
+                    browser.version.as_deref().unwrap_or("unknown"),
+                    browser.path.display()
+                );
+            }
+        }
+        Command::Clean(args) => {
+            let cache = args.cache.cache()?;
+            let removed = match (args.keep, args.older_than) {
+                (Some(count), _) => cache.prune(PrunePolicy::KeepLatest(count))?,
+                (_, Some(days)) => cache.prune(PrunePolicy::OlderThan(days))?,
+                _ => cache.clear()?,
+            };
+            println!("Removed {} driver build(s):", removed.len());
+            print_builds(&removed);
+        }
+        Command::Doctor(args) => {
+            let mut config = Config::load()?;
+            config.register_browser_paths();
+            if let Some(dir) = args.install_dir {
+                config.install_dir = Some(dir);
+            }
+            if args.offline {
+                config.offline = Some(true);
+            }
+            if let Some(proxy) = args.proxy {
+                config.proxy = Some(proxy);
+            }
+            let checks = run_checks(&config).await;
+            print_checks(&checks);
+            if checks.iter().any(|check| check.status == CheckStatus::Error) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Bundle(BundleCommand::Create(args)) => {
+            let cache = args.cache.cache()?;
+            let mut items: Vec<BundleItem> = cache
+                .list_installed()?
+                .into_iter()
+                .filter(|build| args.drivers.is_empty() || args.drivers.contains(&build.name))
+                .map(|build| {
+                    let dir = cache.install_dir(&build.name, &build.version, &build.platform);
+                    BundleItem::driver(&build.name, &build.version, &build.platform, dir)
+                })
+                .collect();
+            if args.metadata
+                && let Ok(entries) = std::fs::read_dir(cache.metadata_dir())
+            {
+                for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+                    items.push(BundleItem::metadata(&entry.file_name().to_string_lossy(), entry.path()));
+                }
+            }
+            if items.is_empty() {
+                return Err(WebDriverError::Custom("Nothing to bundle: no matching drivers installed".to_string()));
+            }
+            let manifest = create_bundle(&items, &args.output).await?;
+            println!("Bundled {} item(s) into {}", manifest.items.len(), args.output.display());
+        }
+        Command::Bundle(BundleCommand::Install(args)) => {
+            let cache = args.cache.cache()?;
+            let manifest = install_bundle(&args.bundle, &cache).await?;
+            println!("Installed {} item(s) into {}:", manifest.items.len(), cache.root().display());
+            for item in &manifest.items {
+                println!("  {}", item.install_path(&cache).display());
+            }
+        }
+        Command::Mirror(MirrorCommand::Sync(args)) => {
+            let mut config = Config::load()?;
+            if let Some(proxy) = args.proxy {
+                config.proxy = Some(proxy);
+            }
+            let report = sync_mirror(&MirrorConfig {
+                output_dir: args.output_dir,
+                base_url: args.base_url,
+                versions: args.versions,
+                platforms: args.platforms,
+                download_options: config.download_options()?,
+            })
+            .await?;
+            println!("Downloaded {} archive(s), {} already mirrored.", report.downloaded.len(), report.skipped.len());
+            println!("{}", report.metadata_path.display());
+            println!("{}", report.channels_path.display());
+            println!("{}", report.builds_path.display());
+            println!("{}", report.milestones_path.display());
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The browser and options of an install reported in Selenium Manager's format.
+fn selenium_manager_options(args: InstallArgs) -> Result<(Browser, SetupOptions), WebDriverError> {
+    let browser: Browser = args.browser.parse()?;
+    Ok((browser, args.setup.setup_options(browser)?))
+}
+
+/// `export` lines setting `CHROMEDRIVER` (after the driver's file name) to
+/// the driver and prepending its directory to `PATH`.
+fn env_exports(driver_path: &Path) -> String {
+    let name = driver_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_uppercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+        .unwrap_or_else(|| "WEBDRIVER".to_string());
+    let mut exports = format!("export {}={}\n", name, shell_quote(&driver_path.to_string_lossy()));
+    if let Some(dir) = driver_path.parent() {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        exports.push_str(&format!("export PATH={}\"{}$PATH\"\n", shell_quote(&dir.to_string_lossy()), separator));
+    }
+    exports
+}
+
+/// Quotes `value` for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Prints one line per check, colored when stdout is a terminal.
+fn print_checks(checks: &[Check]) {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    for check in checks {
+        let status = format!("{:<7}", check.status.to_string());
+        let status = match (color, check.status) {
+            (false, _) => status,
+            (true, CheckStatus::Ok) => format!("\x1b[32m{}\x1b[0m", status),
+            (true, CheckStatus::Warning) => format!("\x1b[33m{}\x1b[0m", status),
+            (true, CheckStatus::Error) => format!("\x1b[31m{}\x1b[0m", status),
+        };
+        println!("[{}] {:<40} {}", status, check.name, check.detail);
+    }
+}
+
+fn print_builds(builds: &[InstalledDriver]) {
+    if builds.is_empty() {
+        println!("  (none)");
+    }
+    for build in builds {
+        println!("  {:<16} {:<18} {:<12} {}", build.name, build.version, build.platform, build.path.display());
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_env_exports() {
+        let exports = env_exports(Path::new("/tmp/it's/chromedriver"));
+        assert_eq!(
+            exports,
+            "export CHROMEDRIVER='/tmp/it'\\''s/chromedriver'\nexport PATH='/tmp/it'\\''s'\":$PATH\"\n"
+        );
+        assert!(env_exports(Path::new("/d/IEDriverServer.exe")).starts_with("export IEDRIVERSERVER="));
+    }
+}
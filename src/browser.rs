@@ -1,92 +1,803 @@
 //! Description [TODO]
 
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+use regex::Regex;
+use crate::arch::{binary_architectures, preferred_arch, Arch};
 use crate::error::WebDriverError;
+use crate::runtime;
 
 #[cfg(target_os = "windows")]
 use std::process::Command as StdCommand;
 
+/// The browsers this crate can manage drivers for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Browser {
+    #[default]
+    Chrome,
+    /// The open-source Chromium browser, driven by chromedriver.
+    Chromium,
+    /// Brave, a Chromium-based browser driven by chromedriver.
+    Brave,
+    /// Microsoft Edge, driven by msedgedriver.
+    Edge,
+    Firefox,
+    Safari,
+    InternetExplorer,
+}
+
+impl Browser {
+    /// The canonical lowercase name, as accepted by [`get_browser_version`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Chromium => "chromium",
+            Browser::Brave => "brave",
+            Browser::Edge => "edge",
+            Browser::Firefox => "firefox",
+            Browser::Safari => "safari",
+            Browser::InternetExplorer => "ie",
+        }
+    }
+}
+
+impl fmt::Display for Browser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Browser {
+    type Err = WebDriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "chrome" | "google-chrome" | "googlechrome" => Ok(Browser::Chrome),
+            "chromium" | "chromium-browser" => Ok(Browser::Chromium),
+            "brave" | "brave-browser" => Ok(Browser::Brave),
+            "edge" | "msedge" | "microsoft-edge" | "microsoft edge" => Ok(Browser::Edge),
+            "firefox" | "ff" | "mozilla firefox" => Ok(Browser::Firefox),
+            "safari" => Ok(Browser::Safari),
+            "ie" | "internet explorer" | "internetexplorer" | "iexplore" => Ok(Browser::InternetExplorer),
+            _ => Err(WebDriverError::UnsupportedBrowser(s.to_string())),
+        }
+    }
+}
+
+/// Returns the version of the Chromium engine underlying a browser version,
+/// which is what chromedriver has to match.
+///
+/// Brave reports `<chromium major>.<brave version>` (e.g. `127.1.68.131`),
+/// so only the Chromium major version (`127`) can be recovered from it.
+pub fn chromium_version(browser: Browser, version: &str) -> String {
+    match browser {
+        Browser::Brave => version.split('.').next().unwrap_or(version).to_string(),
+        _ => version.to_string(),
+    }
+}
+
 /// Gets the version of the specified browser.
 /// 
 /// If `path` is provided, it will be used directly. Otherwise, the function will
 /// attempt to find the browser in standard system locations.
-/// On Windows, it uses PowerShell for Chrome and parses `application.ini` for Firefox.
-/// On macOS and Linux, it uses the `--version` or `-V` command-line flag.
+/// Firefox versions are read from `application.ini` on every platform.
+/// On Windows, it uses PowerShell for Chrome.
+/// On macOS it reads the `Info.plist` of the app bundle. Otherwise, and when
+/// the ini or plist files are missing, it uses the `--version` or `-V`
+/// command-line flag.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret, err))]
 pub async fn get_browser_version(
     browser_name: &str,
     path_override: Option<&Path>,
 ) -> Result<String, WebDriverError> {
     let path = match path_override {
         Some(p) => p.to_path_buf(),
-        None => find_browser_path(browser_name).ok_or(WebDriverError::BrowserNotFound)?,
+        None => locate_browser(browser_name).await.ok_or(WebDriverError::BrowserNotFound)?,
     };
     get_version_on_platform(browser_name, &path).await
 }
 
+/// Release channel of an installed browser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BrowserChannel {
+    #[default]
+    Stable,
+    Beta,
+    Dev,
+    Canary,
+    Nightly,
+    /// Firefox Extended Support Release.
+    Esr,
+    /// Firefox Developer Edition.
+    DeveloperEdition,
+}
+
+impl BrowserChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BrowserChannel::Stable => "stable",
+            BrowserChannel::Beta => "beta",
+            BrowserChannel::Dev => "dev",
+            BrowserChannel::Canary => "canary",
+            BrowserChannel::Nightly => "nightly",
+            BrowserChannel::Esr => "esr",
+            BrowserChannel::DeveloperEdition => "devedition",
+        }
+    }
+}
+
+/// The name [`get_browser_version`] accepts for a release channel of a
+/// browser, e.g. `chrome-beta`. The stable channel uses the plain name.
+pub fn channel_browser_name(browser_name: &str, channel: BrowserChannel) -> String {
+    match channel {
+        BrowserChannel::Stable => browser_name.to_string(),
+        _ => format!("{}-{}", browser_name, channel.as_str()),
+    }
+}
+
+/// How a browser was installed. Snap and Flatpak browsers run confined, which
+/// may need workarounds such as a driver placed inside the sandbox or
+/// `--no-sandbox`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BrowserPackaging {
+    #[default]
+    Native,
+    Snap,
+    Flatpak,
+}
+
+impl BrowserPackaging {
+    /// Determines the packaging from the path of the browser executable.
+    pub fn from_path(path: &Path) -> Self {
+        if path.starts_with("/snap") {
+            BrowserPackaging::Snap
+        } else if path.to_string_lossy().contains("flatpak/exports/bin") {
+            BrowserPackaging::Flatpak
+        } else {
+            BrowserPackaging::Native
+        }
+    }
+
+    /// Whether the browser runs in a sandbox.
+    pub fn is_sandboxed(&self) -> bool {
+        *self != BrowserPackaging::Native
+    }
+}
+
+/// A browser found on the system by [`detect_installed_browsers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserInfo {
+    /// The browser name as accepted by [`get_browser_version`], e.g. `"chrome"`.
+    pub name: String,
+    pub path: PathBuf,
+    /// `None` when the browser was found but its version could not be read.
+    pub version: Option<String>,
+    pub channel: BrowserChannel,
+    /// The architecture of the browser binary, e.g. `x86_64` or `aarch64`.
+    /// Falls back to the host architecture when it can't be read.
+    pub arch: String,
+    pub packaging: BrowserPackaging,
+}
+
+/// Browsers and channels probed by [`detect_installed_browsers`].
+const DETECTABLE_BROWSERS: &[(&str, BrowserChannel)] = &[
+    ("chrome", BrowserChannel::Stable),
+    ("chrome", BrowserChannel::Beta),
+    ("chrome", BrowserChannel::Dev),
+    ("chrome", BrowserChannel::Canary),
+    ("chromium", BrowserChannel::Stable),
+    ("brave", BrowserChannel::Stable),
+    ("edge", BrowserChannel::Stable),
+    ("firefox", BrowserChannel::Stable),
+    ("firefox", BrowserChannel::Esr),
+    ("firefox", BrowserChannel::DeveloperEdition),
+    ("firefox", BrowserChannel::Nightly),
+];
+
+/// Every name accepted by [`find_browser_path`].
+const SUPPORTED_BROWSER_NAMES: &[&str] = &[
+    "chrome", "chrome-beta", "chrome-dev", "chrome-canary",
+    "chromium", "brave", "edge",
+    "firefox", "firefox-esr", "firefox-devedition", "firefox-nightly",
+];
+
+/// Scans the system for every supported browser and returns what was found.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+pub async fn detect_installed_browsers() -> Vec<BrowserInfo> {
+    let mut found = Vec::new();
+
+    for &(name, channel) in DETECTABLE_BROWSERS {
+        let lookup_name = channel_browser_name(name, channel);
+        if let Some(path) = locate_browser(&lookup_name).await {
+            // Channels without a dedicated location resolve to the stable install.
+            if found.iter().any(|info: &BrowserInfo| info.path == path) {
+                continue;
+            }
+            let version = get_version_on_platform(&lookup_name, &path).await.ok();
+            debug!(browser = %lookup_name, path = %path.display(), version = ?version, "found browser");
+
+            // ESR usually installs in place of the regular release and only
+            // gives itself away through its version ("115.13.0esr").
+            let channel = match &version {
+                Some(version) if channel == BrowserChannel::Stable && version.ends_with("esr") => BrowserChannel::Esr,
+                _ => channel,
+            };
+
+            found.push(BrowserInfo {
+                name: name.to_string(),
+                packaging: BrowserPackaging::from_path(&path),
+                arch: browser_arch(&path).map_or(std::env::consts::ARCH, |arch| arch.as_str()).to_string(),
+                path,
+                version,
+                channel,
+            });
+        }
+    }
+
+    // Safari only exists on macOS and reports its version in Info.plist.
+    let safari = Path::new("/Applications/Safari.app");
+    if cfg!(target_os = "macos") && runtime::fs::exists(safari).await.unwrap_or(false) {
+        let version = runtime::fs::read_to_string(safari.join("Contents").join("Info.plist"))
+            .await
+            .ok()
+            .and_then(|plist| plist_string_value(&plist, "CFBundleShortVersionString"));
+        found.push(BrowserInfo {
+            name: "safari".to_string(),
+            path: safari.join("Contents").join("MacOS").join("Safari"),
+            version,
+            channel: BrowserChannel::Stable,
+            arch: std::env::consts::ARCH.to_string(),
+            packaging: BrowserPackaging::Native,
+        });
+    }
+
+    found
+}
+
+static EXTRA_SEARCH_PATHS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+/// Registers an additional location to search when a browser is not found
+/// in the standard system locations, e.g. a portable Chrome in
+/// `D:\tools\chrome\`. The location may be a directory containing the
+/// browser executable or the executable itself. Locations registered
+/// before are ignored. The `browser_paths` of [`crate::Config`] end up here.
+pub fn add_browser_search_path(path: impl Into<PathBuf>) {
+    let path = path.into();
+    let mut paths = EXTRA_SEARCH_PATHS.write().unwrap_or_else(|e| e.into_inner());
+    if !paths.contains(&path) {
+        paths.push(path);
+    }
+}
+
+/// The registered search locations.
+pub fn browser_search_paths() -> Vec<PathBuf> {
+    EXTRA_SEARCH_PATHS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The architecture of the browser binary at `path`, preferring the host
+/// architecture for universal binaries. `None` if the header can't be read,
+/// e.g. because `path` is a launcher script.
+pub fn browser_arch(path: &Path) -> Option<Arch> {
+    // Launchers such as /usr/bin/google-chrome are often symlinks.
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    preferred_arch(&binary_architectures(&path).ok()?)
+}
+
 /// Gets the version of the specified browser.
-fn find_browser_path(browser_name: &str) -> Option<PathBuf> {
-    if browser_name != "chrome" && browser_name != "firefox" {
+pub(crate) fn find_browser_path(browser_name: &str) -> Option<PathBuf> {
+    if !SUPPORTED_BROWSER_NAMES.contains(&browser_name) {
         return None;
     }
 
     find_browser_path_system(browser_name)
+        .or_else(|| find_in_search_paths(browser_name, &browser_search_paths()))
+}
+
+/// [`find_browser_path`] for async callers. The lookup may query the registry
+/// or Spotlight, so it runs through [`runtime::spawn_blocking`].
+pub(crate) async fn locate_browser(browser_name: &str) -> Option<PathBuf> {
+    let browser_name = browser_name.to_string();
+    runtime::spawn_blocking(move || find_browser_path(&browser_name)).await
+}
+
+/// Executable names a browser may have in a user provided location.
+fn executable_names(browser_name: &str) -> &'static [&'static str] {
+    match browser_name.split('-').next().unwrap_or(browser_name) {
+        "chrome" => &["chrome.exe", "google-chrome", "chrome", "Google Chrome"],
+        "chromium" => &["chromium", "chromium-browser", "Chromium", "chrome.exe", "chrome"],
+        "brave" => &["brave.exe", "brave-browser", "brave", "Brave Browser"],
+        "edge" => &["msedge.exe", "microsoft-edge", "msedge", "Microsoft Edge"],
+        _ => &["firefox.exe", "firefox"],
+    }
+}
+
+/// Looks for the browser in the given locations, each either a directory or
+/// the browser executable itself.
+fn find_in_search_paths(browser_name: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
+    let names = executable_names(browser_name);
+
+    search_paths.iter().find_map(|location| {
+        if location.is_file() {
+            let file_name = location.file_name()?.to_string_lossy();
+            return names.contains(&file_name.as_ref()).then(|| location.clone());
+        }
+        names
+            .iter()
+            .map(|name| location.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+/// The install directory below `Program Files` (or `%LOCALAPPDATA%`) and the
+/// executable name of a browser on Windows.
+fn windows_install_location(browser_name: &str) -> (&'static str, &'static str) {
+    match browser_name {
+        "chrome" => ("Google\\Chrome\\Application", "chrome.exe"),
+        "chrome-beta" => ("Google\\Chrome Beta\\Application", "chrome.exe"),
+        "chrome-dev" => ("Google\\Chrome Dev\\Application", "chrome.exe"),
+        // Canary is a per-user "side by side" install.
+        "chrome-canary" => ("Google\\Chrome SxS\\Application", "chrome.exe"),
+        "chromium" => ("Chromium\\Application", "chrome.exe"),
+        "brave" => ("BraveSoftware\\Brave-Browser\\Application", "brave.exe"),
+        "edge" => ("Microsoft\\Edge\\Application", "msedge.exe"),
+        "firefox-devedition" => ("Firefox Developer Edition", "firefox.exe"),
+        "firefox-nightly" => ("Firefox Nightly", "firefox.exe"),
+        // firefox, firefox-esr
+        _ => ("Mozilla Firefox", "firefox.exe"),
+    }
+}
+
+/// Reads the `Version=` entry of the `application.ini` Firefox ships next to
+/// its executable.
+fn application_ini_version(install_dir: &Path) -> Result<String, WebDriverError> {
+    let ini_path = install_dir.join("application.ini");
+
+    if !ini_path.exists() {
+        return Err(WebDriverError::IoError {
+            path: ini_path,
+            source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
+        });
+    }
+
+    let content = std::fs::read_to_string(&ini_path).map_err(|e| WebDriverError::IoError {
+        path: ini_path,
+        source: e,
+    })?;
+
+    content
+        .lines()
+        .find(|line| line.starts_with("Version="))
+        .and_then(|line| line.split('=').nth(1))
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| WebDriverError::BrowserVersionParsingError { output: content })
+}
+
+/// Reads the Firefox version from the `application.ini` (or the `Milestone` of
+/// `platform.ini`) installed next to the binary, or in the `Resources`
+/// directory of the macOS app bundle. Launchers like `/usr/bin/firefox` are
+/// usually symlinks into the install directory and are resolved first.
+#[cfg(any(not(target_os = "windows"), test))]
+fn firefox_ini_version(path: &Path) -> Option<String> {
+    let resolved = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = resolved.parent()?;
+
+    let mut candidates = vec![dir.to_path_buf(), dir.with_file_name("Resources")];
+    if BrowserPackaging::from_path(path) == BrowserPackaging::Snap {
+        candidates.push(PathBuf::from("/snap/firefox/current/usr/lib/firefox"));
+    }
+
+    candidates.iter().find_map(|dir| {
+        application_ini_version(dir)
+            .ok()
+            .or_else(|| ini_value(&dir.join("platform.ini"), "Milestone"))
+    })
+}
+
+/// Reads `key=value` from an ini file.
+#[cfg(any(not(target_os = "windows"), test))]
+fn ini_value(path: &Path, key: &str) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .map(|value| value.trim().to_string())
+}
+
+// --- WSL ---
+
+/// Whether this process runs inside the Windows Subsystem for Linux.
+pub fn is_wsl() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|release| release.to_ascii_lowercase().contains("microsoft"))
+}
+
+/// Finds a browser installed on the Windows host from inside WSL, through the
+/// `C:` drive mounted at `/mnt/c`.
+pub fn find_windows_browser_from_wsl(browser_name: &str) -> Option<PathBuf> {
+    if !is_wsl() || !SUPPORTED_BROWSER_NAMES.contains(&browser_name) {
+        return None;
+    }
+
+    let (sub_path, exe_name) = windows_install_location(browser_name);
+    let sub_path = sub_path.replace('\\', "/");
+
+    let mut bases = vec![
+        PathBuf::from("/mnt/c/Program Files"),
+        PathBuf::from("/mnt/c/Program Files (x86)"),
+    ];
+    // Per-user installs live in %LOCALAPPDATA% of any of the Windows users.
+    if let Ok(users) = std::fs::read_dir("/mnt/c/Users") {
+        bases.extend(users.flatten().map(|user| user.path().join("AppData").join("Local")));
+    }
+
+    bases
+        .into_iter()
+        .map(|base| base.join(&sub_path).join(exe_name))
+        .find(|path| path.exists())
+}
+
+/// Gets the version of a browser installed on the Windows host from inside
+/// WSL. Chromium-based browsers are queried through `powershell.exe`, Firefox
+/// through its `application.ini`.
+pub async fn get_windows_browser_version_from_wsl(
+    browser_name: &str,
+    path: Option<&Path>,
+) -> Result<String, WebDriverError> {
+    let path = match path {
+        Some(p) => p.to_path_buf(),
+        None => {
+            let browser_name = browser_name.to_string();
+            runtime::spawn_blocking(move || find_windows_browser_from_wsl(&browser_name))
+                .await
+                .ok_or(WebDriverError::BrowserNotFound)?
+        }
+    };
+
+    if browser_name.starts_with("firefox") {
+        let install_dir = path.parent().ok_or(WebDriverError::BrowserNotFound)?.to_path_buf();
+        return runtime::spawn_blocking(move || application_ini_version(&install_dir)).await;
+    }
+
+    let command_str = format!(
+        "(Get-Item '{}').VersionInfo.ProductVersion",
+        wsl_to_windows_path(&path)
+    );
+    let mut command = Command::new("powershell.exe");
+    command.args(["-NoProfile", "-Command", &command_str]);
+    let output = runtime::output(&mut command)
+        .await
+        .map_err(|e| WebDriverError::CommandExecutionError {
+            command: command_str.clone(),
+            source: e,
+        })?;
+
+    let stdout = String::from_utf8(output.stdout).map_err(|e| {
+        WebDriverError::CommandOutputParsingError {
+            command: command_str,
+            source: e,
+        }
+    })?;
+    parse_version_token(&stdout).ok_or(WebDriverError::BrowserVersionParsingError { output: stdout })
+}
+
+/// Converts a `/mnt/<drive>/...` path to its Windows form (`C:\...`).
+/// Other paths are returned unchanged.
+fn wsl_to_windows_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let Some(rest) = path.strip_prefix("/mnt/") else {
+        return path.into_owned();
+    };
+
+    let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    format!("{}:\\{}", drive.to_ascii_uppercase(), rest.replace('/', "\\"))
 }
 
 // --- Platform-Specific Implementations ---
 
 #[cfg(target_os = "windows")]
 fn find_browser_path_system(browser_name: &str) -> Option<PathBuf> {
-    let program_files = std::env::var("ProgramFiles").ok()?;
-    let program_files_x86 = std::env::var("ProgramFiles(x86)").ok()?;
-    let local_appdata = std::env::var("LOCALAPPDATA").ok()?;
+    let env_path = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let local_appdata = env_path("LOCALAPPDATA");
 
-    let (sub_path, exe_name) = if browser_name.contains("chrome") {
-        ("Google\\Chrome\\Application", "chrome.exe")
-    } else {
+    let (sub_path, exe_name) = windows_install_location(browser_name);
+
+    // Per-user installers (e.g. WinGet in user scope) use %LOCALAPPDATA%\Programs.
+    let bases = [
+        env_path("ProgramFiles"),
+        env_path("ProgramFiles(x86)"),
+        local_appdata.clone(),
+        local_appdata.map(|dir| dir.join("Programs")),
+    ];
+    let scoop_roots = [
+        env_path("SCOOP").or_else(|| env_path("USERPROFILE").map(|home| home.join("scoop"))),
+        env_path("SCOOP_GLOBAL").or_else(|| env_path("ProgramData").map(|dir| dir.join("scoop"))),
+    ];
+
+    let found = bases
+        .into_iter()
+        .flatten()
+        .map(|base| base.join(sub_path).join(exe_name))
+        .chain(package_manager_paths(
+            browser_name,
+            exe_name,
+            &scoop_roots.into_iter().flatten().collect::<Vec<_>>(),
+            env_path("ChocolateyInstall").as_deref(),
+        ))
+        .find(|path| path.exists());
+
+    // Chromium and the release channels share their executable name with
+    // Chrome or Firefox, so the registry can't tell them apart.
+    if found.is_some() || browser_name == "chromium" || browser_name.contains('-') {
+        return found;
+    }
+
+    // Installs outside the default directories are still registered under
+    // "App Paths" or in the uninstall entries.
+    registry_app_path(exe_name).or_else(|| registry_uninstall_path(exe_name))
+}
+
+/// Scoop and Chocolatey package names of a browser.
+#[cfg(any(target_os = "windows", test))]
+fn package_manager_names(browser_name: &str) -> (&'static [&'static str], &'static [&'static str]) {
+    match browser_name {
+        "chrome" => (&["googlechrome"], &["googlechrome", "GoogleChromePortable"]),
+        "chrome-beta" => (&["googlechrome-beta"], &["googlechrome-beta"]),
+        "chrome-dev" => (&["googlechrome-dev"], &["googlechrome-dev"]),
+        "chrome-canary" => (&["googlechrome-canary"], &["googlechrome-canary"]),
+        "chromium" => (&["chromium", "ungoogled-chromium"], &["chromium"]),
+        "brave" => (&["brave"], &["brave"]),
+        "edge" => (&[], &["microsoft-edge"]),
+        "firefox-esr" => (&["firefox-esr"], &["firefoxesr"]),
+        "firefox-devedition" => (&["firefox-developer"], &["firefox-dev"]),
+        "firefox-nightly" => (&["firefox-nightly"], &["firefox-nightly"]),
         // firefox
-        ("Mozilla Firefox", "firefox.exe")
+        _ => (&["firefox"], &["firefox", "FirefoxPortable"]),
+    }
+}
+
+/// Candidate executables installed through Scoop (`<root>\apps\<app>\current`)
+/// and portable Chocolatey packages (`<ChocolateyInstall>\lib\<package>\tools`).
+#[cfg(any(target_os = "windows", test))]
+fn package_manager_paths(
+    browser_name: &str,
+    exe_name: &str,
+    scoop_roots: &[PathBuf],
+    chocolatey_root: Option<&Path>,
+) -> Vec<PathBuf> {
+    let (scoop_apps, chocolatey_packages) = package_manager_names(browser_name);
+
+    let scoop = scoop_roots.iter().flat_map(|root| {
+        scoop_apps
+            .iter()
+            .map(move |app| root.join("apps").join(app).join("current").join(exe_name))
+    });
+    let chocolatey = chocolatey_root.into_iter().flat_map(|root| {
+        chocolatey_packages
+            .iter()
+            .map(move |package| root.join("lib").join(package).join("tools").join(exe_name))
+    });
+    scoop.chain(chocolatey).collect()
+}
+
+/// Runs `reg query` with the given arguments and returns its output, or
+/// `None` when the key or value does not exist.
+#[cfg(target_os = "windows")]
+fn reg_query(args: &[&str]) -> Option<String> {
+    let output = Command::new("reg").arg("query").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Looks up an executable in the `App Paths` registry key of the machine and
+/// the current user.
+#[cfg(target_os = "windows")]
+fn registry_app_path(exe_name: &str) -> Option<PathBuf> {
+    ["HKLM", "HKCU"].into_iter().find_map(|hive| {
+        let key = format!(
+            "{}\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\{}",
+            hive, exe_name
+        );
+        let stdout = reg_query(&[key.as_str(), "/ve"])?;
+
+        // "    (Default)    REG_SZ    C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe"
+        reg_values(&stdout, "(Default)")
+            .into_iter()
+            .map(|value| PathBuf::from(value.trim_matches('"')))
+            .find(|path| path.exists())
+    })
+}
+
+/// Searches the `DisplayIcon` values of the uninstall entries for the
+/// executable. Firefox registers itself as e.g. "Mozilla Firefox (x64 en-US)",
+/// so the entries are matched on the executable rather than the key name.
+#[cfg(target_os = "windows")]
+fn registry_uninstall_path(exe_name: &str) -> Option<PathBuf> {
+    const UNINSTALL_KEYS: &[&str] = &[
+        "HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "HKLM\\SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "HKCU\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    ];
+
+    UNINSTALL_KEYS.iter().find_map(|&key| {
+        let stdout = reg_query(&[key, "/s", "/v", "DisplayIcon"])?;
+        reg_values(&stdout, "DisplayIcon")
+            .into_iter()
+            // "C:\Program Files\Google\Chrome\Application\chrome.exe,0"
+            .map(|value| value.split(',').next().unwrap_or_default().trim_matches('"').to_string())
+            .map(PathBuf::from)
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(exe_name))
+            })
+            .find(|path| path.exists())
+    })
+}
+
+/// Reads the version Chromium-based browsers record in their `BLBeacon` key
+/// on every start.
+#[cfg(target_os = "windows")]
+fn registry_blbeacon_version(browser_name: &str) -> Option<String> {
+    let vendor_key = match browser_name {
+        "chrome" => "Google\\Chrome",
+        "chrome-beta" => "Google\\Chrome Beta",
+        "chrome-dev" => "Google\\Chrome Dev",
+        "chrome-canary" => "Google\\Chrome SxS",
+        "chromium" => "Chromium",
+        "brave" => "BraveSoftware\\Brave-Browser",
+        "edge" => "Microsoft\\Edge",
+        _ => return None,
     };
+    let key = format!("HKCU\\SOFTWARE\\{}\\BLBeacon", vendor_key);
+    let stdout = reg_query(&[key.as_str(), "/v", "version"])?;
+    reg_values(&stdout, "version").into_iter().next()
+}
+
+/// Extracts the data of every `REG_SZ`/`REG_EXPAND_SZ` value named `name`
+/// from `reg query` output.
+#[cfg(any(target_os = "windows", test))]
+fn reg_values(output: &str, name: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (value_name, rest) = line.trim().split_once("    ")?;
+            if !value_name.eq_ignore_ascii_case(name) {
+                return None;
+            }
+            let (_, data) = rest.trim().split_once("REG_")?;
+            let (_, data) = data.split_once(char::is_whitespace)?;
+            Some(data.trim().to_string())
+        })
+        .filter(|data| !data.is_empty())
+        .collect()
+}
+
+/// The application bundle, executable, bundle identifier and Homebrew cask
+/// of a browser on macOS.
+#[cfg(any(target_os = "macos", test))]
+fn macos_app(browser_name: &str) -> (&'static str, &'static str, &'static str, &'static str) {
+    match browser_name {
+        "chrome" => ("Google Chrome.app", "Google Chrome", "com.google.Chrome", "google-chrome"),
+        "chrome-beta" => ("Google Chrome Beta.app", "Google Chrome Beta", "com.google.Chrome.beta", "google-chrome@beta"),
+        "chrome-dev" => ("Google Chrome Dev.app", "Google Chrome Dev", "com.google.Chrome.dev", "google-chrome@dev"),
+        "chrome-canary" => ("Google Chrome Canary.app", "Google Chrome Canary", "com.google.Chrome.canary", "google-chrome@canary"),
+        "chromium" => ("Chromium.app", "Chromium", "org.chromium.Chromium", "chromium"),
+        "brave" => ("Brave Browser.app", "Brave Browser", "com.brave.Browser", "brave-browser"),
+        "edge" => ("Microsoft Edge.app", "Microsoft Edge", "com.microsoft.edgemac", "microsoft-edge"),
+        "firefox-esr" => ("Firefox.app", "firefox", "org.mozilla.firefox", "firefox@esr"),
+        "firefox-devedition" => ("Firefox Developer Edition.app", "firefox", "org.mozilla.firefoxdeveloperedition", "firefox@developer-edition"),
+        "firefox-nightly" => ("Firefox Nightly.app", "firefox", "org.mozilla.nightly", "firefox@nightly"),
+        // firefox
+        _ => ("Firefox.app", "firefox", "org.mozilla.firefox", "firefox"),
+    }
+}
+
+/// Application bundles of a browser in the system and per-user
+/// `Applications` folders and the Homebrew Caskroom.
+#[cfg(any(target_os = "macos", test))]
+fn macos_app_candidates(app_name: &str, cask: &str, home: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates = vec![Path::new("/Applications").join(app_name)];
+    if let Some(home) = home {
+        candidates.push(home.join("Applications").join(app_name));
+    }
+
+    // Some casks keep the bundle in "<prefix>/Caskroom/<cask>/<version>/" and
+    // only link it into an Applications folder.
+    for prefix in ["/opt/homebrew", "/usr/local"] {
+        let Ok(versions) = std::fs::read_dir(Path::new(prefix).join("Caskroom").join(cask)) else {
+            continue;
+        };
+        candidates.extend(versions.flatten().map(|version| version.path().join(app_name)));
+    }
+    candidates
+}
+
+#[cfg(target_os = "macos")]
+fn find_browser_path_system(browser_name: &str) -> Option<PathBuf> {
+    let (app_name, exe_name, bundle_id, cask) = macos_app(browser_name);
+    let home = std::env::var_os("HOME").map(PathBuf::from);
 
-    [program_files, program_files_x86, local_appdata]
+    macos_app_candidates(app_name, cask, home.as_deref())
         .into_iter()
-        .map(|base| Path::new(&base).join(sub_path).join(exe_name))
+        .map(|app| app.join("Contents").join("MacOS").join(exe_name))
         .find(|path| path.exists())
+        .or_else(|| mdfind_app(bundle_id).map(|app| app.join("Contents").join("MacOS").join(exe_name)))
+        .filter(|path| path.exists())
 }
 
+/// Asks Spotlight for an application bundle by its bundle identifier.
 #[cfg(target_os = "macos")]
-fn find_browser_path_system(browser_name: &str) -> Option<PathBuf> {
-    let path_str = if browser_name.contains("chrome") {
-        "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"
-    } else {
-        // firefox
-        "/Applications/Firefox.app/Contents/MacOS/firefox"
-    };
-    let path = PathBuf::from(path_str);
-    if path.exists() {
-        Some(path)
-    } else {
-        None
+fn mdfind_app(bundle_id: &str) -> Option<PathBuf> {
+    let query = format!("kMDItemCFBundleIdentifier == '{}'", bundle_id);
+    let output = Command::new("mdfind").arg(&query).output().ok()?;
+    if !output.status.success() {
+        return None;
     }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| line.ends_with(".app"))
+        .map(PathBuf::from)
 }
 
 #[cfg(target_os = "linux")]
 fn find_browser_path_system(browser_name: &str) -> Option<PathBuf> {
-    let candidates = if browser_name.contains("chrome") {
-        vec![
-            "google-chrome",
-            "google-chrome-stable",
-            "chromium-browser",
-            "chromium",
-        ]
-    } else {
+    let candidates = match browser_name {
+        "chrome" => vec!["google-chrome", "google-chrome-stable"],
+        "chrome-beta" => vec!["google-chrome-beta"],
+        // The Linux packages call the Dev channel "unstable".
+        "chrome-dev" => vec!["google-chrome-unstable"],
+        "chrome-canary" => vec!["google-chrome-canary"],
+        "chromium" => vec!["chromium", "chromium-browser"],
+        "brave" => vec!["brave-browser", "brave-browser-stable", "brave"],
+        "edge" => vec!["microsoft-edge", "microsoft-edge-stable"],
+        "firefox-esr" => vec!["firefox-esr"],
+        "firefox-devedition" => vec!["firefox-developer-edition", "firefox-devedition"],
+        "firefox-nightly" => vec!["firefox-nightly"],
         // firefox
-        vec!["firefox"]
+        _ => vec!["firefox"],
     };
 
     candidates
         .into_iter()
         .find_map(|name| which::which(name).ok())
+        .or_else(|| find_sandboxed_browser(browser_name))
+}
+
+/// Snap and Flatpak names of the supported browsers.
+#[cfg(any(target_os = "linux", test))]
+fn sandboxed_package(browser_name: &str) -> Option<(Option<&'static str>, &'static str)> {
+    match browser_name {
+        "chrome" => Some((None, "com.google.Chrome")),
+        "chromium" => Some((Some("chromium"), "org.chromium.Chromium")),
+        "brave" => Some((Some("brave"), "com.brave.Browser")),
+        "edge" => Some((None, "com.microsoft.Edge")),
+        "firefox" => Some((Some("firefox"), "org.mozilla.firefox")),
+        _ => None,
+    }
+}
+
+/// Looks for a Snap or Flatpak install whose launcher is not on `PATH`.
+#[cfg(target_os = "linux")]
+fn find_sandboxed_browser(browser_name: &str) -> Option<PathBuf> {
+    let (snap, flatpak) = sandboxed_package(browser_name)?;
+
+    let snap_path = snap.map(|name| Path::new("/snap/bin").join(name));
+    let flatpak_paths = std::env::var_os("HOME")
+        .map(|home| Path::new(&home).join(".local/share/flatpak/exports/bin"))
+        .into_iter()
+        .chain([PathBuf::from("/var/lib/flatpak/exports/bin")])
+        .map(|dir| dir.join(flatpak));
+
+    snap_path.into_iter().chain(flatpak_paths).find(|path| path.exists())
 }
 
 #[cfg(target_os = "windows")]
@@ -94,14 +805,13 @@ async fn get_version_on_platform(
     browser_name: &str,
     path: &Path,
 ) -> Result<String, WebDriverError> {
-    if browser_name.contains("chrome") {
+    if !browser_name.starts_with("firefox") {
         let command_str = format!(
             "(Get-Command '{}').Version.ToString()",
             path.to_string_lossy()
         );
-        let output = Command::new("powershell")
-            .args(["-Command", &command_str])
-            .output()
+        let output = runtime::output(Command::new("powershell").args(["-Command", &command_str]))
+            .await
             .map_err(|e| WebDriverError::CommandExecutionError {
                 command: command_str.clone(),
                 source: e,
@@ -113,30 +823,19 @@ async fn get_version_on_platform(
                 source: e,
             }
         })?;
-        Ok(version.trim().to_string())
-    } else {
-        // For Firefox, reading application.ini is most reliable on Windows
-        let install_dir = path.parent().ok_or(WebDriverError::BrowserNotFound)?;
-        let ini_path = install_dir.join("application.ini");
+        let version = version.trim();
 
-        if !ini_path.exists() {
-            return Err(WebDriverError::IoError {
-                path: ini_path,
-                source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
-            });
+        if version.is_empty() {
+            let browser_name = browser_name.to_string();
+            return runtime::spawn_blocking(move || registry_blbeacon_version(&browser_name))
+                .await
+                .ok_or_else(|| WebDriverError::BrowserVersionParsingError { output: version.to_string() });
         }
-
-        let content = std::fs::read_to_string(&ini_path).map_err(|e| WebDriverError::IoError {
-            path: ini_path,
-            source: e,
-        })?;
-
-        content
-            .lines()
-            .find(|line| line.starts_with("Version="))
-            .and_then(|line| line.split('=').nth(1))
-            .map(|s| s.to_string())
-            .ok_or_else(|| WebDriverError::BrowserVersionParsingError { output: content })
+        Ok(version.to_string())
+    } else {
+        // For Firefox, reading application.ini is most reliable on Windows
+        let install_dir = path.parent().ok_or(WebDriverError::BrowserNotFound)?.to_path_buf();
+        runtime::spawn_blocking(move || application_ini_version(&install_dir)).await
     }
 }
 
@@ -145,15 +844,34 @@ async fn get_version_on_platform(
     browser_name: &str,
     path: &Path,
 ) -> Result<String, WebDriverError> {
-    get_version_from_cli(browser_name, path)
+    // Launching Firefox may grab the running profile, read its ini files instead.
+    if browser_name.starts_with("firefox") {
+        let path = path.to_path_buf();
+        if let Some(version) = runtime::spawn_blocking(move || firefox_ini_version(&path)).await {
+            return Ok(version);
+        }
+    }
+
+    // Launching a macOS app is slow and may show UI, its Info.plist has the
+    // same version.
+    #[cfg(target_os = "macos")]
+    {
+        let path = path.to_path_buf();
+        if let Some(version) = runtime::spawn_blocking(move || app_bundle_version(&path)).await {
+            return Ok(version);
+        }
+    }
+
+    get_version_from_cli(browser_name, path).await
 }
 
-#[cfg(not(target_os = "windows"))]
-async fn get_version_on_platform(
-    browser_name: &str,
-    path: &Path,
-) -> Result<String, WebDriverError> {
-    get_version_from_cli(browser_name, path)
+/// Reads `CFBundleShortVersionString` from the `Info.plist` of the app
+/// bundle containing `path` (`<App>.app/Contents/MacOS/<exe>`).
+#[cfg(any(target_os = "macos", test))]
+fn app_bundle_version(path: &Path) -> Option<String> {
+    let contents = path.parent().filter(|dir| dir.ends_with("Contents/MacOS"))?.parent()?;
+    let plist = std::fs::read_to_string(contents.join("Info.plist")).ok()?;
+    plist_string_value(&plist, "CFBundleShortVersionString")
 }
 
 
@@ -161,40 +879,81 @@ async fn get_version_from_cli(
     browser_name: &str,
     path: &Path,
 ) -> Result<String, WebDriverError> {
-    let version_arg = if browser_name.contains("chrome") {
-        "--version"
-    } else {
+    let version_arg = if browser_name.starts_with("firefox") {
         // Firefox uses -V or --version on non-windows
         "-V"
+    } else {
+        "--version"
     };
 
-    let output = Command::new(path)
-        .arg(version_arg)
-        .output()
+    let (program, args) = version_command(path, version_arg);
+    let command_str = format!("'{}' {}", program.to_string_lossy(), args.join(" "));
+
+    let output = runtime::output(Command::new(&program).args(&args))
+        .await
         .map_err(|e| WebDriverError::CommandExecutionError {
-            command: format!("'{}' {}", path.to_string_lossy(), version_arg),
+            command: command_str.clone(),
             source: e,
         })?;
 
     let version_str = String::from_utf8(output.stdout).map_err(|e| {
         WebDriverError::CommandOutputParsingError {
-            command: format!("'{}' {}", path.to_string_lossy(), version_arg),
+            command: command_str,
             source: e,
         }
     })?;
 
-    version_str
-        .split_whitespace()
-        .find_map(|s| {
-            if s.chars().next().map_or(false, |c| c.is_ascii_digit()) && s.contains('.') {
-                Some(s.to_string())
-            } else {
-                None
-            }
-        })
-        .ok_or_else(|| WebDriverError::BrowserVersionParsingError {
-            output: version_str,
-        })
+    parse_version_token(&version_str).ok_or_else(|| WebDriverError::BrowserVersionParsingError {
+        output: version_str,
+    })
+}
+
+/// Builds the command printing the version of the browser at `path`. Snap and
+/// Flatpak launchers are run through `snap run` and `flatpak run`, which
+/// start the confined browser with the flag.
+fn version_command(path: &Path, version_arg: &str) -> (PathBuf, Vec<String>) {
+    let package = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+    match BrowserPackaging::from_path(path) {
+        BrowserPackaging::Snap => (PathBuf::from("snap"), vec!["run".into(), package, version_arg.into()]),
+        BrowserPackaging::Flatpak => (PathBuf::from("flatpak"), vec!["run".into(), package, version_arg.into()]),
+        BrowserPackaging::Native => (path.to_path_buf(), vec![version_arg.into()]),
+    }
+}
+
+// A dotted version of two to four numbers, optionally followed by a Firefox
+// qualifier ("esr", "a1", "b3"). It must not be glued to other letters or
+// digits, so "x86_64" or build ids don't match.
+static VERSION_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w.])(\d+(?:\.\d+){1,3}(?:esr|[ab]\d+)?)(?:$|[^\w.])").expect("valid version pattern")
+});
+
+/// Extracts the first dotted version from the output of `<browser> --version`.
+///
+/// The surrounding text depends on the vendor and the locale, e.g.
+/// `Chromium 120.0.6099.224 built on Debian 12.4, running on Debian 12.4`.
+/// Only the version itself is returned; vendor suffixes such as the
+/// `-1ubuntu1` of distribution packages are dropped.
+pub(crate) fn parse_version_token(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        // Vendor suffixes attached with a dash are not part of the version.
+        let line = line.replace(['-', '_'], " ");
+        VERSION_PATTERN.captures(&line).map(|captures| captures[1].to_string())
+    })
+}
+
+/// Extracts the `<string>` value following `<key>{key}</key>` in an XML plist.
+pub(crate) fn plist_string_value(content: &str, key: &str) -> Option<String> {
+    let key_tag = format!("<key>{}</key>", key);
+    let after_key = &content[content.find(&key_tag)? + key_tag.len()..];
+    let start = after_key.find("<string>")? + "<string>".len();
+    let end = after_key[start..].find("</string>")?;
+
+    // Make sure the value belongs to this key and not to a later one.
+    if after_key[..start].contains("<key>") {
+        return None;
+    }
+    Some(after_key[start..start + end].trim().to_string())
 }
 
 // --- Tests ---
@@ -203,6 +962,184 @@ async fn get_version_from_cli(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_browser_from_str() {
+        for name in ["edge", "msedge", "microsoft-edge", " Microsoft Edge "] {
+            assert_eq!(name.parse::<Browser>().unwrap(), Browser::Edge, "{}", name);
+        }
+        assert_eq!(Browser::Edge.to_string().parse::<Browser>().unwrap(), Browser::Edge);
+        assert_eq!("Chrome".parse::<Browser>().unwrap(), Browser::Chrome);
+        assert!(matches!("netscape".parse::<Browser>(), Err(WebDriverError::UnsupportedBrowser(_))));
+    }
+
+    #[test]
+    fn test_plist_string_value() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.apple.Safari</string>
+    <key>CFBundleShortVersionString</key>
+    <string>17.5</string>
+    <key>LSMinimumSystemVersion</key>
+    <string>13.5</string>
+</dict>
+</plist>"#;
+
+        assert_eq!(plist_string_value(plist, "CFBundleShortVersionString").as_deref(), Some("17.5"));
+        assert_eq!(plist_string_value(plist, "CFBundleVersion"), None);
+    }
+
+    #[test]
+    fn test_sandboxed_version_command() {
+        let snap = Path::new("/snap/bin/chromium");
+        assert_eq!(BrowserPackaging::from_path(snap), BrowserPackaging::Snap);
+        assert_eq!(version_command(snap, "--version").1, vec!["run", "chromium", "--version"]);
+
+        let (_, flatpak) = sandboxed_package("firefox").unwrap();
+        let flatpak = Path::new("/var/lib/flatpak/exports/bin").join(flatpak);
+        assert!(BrowserPackaging::from_path(&flatpak).is_sandboxed());
+        assert_eq!(version_command(&flatpak, "-V"), (PathBuf::from("flatpak"), vec!["run".into(), "org.mozilla.firefox".into(), "-V".into()]));
+
+        assert_eq!(BrowserPackaging::from_path(Path::new("/usr/bin/firefox")), BrowserPackaging::Native);
+    }
+
+    #[test]
+    fn test_wsl_to_windows_path() {
+        let path = Path::new("/mnt/c/Program Files/Google/Chrome/Application/chrome.exe");
+        assert_eq!(wsl_to_windows_path(path), "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe");
+        assert_eq!(wsl_to_windows_path(Path::new("/usr/bin/chrome")), "/usr/bin/chrome");
+    }
+
+    #[test]
+    fn test_application_ini_version() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("application.ini"), "[App]\nVendor=Mozilla\nName=Firefox\nVersion=128.2.0\n").unwrap();
+        assert_eq!(application_ini_version(dir.path()).unwrap(), "128.2.0");
+    }
+
+    #[test]
+    fn test_find_in_search_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let firefox = dir.path().join("firefox.exe");
+        std::fs::write(&firefox, "").unwrap();
+        let search_paths = vec![dir.path().join("missing"), dir.path().to_path_buf()];
+
+        assert_eq!(find_in_search_paths("firefox", &search_paths), Some(firefox.clone()));
+        assert_eq!(find_in_search_paths("firefox-esr", std::slice::from_ref(&firefox)), Some(firefox));
+        assert_eq!(find_in_search_paths("chrome", &search_paths), None);
+    }
+
+    #[test]
+    fn test_macos_app_candidates() {
+        let (app_name, exe_name, _, cask) = macos_app("chrome-canary");
+        assert_eq!((app_name, exe_name), ("Google Chrome Canary.app", "Google Chrome Canary"));
+
+        let candidates = macos_app_candidates(app_name, cask, Some(Path::new("/Users/test")));
+        assert_eq!(candidates[0], Path::new("/Applications/Google Chrome Canary.app"));
+        assert_eq!(candidates[1], Path::new("/Users/test/Applications/Google Chrome Canary.app"));
+    }
+
+    #[test]
+    fn test_package_manager_paths() {
+        let paths = package_manager_paths(
+            "chrome",
+            "chrome.exe",
+            &[PathBuf::from("scoop")],
+            Some(Path::new("choco")),
+        );
+        assert_eq!(paths[0], Path::new("scoop/apps/googlechrome/current/chrome.exe"));
+        assert!(paths.contains(&Path::new("choco/lib/googlechrome/tools/chrome.exe").to_path_buf()));
+        assert!(package_manager_paths("edge", "msedge.exe", &[PathBuf::from("scoop")], None).is_empty());
+    }
+
+    #[test]
+    fn test_firefox_ini_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let firefox = dir.path().join("firefox");
+        std::fs::write(&firefox, "").unwrap();
+        assert_eq!(firefox_ini_version(&firefox), None);
+
+        std::fs::write(dir.path().join("platform.ini"), "[Build]\nMilestone=115.13.0\n").unwrap();
+        assert_eq!(firefox_ini_version(&firefox).as_deref(), Some("115.13.0"));
+
+        std::fs::write(dir.path().join("application.ini"), "[App]\nVersion=115.13.0esr\n").unwrap();
+        assert_eq!(firefox_ini_version(&firefox).as_deref(), Some("115.13.0esr"));
+    }
+
+    #[test]
+    fn test_parse_version_token() {
+        let cases = [
+            ("Google Chrome 126.0.6478.126 \n", "126.0.6478.126"),
+            ("Chromium 120.0.6099.224 built on Debian 12.4, running on Debian 12.4\n", "120.0.6099.224"),
+            ("Chromium 125.0.6422.141 snap\n", "125.0.6422.141"),
+            ("Brave Browser 127.1.68.131\n", "127.1.68.131"),
+            ("Microsoft Edge 126.0.2592.87 \n", "126.0.2592.87"),
+            ("Mozilla Firefox 128.0.3\n", "128.0.3"),
+            ("Mozilla Firefox 115.13.0esr\n", "115.13.0esr"),
+            ("Mozilla Firefox 130.0b9\n", "130.0b9"),
+            ("Vivaldi 6.8.3381.48 stable\n", "6.8.3381.48"),
+            ("Chromium 122.0.6261.94-1ubuntu1\n", "122.0.6261.94"),
+            ("Google Chrome for Testing 138.0.7204.157\r\n", "138.0.7204.157"),
+            ("Chromium (x86_64) version 131.0.6778.85", "131.0.6778.85"),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(parse_version_token(output).as_deref(), Some(expected), "{:?}", output);
+        }
+        assert_eq!(parse_version_token("Google Chrome\n"), None);
+    }
+
+    #[test]
+    fn test_reg_values() {
+        let output = "\r\nHKEY_CURRENT_USER\\SOFTWARE\\Google\\Chrome\\BLBeacon\r\n    version    REG_SZ    126.0.6478.127\r\n\r\n";
+        assert_eq!(reg_values(output, "version"), vec!["126.0.6478.127"]);
+
+        let output = "HKEY_LOCAL_MACHINE\\...\\Uninstall\\Mozilla Firefox (x64 en-US)\r\n    DisplayIcon    REG_SZ    C:\\Program Files\\Mozilla Firefox\\firefox.exe,0\r\n";
+        assert_eq!(reg_values(output, "DisplayIcon"), vec!["C:\\Program Files\\Mozilla Firefox\\firefox.exe,0"]);
+        assert!(reg_values(output, "DisplayVersion").is_empty());
+    }
+
+    #[test]
+    fn test_app_bundle_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = dir.path().join("Google Chrome.app").join("Contents");
+        std::fs::create_dir_all(contents.join("MacOS")).unwrap();
+        let exe = contents.join("MacOS").join("Google Chrome");
+
+        assert_eq!(app_bundle_version(&exe), None);
+
+        std::fs::write(
+            contents.join("Info.plist"),
+            "<plist><dict><key>CFBundleShortVersionString</key><string>126.0.6478.127</string></dict></plist>",
+        )
+        .unwrap();
+        assert_eq!(app_bundle_version(&exe).as_deref(), Some("126.0.6478.127"));
+        assert_eq!(app_bundle_version(&dir.path().join("chrome")), None);
+    }
+
+    #[tokio::test]
+    async fn test_detect_installed_browsers() {
+        let browsers = detect_installed_browsers().await;
+        println!("Detected browsers: {:#?}", browsers);
+        for browser in browsers {
+            assert!(browser.path.exists());
+            assert!(!browser.arch.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_channel_browser_name() {
+        assert_eq!(channel_browser_name("chrome", BrowserChannel::Stable), "chrome");
+        assert_eq!(channel_browser_name("chrome", BrowserChannel::Canary), "chrome-canary");
+        assert!(find_browser_path("chrome-nightly").is_none());
+    }
+
+    #[test]
+    fn test_chromium_version_of_brave() {
+        assert_eq!(chromium_version(Browser::Brave, "127.1.68.131"), "127");
+        assert_eq!(chromium_version(Browser::Chrome, "127.0.6533.88"), "127.0.6533.88");
+    }
+
     // This test will run and attempt to find your installed Chrome version.
     // It will be skipped if the function returns a BrowserNotFound error.
     #[tokio::test]
@@ -222,6 +1159,42 @@ mod tests {
         }
     }
 
+    // This test will run and attempt to find your installed Chromium version.
+    // It will be skipped if the function returns a BrowserNotFound error.
+    #[tokio::test]
+    async fn test_get_chromium_version() {
+        match get_browser_version("chromium", None).await {
+            Ok(version_string) => {
+                println!("Successfully detected Chromium version: {}", version_string);
+                assert!(version_string.contains('.'));
+            }
+            Err(WebDriverError::BrowserNotFound) => {
+                println!("Chromium not found, skipping test.");
+            }
+            Err(e) => {
+                panic!("An unexpected error occurred: {:?}", e);
+            }
+        }
+    }
+
+    // This test will run and attempt to find your installed Edge version.
+    // It will be skipped if the function returns a BrowserNotFound error.
+    #[tokio::test]
+    async fn test_get_edge_version() {
+        match get_browser_version("edge", None).await {
+            Ok(version_string) => {
+                println!("Successfully detected Edge version: {}", version_string);
+                assert!(version_string.contains('.'));
+            }
+            Err(WebDriverError::BrowserNotFound) => {
+                println!("Edge not found, skipping test.");
+            }
+            Err(e) => {
+                panic!("An unexpected error occurred: {:?}", e);
+            }
+        }
+    }
+
     // This test will run and attempt to find your installed Firefox version.
     // It will be skipped if the function returns a BrowserNotFound error.
     #[tokio::test]
@@ -0,0 +1,343 @@
+//! Builder-style configuration of the driver managers.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), webdriver_manager::WebDriverError> {
+//! use std::time::Duration;
+//! use webdriver_manager::drivers::chromedriver::ChromeDriver;
+//!
+//! let chrome = ChromeDriver::builder()
+//!     .install_dir("./drivers")
+//!     .version("stable")
+//!     .timeout(Duration::from_secs(30))
+//!     .build();
+//! let report = chrome.install().await?;
+//! println!("{} in {:?}", report.driver_path.display(), report.durations.total);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::browser::Browser;
+use crate::cache::Cache;
+use crate::downloader::{DownloadOptions, DownloadProgress};
+use crate::drivers::chromedriver::ChromeDriver;
+use crate::drivers::edgedriver::EdgeDriver;
+use crate::drivers::geckodriver::GeckoDriver;
+use crate::drivers::iedriver::IEDriver;
+use crate::drivers::Driver;
+use crate::error::WebDriverError;
+use crate::events::InstallEventSender;
+use crate::http::{NetworkPolicy, ProxyConfig};
+use crate::observer::InstallObserver;
+use crate::report::{InstallRecorder, InstallReport};
+use crate::setup::{install_with_report, plan_install, InstallPlan, SetupOptions};
+use crate::WebDriverManager;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Collects the configuration of a manager `M`. Created with e.g.
+/// [`ChromeDriver::builder`], or [`ManagerBuilder::new`] for a manager that
+/// is already customized.
+#[derive(Debug, Clone)]
+pub struct ManagerBuilder<M> {
+    manager: M,
+    setup: SetupOptions,
+}
+
+impl<M> ManagerBuilder<M> {
+    /// Starts from `manager`. Its download options are replaced by the ones
+    /// of the builder on [`ManagerBuilder::build`].
+    pub fn new(manager: M) -> Self {
+        Self {
+            manager,
+            setup: SetupOptions::default(),
+        }
+    }
+
+    /// Uses the browser at `path` instead of detecting it.
+    pub fn browser_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.setup.browser_path = Some(path.into());
+        self
+    }
+
+    /// Installs drivers into a cache at `dir` instead of the user cache.
+    pub fn install_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.setup.cache = Some(Cache::new(dir));
+        self
+    }
+
+    /// Installs drivers into `.webdrivers/` next to the project's
+    /// `Cargo.toml`, see [`Cache::project`].
+    pub fn project_local(mut self) -> Self {
+        self.setup.cache = Some(Cache::project());
+        self
+    }
+
+    /// Installs drivers into `cache`, e.g. one with a prune policy.
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.setup.cache = Some(cache);
+        self
+    }
+
+    /// Installs the driver for a browser version or [`crate::VersionSpec`]
+    /// such as `"126"` or `"stable"`, instead of the installed browser.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.setup.version = Some(version.into());
+        self
+    }
+
+    /// Pins the driver in the lockfile at `path`, see [`crate::lockfile`].
+    pub fn lockfile(mut self, path: impl Into<PathBuf>) -> Self {
+        self.setup.lockfile = Some(path.into());
+        self
+    }
+
+    /// Updates the pin in the lockfile instead of installing the pinned driver.
+    pub fn update_lockfile(mut self, update: bool) -> Self {
+        self.setup.update_lockfile = update;
+        self
+    }
+
+    /// Uses a compatible driver from the environment or `PATH` before
+    /// resolving or downloading one, see [`SetupOptions::reuse_system_driver`].
+    pub fn reuse_system_driver(mut self, reuse: bool) -> Self {
+        self.setup.reuse_system_driver = reuse;
+        self
+    }
+
+    /// Sends all requests through `proxy`.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.setup.download_options.proxy = Some(proxy);
+        self
+    }
+
+    /// Disables network requests; only cached drivers and metadata are used.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.setup.download_options.network = if offline { NetworkPolicy::Offline } else { NetworkPolicy::Online };
+        self
+    }
+
+    /// Sets how long connecting or a single read may stall.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.setup.download_options.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how often a download failing with a transient error is retried.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.setup.download_options.retries = Some(retries);
+        self
+    }
+
+    /// Sets how long to wait for another process installing the same driver.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.setup.download_options.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a callback receiving progress reports.
+    pub fn on_progress(mut self, callback: impl Fn(DownloadProgress) + Send + Sync + 'static) -> Self {
+        self.setup.download_options = self.setup.download_options.on_progress(callback);
+        self
+    }
+
+    /// Sets the channel the lifecycle events of the installation are sent to.
+    pub fn events(mut self, sender: InstallEventSender) -> Self {
+        self.setup.download_options.events = Some(sender);
+        self
+    }
+
+    /// Sets the observer receiving the metrics of the installation.
+    pub fn observer(mut self, observer: Arc<dyn InstallObserver>) -> Self {
+        self.setup.download_options.observer = Some(observer);
+        self
+    }
+
+    /// Replaces all download options, for settings without a shortcut here.
+    pub fn download_options(mut self, options: DownloadOptions) -> Self {
+        self.setup.download_options = options;
+        self
+    }
+}
+
+/// A manager together with the browser, version and cache to install for.
+#[derive(Debug, Clone)]
+pub struct ConfiguredManager<M> {
+    manager: M,
+    setup: SetupOptions,
+    recorder: InstallRecorder,
+}
+
+impl<M: WebDriverManager> ConfiguredManager<M> {
+    /// The configured manager, for the lower-level [`WebDriverManager`] calls.
+    pub fn manager(&self) -> &M {
+        &self.manager
+    }
+
+    pub fn into_manager(self) -> M {
+        self.manager
+    }
+
+    /// Installs the driver unless a working one is already in the cache, and
+    /// reports what was installed. See [`crate::setup`]. Concurrent installs
+    /// through the same value mix up the download statistics.
+    pub async fn install(&self) -> Result<InstallReport, WebDriverError> {
+        install_with_report(&self.manager, &self.setup, &self.recorder).await
+    }
+
+    /// Resolves what [`ConfiguredManager::install`] would install, without
+    /// downloading anything. See [`crate::plan`].
+    pub async fn plan(&self) -> Result<InstallPlan, WebDriverError> {
+        plan_install(&self.manager, &self.setup).await
+    }
+}
+
+/// Passes [`ManagerBuilder::browser_path`] on to the managers that pick the
+/// driver's platform from the browser.
+trait WithSetupBrowserPath: Sized {
+    fn with_setup_browser_path(self, _setup: &SetupOptions) -> Self {
+        self
+    }
+}
+
+impl WithSetupBrowserPath for ChromeDriver {
+    fn with_setup_browser_path(self, setup: &SetupOptions) -> Self {
+        match &setup.browser_path {
+            Some(path) => self.with_browser_path(path),
+            None => self,
+        }
+    }
+}
+
+impl WithSetupBrowserPath for Driver {
+    fn with_setup_browser_path(self, setup: &SetupOptions) -> Self {
+        match &setup.browser_path {
+            Some(path) => self.with_browser_path(path),
+            None => self,
+        }
+    }
+}
+
+impl WithSetupBrowserPath for EdgeDriver {}
+impl WithSetupBrowserPath for GeckoDriver {}
+impl WithSetupBrowserPath for IEDriver {}
+
+/// Adds `builder()` and [`ManagerBuilder::build`] for managers configured
+/// through `with_download_options`.
+macro_rules! impl_builder {
+    ($($manager:ident),*) => {
+        $(
+            impl $manager {
+                /// Starts a [`ManagerBuilder`] for this manager.
+                pub fn builder() -> ManagerBuilder<$manager> {
+                    ManagerBuilder::new($manager::new())
+                }
+            }
+
+            impl ManagerBuilder<$manager> {
+                /// Applies the download options and returns the configured manager.
+                pub fn build(self) -> ConfiguredManager<$manager> {
+                    let recorder = InstallRecorder::default();
+                    ConfiguredManager {
+                        manager: self
+                            .manager
+                            .with_download_options(recorder.attach(self.setup.download_options.clone()))
+                            .with_setup_browser_path(&self.setup),
+                        setup: self.setup,
+                        recorder,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_builder!(ChromeDriver, EdgeDriver, GeckoDriver, IEDriver);
+
+impl Driver {
+    /// Starts a [`ManagerBuilder`] for the manager of `browser`.
+    pub fn builder(browser: Browser) -> ManagerBuilder<Driver> {
+        ManagerBuilder::new(Driver::from(browser))
+    }
+}
+
+impl ManagerBuilder<Driver> {
+    /// Applies the download options and returns the configured manager.
+    pub fn build(self) -> ConfiguredManager<Driver> {
+        let recorder = InstallRecorder::default();
+        ConfiguredManager {
+            manager: self
+                .manager
+                .with_download_options(recorder.attach(self.setup.download_options.clone()))
+                .with_setup_browser_path(&self.setup),
+            setup: self.setup,
+            recorder,
+        }
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builder_configures_the_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let firefox = GeckoDriver::builder()
+            .install_dir(dir.path())
+            .version("128.0")
+            .offline(true)
+            .timeout(Duration::from_secs(5))
+            .build();
+        assert_eq!(firefox.manager().get_driver_name(), "geckodriver");
+
+        // Offline, with an empty cache, the install can't succeed.
+        assert!(matches!(
+            firefox.install().await,
+            Err(WebDriverError::OfflineUnavailable { .. })
+        ));
+
+        let chrome = Driver::builder(Browser::Chromium).version("126").build();
+        assert_eq!(chrome.into_manager().get_driver_name(), "chromedriver");
+    }
+
+    #[tokio::test]
+    async fn test_install_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let configured = ConfiguredManager {
+            manager: crate::tests::FakeManager::default(),
+            setup: SetupOptions::new().version("1.0").cache(Cache::new(dir.path())),
+            recorder: InstallRecorder::default(),
+        };
+
+        let report = configured.install().await.unwrap();
+        assert_eq!(report.browser_version, "1.0");
+        assert_eq!(report.driver_version, "1.0.1");
+        assert_eq!(report.sha256, crate::downloader::sha256_file(&report.driver_path).unwrap());
+        assert!(report.durations.total >= report.durations.resolve);
+
+        let again = configured.install().await.unwrap();
+        assert!(again.cache_hit);
+        assert_eq!(again.bytes_downloaded, 0);
+        assert_eq!(again.driver_path, report.driver_path);
+        assert_eq!(again.digest, None);
+
+        // In deterministic mode, fresh installs and cache hits report the
+        // same digest of the installed tree.
+        let dir = tempfile::tempdir().unwrap();
+        let configured = ConfiguredManager {
+            setup: SetupOptions::new()
+                .version("1.0")
+                .cache(Cache::new(dir.path()))
+                .download_options(crate::downloader::DownloadOptions::new().deterministic(true)),
+            ..configured
+        };
+        let report = configured.install().await.unwrap();
+        let again = configured.install().await.unwrap();
+        assert!(again.cache_hit);
+        assert_eq!(report.digest.as_ref().map(String::len), Some(64));
+        assert_eq!(again.digest, report.digest);
+    }
+}
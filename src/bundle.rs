@@ -0,0 +1,523 @@
+//! Portable bundles for air-gapped machines.
+//!
+//! [`create_bundle`] packages drivers, browsers, and metadata snapshots into
+//! a single zip archive together with a `manifest.json` holding a SHA-256
+//! checksum for every file. [`install_bundle`] provisions a machine entirely
+//! from such an archive, verifying every checksum on the way: drivers are
+//! installed into a [`Cache`] and recorded in its manifest like downloaded
+//! ones, metadata snapshots land in its [`crate::cache::MetadataCache`].
+
+use crate::cache::{check_path_segment, Cache, ManifestEntry};
+use crate::downloader::{find_driver_executable, relative_slash_path, sha256_file, unzip_file, PARTIAL_DIR};
+use crate::error::WebDriverError;
+use crate::lock::{InstallLock, DEFAULT_LOCK_TIMEOUT};
+use crate::runtime::{self, fs};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Name of the manifest file at the root of a bundle.
+pub const BUNDLE_MANIFEST: &str = "manifest.json";
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// The kind of content a bundle item holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleItemKind {
+    Driver,
+    Browser,
+    Metadata,
+}
+
+/// A file or directory to include in a bundle.
+#[derive(Debug, Clone)]
+pub struct BundleItem {
+    pub kind: BundleItemKind,
+    pub name: String,
+    pub version: Option<String>,
+    pub platform: Option<String>,
+    /// The file or directory to package.
+    pub source: PathBuf,
+}
+
+impl BundleItem {
+    /// An installed driver (the executable or its install directory).
+    pub fn driver(name: &str, version: &str, platform: &str, source: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: BundleItemKind::Driver,
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            platform: Some(platform.to_string()),
+            source: source.into(),
+        }
+    }
+
+    /// A browser installation directory.
+    pub fn browser(name: &str, version: &str, platform: &str, source: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: BundleItemKind::Browser,
+            ..Self::driver(name, version, platform, source)
+        }
+    }
+
+    /// A metadata snapshot, such as a copy of the known-good-versions JSON.
+    /// It is installed as `<metadata dir>/<name>`, so a directory of the
+    /// [`crate::cache::MetadataCache`] (named after the host) is served from
+    /// the cache again after the install.
+    pub fn metadata(name: &str, source: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: BundleItemKind::Metadata,
+            name: name.to_string(),
+            version: None,
+            platform: None,
+            source: source.into(),
+        }
+    }
+
+    /// The directory of this item inside the bundle.
+    fn bundle_dir(&self) -> String {
+        let kind = match self.kind {
+            BundleItemKind::Driver => "drivers",
+            BundleItemKind::Browser => "browsers",
+            BundleItemKind::Metadata => "metadata",
+        };
+        [Some(kind), Some(self.name.as_str()), self.version.as_deref(), self.platform.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// A bundle item as recorded in the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifestItem {
+    pub kind: BundleItemKind,
+    pub name: String,
+    pub version: Option<String>,
+    pub platform: Option<String>,
+    /// The directory of the item inside the bundle.
+    pub path: String,
+    /// SHA-256 checksums keyed by file path inside the bundle.
+    pub files: BTreeMap<String, String>,
+}
+
+/// The contents of a bundle's `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    /// Creation time in seconds since the Unix epoch.
+    pub created_at: u64,
+    pub items: Vec<BundleManifestItem>,
+}
+
+impl BundleManifestItem {
+    /// Where the item ends up when the bundle is installed into `cache`.
+    pub fn install_path(&self, cache: &Cache) -> PathBuf {
+        let version = self.version.as_deref().unwrap_or_default();
+        let platform = self.platform.as_deref().unwrap_or_default();
+        match self.kind {
+            BundleItemKind::Driver => cache.install_dir(&self.name, version, platform),
+            BundleItemKind::Browser => cache.browser_dir(&self.name, version, platform),
+            BundleItemKind::Metadata => cache.metadata_dir().join(&self.name),
+        }
+    }
+
+    /// Checks that the paths of an item read from a bundle stay inside the
+    /// cache and the staging directory: every name, version, platform and
+    /// path segment must be a plain directory or file name, and every file
+    /// must lie below the item's directory.
+    fn validate(&self) -> Result<(), WebDriverError> {
+        let segments = [Some(self.name.as_str()), self.version.as_deref(), self.platform.as_deref()];
+        for segment in segments.into_iter().flatten().chain(self.path.split('/')) {
+            check_path_segment(segment)?;
+        }
+        for name in self.files.keys() {
+            match name.strip_prefix(self.path.as_str()).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest.split('/').try_for_each(check_path_segment)?,
+                None => {
+                    return Err(WebDriverError::Custom(format!(
+                        "Bundle file '{}' is outside of its item '{}'",
+                        name, self.path
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BundleManifest {
+    /// Returns where an item ends up when the bundle is installed into `cache`.
+    pub fn item_path(&self, cache: &Cache, kind: BundleItemKind, name: &str) -> Option<PathBuf> {
+        self.items
+            .iter()
+            .find(|item| item.kind == kind && item.name == name)
+            .map(|item| item.install_path(cache))
+    }
+}
+
+/// Packages `items` into a single zip archive at `output`.
+pub async fn create_bundle(items: &[BundleItem], output: &Path) -> Result<BundleManifest, WebDriverError> {
+    let items = items.to_vec();
+    let output = output.to_path_buf();
+
+    runtime::spawn_blocking(move || {
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| WebDriverError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+        let file = std::fs::File::create(&output).map_err(|e| WebDriverError::IoError {
+            path: output.clone(),
+            source: e,
+        })?;
+        let mut zip = zip::ZipWriter::new(file);
+        let zip_err = |e| WebDriverError::ZipError {
+            path: output.clone(),
+            source: e,
+        };
+
+        let mut manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            items: Vec::with_capacity(items.len()),
+        };
+
+        for item in &items {
+            let bundle_dir = item.bundle_dir();
+            let mut files = BTreeMap::new();
+
+            // A single file is stored under its own name, a directory is stored recursively.
+            let base = if item.source.is_dir() {
+                item.source.clone()
+            } else {
+                item.source.parent().map(Path::to_path_buf).unwrap_or_default()
+            };
+
+            for entry in WalkDir::new(&item.source).sort_by_file_name() {
+                let entry = entry.map_err(|e| WebDriverError::IoError {
+                    path: item.source.clone(),
+                    source: e.into(),
+                })?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                let name = format!("{}/{}", bundle_dir, relative_slash_path(entry.path(), &base));
+                let mut options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if let Ok(metadata) = entry.metadata() {
+                        options = options.unix_permissions(metadata.permissions().mode());
+                    }
+                }
+
+                zip.start_file(name.as_str(), options).map_err(zip_err)?;
+                let mut source = std::fs::File::open(entry.path()).map_err(|e| WebDriverError::IoError {
+                    path: entry.path().to_path_buf(),
+                    source: e,
+                })?;
+                std::io::copy(&mut source, &mut zip).map_err(|e| WebDriverError::IoError {
+                    path: output.clone(),
+                    source: e,
+                })?;
+
+                files.insert(name, sha256_file(entry.path())?);
+            }
+
+            manifest.items.push(BundleManifestItem {
+                kind: item.kind,
+                name: item.name.clone(),
+                version: item.version.clone(),
+                platform: item.platform.clone(),
+                path: bundle_dir,
+                files,
+            });
+        }
+
+        let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| WebDriverError::JsonParseError {
+            url: BUNDLE_MANIFEST.to_string(),
+            source: e,
+            snippet: String::new(),
+        })?;
+        zip.start_file(BUNDLE_MANIFEST, zip::write::SimpleFileOptions::default())
+            .map_err(zip_err)?;
+        zip.write_all(&manifest_json).map_err(|e| WebDriverError::IoError {
+            path: output.clone(),
+            source: e,
+        })?;
+        zip.finish().map_err(zip_err)?;
+
+        Ok(manifest)
+    })
+    .await
+}
+
+/// Installs a bundle into `cache`. The bundle is extracted into a staging
+/// directory first and every file is verified against the checksums of its
+/// manifest, so nothing is installed from a damaged bundle. Items whose
+/// paths would leave the cache, and files the manifest doesn't list, are
+/// rejected. Drivers replace
+/// an existing install of the same build and are recorded in the cache
+/// manifest.
+pub async fn install_bundle(bundle: &Path, cache: &Cache) -> Result<BundleManifest, WebDriverError> {
+    let staging = cache
+        .root()
+        .join(PARTIAL_DIR)
+        .join(format!("bundle-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging).await;
+    let result = install_staged(bundle, cache, &staging).await;
+    let _ = fs::remove_dir_all(&staging).await;
+    result
+}
+
+async fn install_staged(bundle: &Path, cache: &Cache, staging: &Path) -> Result<BundleManifest, WebDriverError> {
+    unzip_file(bundle, staging).await?;
+
+    let manifest_path = staging.join(BUNDLE_MANIFEST);
+    let content = fs::read_to_string(&manifest_path)
+        .await
+        .map_err(|e| WebDriverError::IoError {
+            path: manifest_path.clone(),
+            source: e,
+        })?;
+    let manifest: BundleManifest = serde_json::from_str(&content)
+        .map_err(|e| WebDriverError::json_parse(manifest_path.display().to_string(), content.as_bytes(), e))?;
+
+    for item in &manifest.items {
+        item.validate()?;
+    }
+
+    let staged = staging.to_path_buf();
+    let manifest = runtime::spawn_blocking(move || {
+        // Everything that gets installed must have been verified.
+        for item in &manifest.items {
+            for entry in WalkDir::new(bundle_path(&staged, &item.path)) {
+                let entry = entry.map_err(|e| WebDriverError::IoError {
+                    path: bundle_path(&staged, &item.path),
+                    source: e.into(),
+                })?;
+                let name = relative_slash_path(entry.path(), &staged);
+                if entry.file_type().is_file() && !item.files.contains_key(&name) {
+                    return Err(WebDriverError::Custom(format!(
+                        "Bundle file '{}' is not listed in the manifest",
+                        name
+                    )));
+                }
+            }
+        }
+        for (name, expected) in manifest.items.iter().flat_map(|item| &item.files) {
+            let path = bundle_path(&staged, name);
+            let actual = sha256_file(&path)?;
+            if &actual != expected {
+                return Err(WebDriverError::ChecksumMismatch {
+                    path,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(manifest)
+    })
+    .await?;
+
+    for item in &manifest.items {
+        let source = bundle_path(staging, &item.path);
+        let target = item.install_path(cache);
+        match (item.kind, &item.version, &item.platform) {
+            (BundleItemKind::Driver, Some(version), Some(platform)) => {
+                let _lock = InstallLock::acquire(&target, DEFAULT_LOCK_TIMEOUT).await?;
+                replace_dir(&source, &target).await?;
+                let driver_path = find_driver_executable(&target, &item.name)?;
+                cache.record_install(ManifestEntry {
+                    driver: item.name.clone(),
+                    version: version.clone(),
+                    platform: platform.clone(),
+                    source_url: None,
+                    sha256: sha256_file(&driver_path)?,
+                    installed_at: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs()),
+                })?;
+            }
+            (BundleItemKind::Driver, _, _) => {
+                return Err(WebDriverError::Custom(format!(
+                    "Bundled driver '{}' has no version or platform",
+                    item.name
+                )));
+            }
+            _ => replace_dir(&source, &target).await?,
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// A `/`-separated path inside the bundle, below `root`.
+fn bundle_path(root: &Path, path: &str) -> PathBuf {
+    path.split('/').fold(root.to_path_buf(), |p, s| p.join(s))
+}
+
+/// Moves the directory `source` to `target`, replacing what was there.
+async fn replace_dir(source: &Path, target: &Path) -> Result<(), WebDriverError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| WebDriverError::IoError { path, source }
+    };
+    if fs::exists(target).await.unwrap_or(false) {
+        fs::remove_dir_all(target).await.map_err(io_error(target))?;
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).await.map_err(io_error(parent))?;
+    }
+    fs::rename(source, target).await.map_err(io_error(target))
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let driver = dir.path().join("chromedriver");
+        let metadata = dir.path().join("known-good-versions-with-downloads.json");
+        std::fs::write(&driver, b"driver").unwrap();
+        std::fs::write(&metadata, b"{}").unwrap();
+
+        let bundle = dir.path().join("bundle.zip");
+        let items = [
+            BundleItem::driver("chromedriver", "138.0.7204.157", "linux64", &driver),
+            BundleItem::metadata("chrome-for-testing", &metadata),
+        ];
+        let created = create_bundle(&items, &bundle).await.unwrap();
+        assert_eq!(created.items.len(), 2);
+
+        let cache = Cache::new(dir.path().join("installed"));
+        let manifest = install_bundle(&bundle, &cache).await.unwrap();
+        let driver_dir = manifest
+            .item_path(&cache, BundleItemKind::Driver, "chromedriver")
+            .unwrap();
+        assert_eq!(driver_dir, cache.install_dir("chromedriver", "138.0.7204.157", "linux64"));
+        assert_eq!(std::fs::read(driver_dir.join("chromedriver")).unwrap(), b"driver");
+        assert_eq!(
+            std::fs::read(cache.metadata_dir().join("chrome-for-testing").join("known-good-versions-with-downloads.json")).unwrap(),
+            b"{}"
+        );
+
+        // The install is recorded and found like a downloaded one.
+        let entry = cache.manifest().unwrap().entry("chromedriver", "138.0.7204.157", "linux64").cloned().unwrap();
+        assert_eq!(entry.sha256, sha256_file(&driver).unwrap());
+        assert!(cache.is_intact("chromedriver", "138.0.7204.157", "linux64", &driver_dir.join("chromedriver")));
+        assert_eq!(cache.list_installed().unwrap().len(), 1);
+        assert!(!cache.root().join(PARTIAL_DIR).join(format!("bundle-{}", std::process::id())).exists());
+
+        // Installing again replaces the build.
+        install_bundle(&bundle, &cache).await.unwrap();
+        assert_eq!(cache.manifest().unwrap().installs.len(), 1);
+    }
+
+    /// Writes a bundle holding `files` with `items` as its manifest.
+    fn write_bundle(path: &Path, files: &[(&str, &[u8])], items: serde_json::Value) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        let manifest = serde_json::json!({
+            "format_version": BUNDLE_FORMAT_VERSION,
+            "created_at": 0,
+            "items": items,
+        });
+        zip.start_file(BUNDLE_MANIFEST, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_damaged_bundle_installs_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("bundle.zip");
+        write_bundle(
+            &bundle,
+            &[("drivers/chromedriver/1.0.0/linux64/chromedriver", b"damaged")],
+            serde_json::json!([{
+                "kind": "driver",
+                "name": "chromedriver",
+                "version": "1.0.0",
+                "platform": "linux64",
+                "path": "drivers/chromedriver/1.0.0/linux64",
+                "files": {"drivers/chromedriver/1.0.0/linux64/chromedriver": "0".repeat(64)},
+            }]),
+        );
+
+        let cache = Cache::new(dir.path().join("installed"));
+        assert!(matches!(
+            install_bundle(&bundle, &cache).await,
+            Err(WebDriverError::ChecksumMismatch { .. })
+        ));
+        assert!(!cache.install_dir("chromedriver", "1.0.0", "linux64").exists());
+        assert!(cache.manifest().unwrap().installs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bundle_paths_stay_in_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(outside.join("keep"), b"keep").unwrap();
+        let cache = Cache::new(dir.path().join("installed"));
+        let bundle = dir.path().join("bundle.zip");
+        let digest = sha256_file(&outside.join("keep")).unwrap();
+
+        let escapes = [
+            ("../../outside", "metadata/x"),
+            (outside.to_str().unwrap(), "metadata/x"),
+            ("x", "metadata/../../outside"),
+            ("x", "metadata//x"),
+        ];
+        for (name, path) in escapes {
+            write_bundle(
+                &bundle,
+                &[("metadata/x/keep", b"keep")],
+                serde_json::json!([{
+                    "kind": "metadata",
+                    "name": name,
+                    "path": path,
+                    "files": {format!("{}/keep", path): digest},
+                }]),
+            );
+            assert!(install_bundle(&bundle, &cache).await.is_err(), "{} {}", name, path);
+            assert_eq!(std::fs::read(outside.join("keep")).unwrap(), b"keep");
+        }
+
+        // Files missing from the manifest are not installed unverified.
+        write_bundle(
+            &bundle,
+            &[("metadata/x/keep", b"keep"), ("metadata/x/extra", b"unverified")],
+            serde_json::json!([{
+                "kind": "metadata",
+                "name": "x",
+                "path": "metadata/x",
+                "files": {"metadata/x/keep": digest},
+            }]),
+        );
+        let err = install_bundle(&bundle, &cache).await.unwrap_err();
+        assert!(err.to_string().contains("metadata/x/extra"));
+        assert!(!cache.metadata_dir().join("x").exists());
+    }
+}
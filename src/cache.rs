@@ -0,0 +1,1126 @@
+//! Artifact caching for downloaded driver archives.
+//!
+//! Archives are cached behind the [`CacheStore`] trait. [`LocalCacheStore`]
+//! keeps them on the local filesystem; other implementations can back the
+//! cache with shared storage (S3, GCS, Azure Blob, ...) so a fleet of CI
+//! runners downloads every archive only once.
+//!
+//! Installed drivers live in a [`Cache`], by default in the per-user cache
+//! directory, laid out as `<driver>/<version>/<platform>/`. Projects on the
+//! same machine share these installs. [`Cache::project`] keeps them in a
+//! `.webdrivers/` directory next to the project's `Cargo.toml` instead, to be
+//! gitignored like `node_modules`. Every install is recorded with its source
+//! URL and checksum in the cache's `manifest.json`.
+//!
+//! Metadata documents such as the Chrome for Testing version list are kept
+//! in a [`MetadataCache`] for a configurable time and revalidated with their
+//! `ETag` afterwards.
+
+use crate::downloader::{find_driver_executable, sha256_file};
+use crate::error::WebDriverError;
+use crate::lock::{InstallLock, DEFAULT_LOCK_TIMEOUT};
+use crate::version::Version;
+use crate::runtime::{self, fs};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Name of the manifest file at the root of a [`Cache`].
+pub const CACHE_MANIFEST: &str = "manifest.json";
+
+// Marker file in an install directory whose modification time records the
+// last time the build was installed or reused.
+const LAST_USED_FILE: &str = ".last-used";
+
+// Directory of the [`MetadataCache`] inside the per-user cache. It sits next
+// to the driver directories but doesn't hold a driver.
+const METADATA_DIR: &str = "metadata";
+
+// Directory of the browsers installed from a bundle.
+const BROWSERS_DIR: &str = "browsers";
+
+/// Name of the project-local cache directory, see [`Cache::project`].
+pub const PROJECT_CACHE_DIR: &str = ".webdrivers";
+
+/// Storage for downloaded driver archives. Errors of a store don't fail an
+/// installation; the archive is downloaded instead, or just not stored.
+#[async_trait]
+pub trait CacheStore: Debug + Send + Sync {
+    /// Copies the artifact stored under `key` to `dest`.
+    /// Returns `Ok(false)` when the store doesn't contain the artifact.
+    async fn fetch(&self, key: &str, dest: &Path) -> Result<bool, WebDriverError>;
+
+    /// Stores the file at `src` under `key`.
+    async fn store(&self, key: &str, src: &Path) -> Result<(), WebDriverError>;
+}
+
+/// How long cached metadata is used without revalidating it by default.
+pub const DEFAULT_METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A metadata document read from a [`MetadataCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedMetadata {
+    pub body: String,
+    /// The `ETag` the server sent along with the document.
+    pub etag: Option<String>,
+    /// When the document was last fetched or revalidated.
+    pub fetched_at: Option<SystemTime>,
+}
+
+impl CachedMetadata {
+    /// Whether the document is younger than `ttl`.
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        self.fetched_at
+            .and_then(|fetched_at| fetched_at.elapsed().ok())
+            .is_some_and(|age| age < ttl)
+    }
+}
+
+/// On-disk cache of metadata documents, keyed by URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl MetadataCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: DEFAULT_METADATA_TTL,
+        }
+    }
+
+    /// The `metadata` directory of the per-user [`Cache`].
+    pub fn user() -> Result<Self, WebDriverError> {
+        Ok(Self::new(Cache::user()?.metadata_dir()))
+    }
+
+    /// Sets how long a cached document is used before it is revalidated.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn time_to_live(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The cached copy of the document at `url`, if any.
+    pub fn get(&self, url: &str) -> Option<CachedMetadata> {
+        let path = self.path_for(url);
+        let body = std::fs::read_to_string(&path).ok()?;
+        Some(CachedMetadata {
+            body,
+            etag: std::fs::read_to_string(etag_path(&path)).ok(),
+            fetched_at: modified(&path),
+        })
+    }
+
+    /// Stores a freshly fetched document.
+    pub fn store(&self, url: &str, body: &str, etag: Option<&str>) -> Result<(), WebDriverError> {
+        let path = self.path_for(url);
+        let parent = path.parent().unwrap_or(&self.dir);
+        let io_err = |e| WebDriverError::IoError {
+            path: path.clone(),
+            source: e,
+        };
+
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+        let mut file = tempfile::NamedTempFile::new_in(parent).map_err(io_err)?;
+        std::io::Write::write_all(&mut file, body.as_bytes()).map_err(io_err)?;
+        file.persist(&path).map_err(|e| io_err(e.error))?;
+
+        match etag {
+            Some(etag) => std::fs::write(etag_path(&path), etag).map_err(io_err)?,
+            None => {
+                let _ = std::fs::remove_file(etag_path(&path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the cached document as revalidated now.
+    pub fn touch(&self, url: &str) {
+        let _ = filetime::set_file_mtime(self.path_for(url), filetime::FileTime::now());
+    }
+
+    fn path_for(&self, url: &str) -> PathBuf {
+        artifact_key(url).split('/').fold(self.dir.clone(), |path, segment| path.join(segment))
+    }
+}
+
+fn etag_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".etag");
+    PathBuf::from(name)
+}
+
+/// A driver build installed in a [`Cache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledDriver {
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    /// Path to the driver executable.
+    pub path: PathBuf,
+    /// When the build was installed, as recorded in the cache manifest. For
+    /// builds the manifest doesn't know, the modification time of their
+    /// directory.
+    pub installed_at: Option<SystemTime>,
+    /// When the build was last installed or reused through the cache.
+    pub last_used: Option<SystemTime>,
+}
+
+/// An install as recorded in the cache manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub driver: String,
+    pub version: String,
+    pub platform: String,
+    /// The URL the driver was downloaded from, when it could be determined.
+    pub source_url: Option<String>,
+    /// SHA-256 checksum of the installed driver executable.
+    pub sha256: String,
+    /// Install time in seconds since the Unix epoch.
+    pub installed_at: u64,
+}
+
+/// The contents of a cache's `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheManifest {
+    pub format_version: u32,
+    pub installs: Vec<ManifestEntry>,
+}
+
+impl Default for CacheManifest {
+    fn default() -> Self {
+        Self {
+            format_version: 1,
+            installs: Vec::new(),
+        }
+    }
+}
+
+impl CacheManifest {
+    /// The recorded install of a driver build, if any.
+    pub fn entry(&self, driver: &str, version: &str, platform: &str) -> Option<&ManifestEntry> {
+        self.installs
+            .iter()
+            .find(|entry| entry.driver == driver && entry.version == version && entry.platform == platform)
+    }
+}
+
+/// Disk usage of a [`Cache`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub total_bytes: u64,
+    /// Bytes used per driver name.
+    pub drivers: BTreeMap<String, u64>,
+    /// Bytes used by the [`MetadataCache`] documents.
+    pub metadata_bytes: u64,
+}
+
+impl CacheStats {
+    /// Bytes used by installed drivers, the part eviction can reclaim.
+    pub fn driver_bytes(&self) -> u64 {
+        self.drivers.values().sum()
+    }
+}
+
+/// Which driver versions [`Cache::prune`] removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrunePolicy {
+    /// Keeps the `n` newest versions of every driver.
+    KeepLatest(usize),
+    /// Removes versions installed more than the given number of days ago,
+    /// going by [`InstalledDriver::installed_at`]. Using a version doesn't
+    /// make it younger.
+    OlderThan(u64),
+}
+
+/// The directory tree installed drivers are kept in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    root: PathBuf,
+    auto_prune: Option<PrunePolicy>,
+    max_size: Option<u64>,
+}
+
+impl Cache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            auto_prune: None,
+            max_size: None,
+        }
+    }
+
+    /// Prunes the versions of a driver with `policy` after every successful
+    /// install into this cache. The version just installed is always kept.
+    pub fn auto_prune(mut self, policy: PrunePolicy) -> Self {
+        self.auto_prune = Some(policy);
+        self
+    }
+
+    /// Limits the cache to `bytes`. After every install the least recently
+    /// used versions are evicted until the cache fits; the version just
+    /// installed is always kept.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// The per-user cache: `~/.cache/webdriver_manager` (or
+    /// `$XDG_CACHE_HOME/webdriver_manager`) on Linux,
+    /// `~/Library/Caches/webdriver_manager` on macOS and
+    /// `%LOCALAPPDATA%\webdriver_manager` on Windows.
+    pub fn user() -> Result<Self, WebDriverError> {
+        Self::default_dir()
+            .map(Self::new)
+            .ok_or_else(|| WebDriverError::Custom("Could not determine the user cache directory".to_string()))
+    }
+
+    /// The project-local cache: `.webdrivers/` next to the `Cargo.toml` of
+    /// the project, found through `CARGO_MANIFEST_DIR` (set by `cargo run`
+    /// and `cargo test`) or by searching upwards from the current directory.
+    /// Outside a Cargo project, `.webdrivers/` in the current directory.
+    pub fn project() -> Self {
+        let root = project_root()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        Self::new(root.join(PROJECT_CACHE_DIR))
+    }
+
+    /// The directory used by [`Cache::user`], if the platform has one.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("webdriver_manager"))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The install directory of a driver build: `<root>/<driver>/<version>/<platform>`.
+    pub fn install_dir(&self, driver: &str, version: &str, platform: &str) -> PathBuf {
+        self.root.join(driver).join(version).join(platform)
+    }
+
+    /// The directory of the [`MetadataCache`] of this cache.
+    pub fn metadata_dir(&self) -> PathBuf {
+        self.root.join(METADATA_DIR)
+    }
+
+    /// The directory of a browser installed from a bundle:
+    /// `<root>/browsers/<browser>/<version>/<platform>`.
+    pub fn browser_dir(&self, browser: &str, version: &str, platform: &str) -> PathBuf {
+        self.root.join(BROWSERS_DIR).join(browser).join(version).join(platform)
+    }
+
+    /// Reads the manifest. A cache without one has an empty manifest.
+    pub fn manifest(&self) -> Result<CacheManifest, WebDriverError> {
+        let path = self.root.join(CACHE_MANIFEST);
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CacheManifest::default()),
+            Err(e) => return Err(WebDriverError::IoError { path, source: e }),
+        };
+        serde_json::from_slice(&contents).map_err(|e| WebDriverError::json_parse(path.display().to_string(), &contents, e))
+    }
+
+    /// Records an install in the manifest, replacing an earlier record of
+    /// the same driver build.
+    pub(crate) fn record_install(&self, entry: ManifestEntry) -> Result<(), WebDriverError> {
+        self.update_manifest(|manifest| {
+            manifest
+                .installs
+                .retain(|e| (&e.driver, &e.version, &e.platform) != (&entry.driver, &entry.version, &entry.platform));
+            manifest.installs.push(entry);
+            true
+        })
+    }
+
+    /// Reads, changes and writes the manifest under a lock, so concurrent
+    /// installs into the cache don't drop each other's records. `update`
+    /// returns whether the manifest changed.
+    fn update_manifest(&self, update: impl FnOnce(&mut CacheManifest) -> bool) -> Result<(), WebDriverError> {
+        let _lock = InstallLock::acquire_blocking(&self.root.join(CACHE_MANIFEST), DEFAULT_LOCK_TIMEOUT)?;
+        let mut manifest = self.manifest()?;
+        if update(&mut manifest) {
+            self.write_manifest(&manifest)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the driver executable of a build still matches the checksum
+    /// recorded at install time. Builds without a record are trusted.
+    pub(crate) fn is_intact(&self, driver: &str, version: &str, platform: &str, driver_path: &Path) -> bool {
+        let Ok(manifest) = self.manifest() else {
+            return false;
+        };
+        match manifest.entry(driver, version, platform) {
+            Some(entry) => sha256_file(driver_path).is_ok_and(|sha256| sha256 == entry.sha256),
+            None => true,
+        }
+    }
+
+    /// Replaces the manifest atomically, so readers never see a partial file.
+    fn write_manifest(&self, manifest: &CacheManifest) -> Result<(), WebDriverError> {
+        let path = self.root.join(CACHE_MANIFEST);
+        let io_err = |e| WebDriverError::IoError {
+            path: path.clone(),
+            source: e,
+        };
+        let json = serde_json::to_vec_pretty(manifest).map_err(|e| WebDriverError::JsonParseError {
+            url: path.display().to_string(),
+            source: e,
+            snippet: String::new(),
+        })?;
+
+        std::fs::create_dir_all(&self.root).map_err(io_err)?;
+        let mut file = tempfile::NamedTempFile::new_in(&self.root).map_err(io_err)?;
+        std::io::Write::write_all(&mut file, &json).map_err(io_err)?;
+        file.persist(&path).map_err(|e| io_err(e.error))?;
+        Ok(())
+    }
+
+    /// Lists the installed driver builds, sorted by name, version and
+    /// platform. Directories without a driver executable are skipped.
+    pub fn list_installed(&self) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        // A damaged manifest only loses the recorded install times.
+        let manifest = self.manifest().unwrap_or_default();
+        let mut installed = Vec::new();
+        for driver_dir in subdirectories(&self.root)? {
+            let name = file_name(&driver_dir);
+            for version_dir in subdirectories(&driver_dir)? {
+                for platform_dir in subdirectories(&version_dir)? {
+                    let Ok(path) = find_driver_executable(&platform_dir, &name) else {
+                        continue;
+                    };
+                    let version = file_name(&version_dir);
+                    let platform = file_name(&platform_dir);
+                    let installed_at = match manifest.entry(&name, &version, &platform) {
+                        Some(entry) => UNIX_EPOCH.checked_add(Duration::from_secs(entry.installed_at)),
+                        None => modified(&platform_dir),
+                    };
+                    installed.push(InstalledDriver {
+                        name: name.clone(),
+                        version,
+                        platform,
+                        path,
+                        installed_at,
+                        last_used: modified(&platform_dir.join(LAST_USED_FILE)),
+                    });
+                }
+            }
+        }
+        Ok(installed)
+    }
+
+    /// Uninstalls a driver version for all platforms and drops it from the
+    /// manifest. Returns `false` when the version isn't installed. Waits for
+    /// concurrent installs of the version to finish first.
+    pub fn remove(&self, driver: &str, version: &str) -> Result<bool, WebDriverError> {
+        self.remove_locked(driver, version, DEFAULT_LOCK_TIMEOUT)
+    }
+
+    /// Like [`Cache::remove`], but leaves a version that is being installed
+    /// alone instead of waiting, for housekeeping.
+    fn remove_unless_busy(&self, driver: &str, version: &str) -> Result<bool, WebDriverError> {
+        match self.remove_locked(driver, version, Duration::ZERO) {
+            Err(WebDriverError::LockTimeout { .. }) => Ok(false),
+            removed => removed,
+        }
+    }
+
+    fn remove_locked(&self, driver: &str, version: &str, timeout: Duration) -> Result<bool, WebDriverError> {
+        for segment in [driver, version] {
+            check_path_segment(segment)?;
+        }
+
+        let driver_dir = self.root.join(driver);
+        let version_dir = driver_dir.join(version);
+        let io_err = |path: &Path, e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let entries: Vec<PathBuf> = match std::fs::read_dir(&version_dir) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(io_err(&version_dir, e)),
+        };
+
+        // Take the install lock of every platform, including installs that
+        // are still downloading and only have their lock file so far.
+        let mut platforms: Vec<String> = entries
+            .iter()
+            .map(|path| file_name(path))
+            .map(|name| name.strip_suffix(".lock").map(str::to_string).unwrap_or(name))
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        platforms.sort();
+        platforms.dedup();
+        let _locks = platforms
+            .iter()
+            .map(|platform| InstallLock::acquire_blocking(&version_dir.join(platform), timeout))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.update_manifest(|manifest| {
+            let recorded = manifest.installs.len();
+            manifest.installs.retain(|entry| entry.driver != driver || entry.version != version);
+            manifest.installs.len() != recorded
+        })?;
+
+        // The lock files stay, as InstallLock requires; everything else goes.
+        let mut removed = false;
+        for path in entries.iter().filter(|path| path.extension().is_none_or(|ext| ext != "lock")) {
+            let result = if path.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) };
+            match result {
+                Ok(()) => removed = true,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(io_err(path, e)),
+            }
+        }
+        // Drop the directories once only lock files are left, if any.
+        let _ = std::fs::remove_dir(&version_dir);
+        let _ = std::fs::remove_dir(&driver_dir);
+        Ok(removed)
+    }
+
+    /// Removes the driver versions selected by `policy` and returns the
+    /// removed builds.
+    pub fn prune(&self, policy: PrunePolicy) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        self.prune_versions(policy, None, None)
+    }
+
+    /// Removes every installed driver and returns the removed builds.
+    pub fn clear(&self) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        let installed = self.list_installed()?;
+        for build in &installed {
+            self.remove(&build.name, &build.version)?;
+        }
+        Ok(installed)
+    }
+
+    /// Disk usage of the cache, in total and per driver.
+    pub fn stats(&self) -> Result<CacheStats, WebDriverError> {
+        let mut stats = CacheStats::default();
+        for entry in WalkDir::new(&self.root) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.io_error().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => continue,
+                Err(e) => {
+                    return Err(WebDriverError::IoError {
+                        path: e.path().unwrap_or(&self.root).to_path_buf(),
+                        source: e.into_io_error().unwrap_or_else(|| std::io::Error::other("WalkDir error")),
+                    })
+                }
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let size = entry.metadata().map_or(0, |m| m.len());
+            stats.total_bytes += size;
+            // Files directly in the root don't belong to a driver, and neither
+            // do the metadata documents, bundled browsers or hidden
+            // bookkeeping directories.
+            if let Ok(relative) = entry.path().strip_prefix(&self.root)
+                && relative.components().count() > 1
+                && let Some(top) = relative.components().next()
+            {
+                let top = top.as_os_str().to_string_lossy();
+                if top == METADATA_DIR {
+                    stats.metadata_bytes += size;
+                } else if top != BROWSERS_DIR && !top.starts_with('.') {
+                    *stats.drivers.entry(top.into_owned()).or_default() += size;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Evicts the least recently used driver versions until the installed
+    /// drivers use at most `max_bytes`, and returns the evicted builds.
+    /// Metadata and other files that can't be evicted aren't counted.
+    pub fn evict_to_size(&self, max_bytes: u64) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        self.evict(max_bytes, None)
+    }
+
+    fn evict(&self, max_bytes: u64, keep: Option<(&str, &str)>) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        let mut total_bytes = self.stats()?.driver_bytes();
+
+        // Versions by the last use of any of their builds, oldest first.
+        let mut versions: BTreeMap<(String, String), Vec<InstalledDriver>> = BTreeMap::new();
+        for installed in self.list_installed()? {
+            versions
+                .entry((installed.name.clone(), installed.version.clone()))
+                .or_default()
+                .push(installed);
+        }
+        let mut versions: Vec<_> = versions.into_iter().collect();
+        versions.sort_by_cached_key(|(_, builds)| {
+            builds.iter().filter_map(|build| build.last_used.or(build.installed_at)).max()
+        });
+
+        let mut evicted = Vec::new();
+        for ((name, version), builds) in versions {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            if keep == Some((name.as_str(), version.as_str())) {
+                continue;
+            }
+            let size = dir_size(&self.root.join(&name).join(&version));
+            if self.remove_unless_busy(&name, &version)? {
+                total_bytes = total_bytes.saturating_sub(size);
+                evicted.extend(builds);
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Records a use of an installed build for the least-recently-used eviction.
+    pub(crate) fn touch(&self, driver: &str, version: &str, platform: &str) {
+        let _ = std::fs::write(self.install_dir(driver, version, platform).join(LAST_USED_FILE), b"");
+    }
+
+    /// Housekeeping after a successful install: records the use, then applies
+    /// the [`Cache::auto_prune`] policy and the [`Cache::max_size`] limit.
+    pub(crate) fn after_install(&self, driver: &str, version: &str, platform: &str) -> Result<(), WebDriverError> {
+        self.touch(driver, version, platform);
+        self.prune_after_install(driver, version)?;
+        if let Some(max_size) = self.max_size {
+            self.evict(max_size, Some((driver, version)))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the [`Cache::auto_prune`] policy for `driver`, keeping `version`.
+    fn prune_after_install(&self, driver: &str, version: &str) -> Result<(), WebDriverError> {
+        if let Some(policy) = self.auto_prune {
+            self.prune_versions(policy, Some(driver), Some(version))?;
+        }
+        Ok(())
+    }
+
+    fn prune_versions(
+        &self,
+        policy: PrunePolicy,
+        driver: Option<&str>,
+        keep: Option<&str>,
+    ) -> Result<Vec<InstalledDriver>, WebDriverError> {
+        // Builds grouped per driver and version, over all platforms.
+        let mut versions: BTreeMap<String, BTreeMap<String, Vec<InstalledDriver>>> = BTreeMap::new();
+        for installed in self.list_installed()? {
+            if driver.is_none_or(|driver| driver == installed.name) {
+                versions
+                    .entry(installed.name.clone())
+                    .or_default()
+                    .entry(installed.version.clone())
+                    .or_default()
+                    .push(installed);
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (name, builds) in versions {
+            let mut builds: Vec<_> = builds.into_iter().collect();
+            // Newest first; versions that don't parse sort last.
+            builds.sort_by_cached_key(|(version, _)| std::cmp::Reverse((version.parse::<Version>().ok(), version.clone())));
+
+            for (index, (version, builds)) in builds.into_iter().enumerate() {
+                let expired = match policy {
+                    PrunePolicy::KeepLatest(n) => index >= n,
+                    PrunePolicy::OlderThan(days) => {
+                        // A cutoff before the epoch can't be older than anything: keep everything.
+                        let cutoff = SystemTime::now().checked_sub(Duration::from_secs(days.saturating_mul(86_400)));
+                        cutoff.is_some_and(|cutoff| {
+                            builds
+                                .iter()
+                                .filter_map(|build| build.installed_at)
+                                .max()
+                                .is_some_and(|installed_at| installed_at < cutoff)
+                        })
+                    }
+                };
+                if expired && keep != Some(version.as_str()) && self.remove_unless_busy(&name, &version)? {
+                    removed.extend(builds);
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Lists the drivers installed in the per-user [`Cache`].
+pub fn list_installed() -> Result<Vec<InstalledDriver>, WebDriverError> {
+    Cache::user()?.list_installed()
+}
+
+/// Uninstalls a driver version from the per-user [`Cache`].
+pub fn remove(driver: &str, version: &str) -> Result<bool, WebDriverError> {
+    Cache::user()?.remove(driver, version)
+}
+
+/// Prunes the per-user [`Cache`] with `policy`.
+pub fn prune(policy: PrunePolicy) -> Result<Vec<InstalledDriver>, WebDriverError> {
+    Cache::user()?.prune(policy)
+}
+
+/// Disk usage of the per-user [`Cache`].
+pub fn stats() -> Result<CacheStats, WebDriverError> {
+    Cache::user()?.stats()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// The directory of the nearest `Cargo.toml`: `CARGO_MANIFEST_DIR`, or the
+/// current directory or the closest parent having one.
+pub fn project_root() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from)
+        && dir.join("Cargo.toml").is_file()
+    {
+        return Some(dir);
+    }
+    find_project_root(&std::env::current_dir().ok()?)
+}
+
+fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Rejects a name that would not stay a single directory below its parent
+/// when joined to it: empty names, `.`, `..`, and names containing a path
+/// separator or a drive prefix.
+pub(crate) fn check_path_segment(segment: &str) -> Result<(), WebDriverError> {
+    if segment.is_empty() || segment == "." || segment == ".." || segment.contains(['/', '\\', ':']) {
+        return Err(WebDriverError::Custom(format!("Invalid cache path segment: '{}'", segment)));
+    }
+    Ok(())
+}
+
+/// The directories directly below `dir`, sorted. A missing `dir` has none.
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>, WebDriverError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(WebDriverError::IoError {
+                path: dir.to_path_buf(),
+                source: e,
+            })
+        }
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// Derives a cache key from a download URL, e.g.
+/// `storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip`.
+pub fn artifact_key(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let without_query = without_scheme.split(['?', '#']).next().unwrap_or_default();
+
+    without_query
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(|segment| segment.replace(|c: char| !(c.is_ascii_alphanumeric() || "._-".contains(c)), "_"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A [`CacheStore`] keeping artifacts in a local directory.
+#[derive(Debug, Clone)]
+pub struct LocalCacheStore {
+    root: PathBuf,
+}
+
+impl LocalCacheStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        key.split('/').fold(self.root.clone(), |path, segment| path.join(segment))
+    }
+}
+
+#[async_trait]
+impl CacheStore for LocalCacheStore {
+    async fn fetch(&self, key: &str, dest: &Path) -> Result<bool, WebDriverError> {
+        let cached = self.path_for(key);
+        if !fs::exists(&cached).await.unwrap_or(false) {
+            return Ok(false);
+        }
+
+        fs::copy(&cached, dest)
+            .await
+            .map_err(|e| WebDriverError::IoError {
+                path: cached,
+                source: e,
+            })?;
+        Ok(true)
+    }
+
+    async fn store(&self, key: &str, src: &Path) -> Result<(), WebDriverError> {
+        let cached = self.path_for(key);
+        let parent = cached.parent().unwrap_or(&self.root).to_path_buf();
+        let src = src.to_path_buf();
+
+        runtime::spawn_blocking(move || {
+            let io_err = |path: &Path| {
+                let path = path.to_path_buf();
+                move |e| WebDriverError::IoError { path, source: e }
+            };
+
+            std::fs::create_dir_all(&parent).map_err(io_err(&parent))?;
+            // Copy to a unique temporary file first so concurrent readers never
+            // see a partial file and concurrent writers don't share one.
+            let mut file = tempfile::NamedTempFile::new_in(&parent).map_err(io_err(&parent))?;
+            let mut source = std::fs::File::open(&src).map_err(io_err(&src))?;
+            std::io::copy(&mut source, &mut file).map_err(io_err(file.path()))?;
+            file.persist(&cached).map_err(|e| io_err(&cached)(e.error))?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_key_from_url() {
+        assert_eq!(
+            artifact_key("https://storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip?x=1"),
+            "storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip"
+        );
+        assert_eq!(artifact_key("https://host:8080/../a b/c.zip"), "host_8080/a_b/c.zip");
+    }
+
+    #[test]
+    fn test_cache_layout() {
+        let cache = Cache::new("/tmp/drivers");
+        assert_eq!(
+            cache.install_dir("chromedriver", "138.0.7204.157", "linux64"),
+            Path::new("/tmp/drivers/chromedriver/138.0.7204.157/linux64")
+        );
+        if let Some(dir) = Cache::default_dir() {
+            assert!(dir.ends_with("webdriver_manager"));
+        }
+    }
+
+    /// Creates a small driver executable in the cache layout.
+    pub(crate) fn install_fake(cache: &Cache, driver: &str, version: &str, platform: &str) -> PathBuf {
+        let dir = cache.install_dir(driver, version, platform);
+        std::fs::create_dir_all(&dir).unwrap();
+        let name = if cfg!(target_os = "windows") { format!("{}.exe", driver) } else { driver.to_string() };
+        std::fs::write(dir.join(&name), b"driver").unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn test_project_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        let nested = dir.path().join("src").join("bin");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_project_root(&nested).as_deref(), Some(dir.path()));
+        // Tests run with `CARGO_MANIFEST_DIR` set to this crate.
+        assert_eq!(Cache::project().root(), Path::new(env!("CARGO_MANIFEST_DIR")).join(PROJECT_CACHE_DIR));
+    }
+
+    #[test]
+    fn test_list_installed() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        assert!(cache.list_installed().unwrap().is_empty());
+
+        let path = install_fake(&cache, "chromedriver", "138.0.7204.157", "linux64");
+        install_fake(&cache, "geckodriver", "0.36.0", "linux64");
+        std::fs::create_dir_all(cache.install_dir("geckodriver", "0.35.0", "linux64")).unwrap();
+
+        let installed = cache.list_installed().unwrap();
+        assert_eq!(installed.len(), 2);
+        assert_eq!(installed[0].name, "chromedriver");
+        assert_eq!(installed[0].version, "138.0.7204.157");
+        assert_eq!(installed[0].platform, "linux64");
+        assert_eq!(installed[0].path, path);
+        assert!(installed[0].installed_at.is_some());
+        assert_eq!(installed[1].version, "0.36.0");
+
+        // The install time of a recorded build comes from the manifest, not
+        // from its directory, which changes on every use.
+        cache
+            .record_install(ManifestEntry {
+                driver: "geckodriver".to_string(),
+                version: "0.36.0".to_string(),
+                platform: "linux64".to_string(),
+                source_url: None,
+                sha256: String::new(),
+                installed_at: 1_752_412_345,
+            })
+            .unwrap();
+        cache.touch("geckodriver", "0.36.0", "linux64");
+        let installed = cache.list_installed().unwrap();
+        assert_eq!(installed[1].installed_at, Some(UNIX_EPOCH + Duration::from_secs(1_752_412_345)));
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        install_fake(&cache, "chromedriver", "137.0.7151.119", "linux64");
+        install_fake(&cache, "chromedriver", "138.0.7204.157", "linux64");
+
+        assert!(cache.remove("chromedriver", "137.0.7151.119").unwrap());
+        assert!(!cache.remove("chromedriver", "137.0.7151.119").unwrap());
+        assert!(cache.remove("chromedriver", "..").is_err());
+
+        let installed = cache.list_installed().unwrap();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].version, "138.0.7204.157");
+
+        assert!(cache.remove("chromedriver", "138.0.7204.157").unwrap());
+        assert!(cache.list_installed().unwrap().is_empty());
+        // Only the install lock files are left behind.
+        let left: Vec<_> = WalkDir::new(dir.path().join("chromedriver"))
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| file_name(entry.path()))
+            .collect();
+        assert_eq!(left, ["linux64.lock"; 2]);
+    }
+
+    #[tokio::test]
+    async fn test_remove_waits_for_installs() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        install_fake(&cache, "chromedriver", "137.0.7151.119", "linux64");
+        install_fake(&cache, "chromedriver", "138.0.7204.157", "linux64");
+
+        // An install that is still downloading only has its lock file.
+        let busy = cache.install_dir("chromedriver", "137.0.7151.119", "win64");
+        let lock = InstallLock::acquire(&busy, Duration::ZERO).await.unwrap();
+        assert!(matches!(
+            cache.remove_locked("chromedriver", "137.0.7151.119", Duration::from_millis(50)),
+            Err(WebDriverError::LockTimeout { .. })
+        ));
+        // Housekeeping skips it instead of waiting.
+        assert!(cache.prune(PrunePolicy::KeepLatest(1)).unwrap().is_empty());
+        assert_eq!(cache.list_installed().unwrap().len(), 2);
+
+        drop(lock);
+        assert_eq!(cache.prune(PrunePolicy::KeepLatest(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        assert_eq!(cache.manifest().unwrap(), CacheManifest::default());
+
+        let path = install_fake(&cache, "geckodriver", "0.36.0", "linux64");
+        let entry = ManifestEntry {
+            driver: "geckodriver".to_string(),
+            version: "0.36.0".to_string(),
+            platform: "linux64".to_string(),
+            source_url: Some("https://example.com/geckodriver.tar.gz".to_string()),
+            sha256: sha256_file(&path).unwrap(),
+            installed_at: 1_752_412_345,
+        };
+        cache.record_install(entry.clone()).unwrap();
+        cache.record_install(entry.clone()).unwrap();
+        assert_eq!(cache.manifest().unwrap().installs, std::slice::from_ref(&entry));
+        assert!(cache.is_intact("geckodriver", "0.36.0", "linux64", &path));
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert!(!cache.is_intact("geckodriver", "0.36.0", "linux64", &path));
+
+        cache.remove("geckodriver", "0.36.0").unwrap();
+        assert!(cache.manifest().unwrap().installs.is_empty());
+
+        // Concurrent installs keep each other's records.
+        std::thread::scope(|scope| {
+            for platform in ["linux64", "mac64", "win64", "linux-aarch64"] {
+                let (cache, entry) = (&cache, &entry);
+                scope.spawn(move || {
+                    let entry = ManifestEntry {
+                        platform: platform.to_string(),
+                        ..entry.clone()
+                    };
+                    cache.record_install(entry).unwrap();
+                });
+            }
+        });
+        assert_eq!(cache.manifest().unwrap().installs.len(), 4);
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path());
+        for version in ["9.0.1", "10.0.0", "11.0.0"] {
+            install_fake(&cache, "fakedriver", version, "linux64");
+            install_fake(&cache, "fakedriver", version, "mac-arm64");
+        }
+        install_fake(&cache, "otherdriver", "1.0.0", "linux64");
+
+        assert!(cache.prune(PrunePolicy::OlderThan(1)).unwrap().is_empty());
+        assert!(cache.prune(PrunePolicy::OlderThan(u64::MAX)).unwrap().is_empty());
+
+        // A build installed long ago stays old when it is used again.
+        let installed_at = SystemTime::now() - Duration::from_secs(3 * 86_400);
+        cache
+            .record_install(ManifestEntry {
+                driver: "otherdriver".to_string(),
+                version: "1.0.0".to_string(),
+                platform: "linux64".to_string(),
+                source_url: None,
+                sha256: String::new(),
+                installed_at: installed_at.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            })
+            .unwrap();
+        cache.touch("otherdriver", "1.0.0", "linux64");
+        let removed = cache.prune(PrunePolicy::OlderThan(2)).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].name, "otherdriver");
+        install_fake(&cache, "otherdriver", "1.0.0", "linux64");
+
+        let removed = cache.prune(PrunePolicy::KeepLatest(2)).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|build| build.version == "9.0.1"));
+
+        // The installed version is kept even when it isn't the newest.
+        let cache = cache.auto_prune(PrunePolicy::KeepLatest(0));
+        cache.prune_after_install("fakedriver", "10.0.0").unwrap();
+        let versions: Vec<_> = cache.list_installed().unwrap().into_iter().map(|b| b.version).collect();
+        assert_eq!(versions, ["10.0.0", "10.0.0", "1.0.0"]);
+
+        assert_eq!(cache.clear().unwrap().len(), 3);
+        assert!(cache.list_installed().unwrap().is_empty());
+        assert!(cache.manifest().unwrap().installs.is_empty());
+    }
+
+    #[test]
+    fn test_stats_and_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path()).max_size(11);
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+
+        install_fake(&cache, "fakedriver", "1.0.0", "linux64");
+        install_fake(&cache, "fakedriver", "2.0.0", "linux64");
+        install_fake(&cache, "otherdriver", "1.0.0", "linux64");
+        std::fs::write(dir.path().join("notes.txt"), b"{}").unwrap();
+        std::fs::create_dir(dir.path().join(METADATA_DIR)).unwrap();
+        std::fs::write(dir.path().join(METADATA_DIR).join("versions.json"), [0; 100]).unwrap();
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.total_bytes, 120);
+        assert_eq!(stats.metadata_bytes, 100);
+        assert_eq!(stats.drivers.len(), 2);
+        assert_eq!(stats.drivers["fakedriver"], 12);
+        assert_eq!(stats.drivers["otherdriver"], 6);
+        assert_eq!(stats.driver_bytes(), 18);
+
+        // fakedriver 1.0.0 was used most recently, so the others go first.
+        cache.touch("otherdriver", "1.0.0", "linux64");
+        std::thread::sleep(Duration::from_millis(20));
+        cache.touch("fakedriver", "2.0.0", "linux64");
+        std::thread::sleep(Duration::from_millis(20));
+        cache.after_install("fakedriver", "1.0.0", "linux64").unwrap();
+
+        let installed = cache.list_installed().unwrap();
+        assert_eq!(installed.len(), 1);
+        assert_eq!((installed[0].name.as_str(), installed[0].version.as_str()), ("fakedriver", "1.0.0"));
+        assert!(installed[0].last_used.is_some());
+        // The metadata can't be evicted and doesn't count against the limit.
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.driver_bytes(), 6);
+        assert_eq!(stats.metadata_bytes, 100);
+    }
+
+    #[test]
+    fn test_metadata_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MetadataCache::new(dir.path()).ttl(Duration::from_secs(60));
+        let url = "https://example.com/known-good-versions-with-downloads.json";
+        assert_eq!(cache.get(url), None);
+
+        cache.store(url, "{}", Some("\"v1\"")).unwrap();
+        let cached = cache.get(url).unwrap();
+        assert_eq!(cached.body, "{}");
+        assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+        assert!(cached.is_fresh(cache.time_to_live()));
+        assert!(!cached.is_fresh(Duration::ZERO));
+
+        cache.store(url, "[]", None).unwrap();
+        assert_eq!(cache.get(url).unwrap().etag, None);
+    }
+
+    #[tokio::test]
+    async fn test_local_cache_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalCacheStore::new(dir.path().join("cache"));
+        let src = dir.path().join("driver.zip");
+        let dest = dir.path().join("restored.zip");
+        std::fs::write(&src, b"archive").unwrap();
+
+        assert!(!store.fetch("host/driver.zip", &dest).await.unwrap());
+        store.store("host/driver.zip", &src).await.unwrap();
+        assert!(store.fetch("host/driver.zip", &dest).await.unwrap());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"archive");
+
+        // Keys differing only in their last extension don't share a temporary file.
+        let other = dir.path().join("driver.tar.gz");
+        std::fs::write(&other, b"gzip").unwrap();
+        std::fs::write(&src, b"xz").unwrap();
+        let (xz, gz) = tokio::join!(store.store("host/driver.tar.xz", &src), store.store("host/driver.tar.gz", &other));
+        xz.unwrap();
+        gz.unwrap();
+        assert_eq!(std::fs::read(store.root().join("host/driver.tar.xz")).unwrap(), b"xz");
+        assert_eq!(std::fs::read(store.root().join("host/driver.tar.gz")).unwrap(), b"gzip");
+        assert_eq!(std::fs::read_dir(store.root().join("host")).unwrap().count(), 3);
+    }
+}
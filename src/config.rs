@@ -0,0 +1,506 @@
+//! Optional configuration file, `webdriver-manager.toml`.
+//!
+//! [`Config::load`] reads the file from the current directory or the closest
+//! parent having one, so a file in the project root applies anywhere in the
+//! project, and otherwise from `<config dir>/webdriver_manager/`. Without a
+//! file the defaults apply. [`crate::setup`] loads it automatically.
+//!
+//! Environment variables override the file, so CI pipelines can tune a run
+//! without touching it. All of them start with `WDM_` and are only read
+//! here; the rest of the crate gets them through [`Config`]. Settings made
+//! in code, such as a manager builder's `.offline(true)` or an explicit
+//! [`Endpoints`] value, win over both:
+//!
+//! 1. settings made in code or command line flags,
+//! 2. the `WDM_*` environment variables,
+//! 3. `webdriver-manager.toml`,
+//! 4. the mirror preset, for the endpoints it covers,
+//! 5. the upstream defaults.
+//!
+//! | Variable | Setting |
+//! |----------|---------|
+//! | `WDM_CONFIG` | Path of the configuration file to load |
+//! | `WDM_CACHE_DIR` | `install_dir` |
+//! | `WDM_LOCAL` | `local` (`1`, `true`, `yes` or `on`) |
+//! | `WDM_LOCKFILE` | `lockfile` |
+//! | `WDM_OFFLINE` | `offline` (`1`, `true`, `yes` or `on`) |
+//! | `WDM_REUSE_SYSTEM_DRIVER` | `reuse_system_driver` (`1`, `true`, `yes` or `on`) |
+//! | `WDM_PROXY` | `proxy` |
+//! | `WDM_NO_PROXY` | `no_proxy` |
+//! | `WDM_MIRROR` | `mirror` |
+//! | `WDM_<BROWSER>_VERSION` | `versions.<browser>`, e.g. `WDM_CHROME_VERSION`, `WDM_FIREFOX_VERSION`, `WDM_IE_VERSION` |
+//! | `WDM_<DRIVER>_DOWNLOAD_BASE` | `mirrors.<driver>`, e.g. `WDM_CHROMEDRIVER_DOWNLOAD_BASE` |
+//! | `WDM_CHROMEDRIVER_ENDPOINT` | `endpoints.chromedriver_versions` |
+//! | `WDM_CHROMEDRIVER_CHANNELS_ENDPOINT` | `endpoints.chromedriver_channels` |
+//! | `WDM_CHROMEDRIVER_BUILDS_ENDPOINT` | `endpoints.chromedriver_builds` |
+//! | `WDM_CHROMEDRIVER_MILESTONES_ENDPOINT` | `endpoints.chromedriver_milestones` |
+//! | `WDM_CHROMEDRIVER_LEGACY_ENDPOINT` | `endpoints.chromedriver_legacy` |
+//! | `WDM_EDGEDRIVER_ENDPOINT` | `endpoints.edgedriver` |
+//! | `WDM_GITHUB_API` | `endpoints.github_api` |
+//! | `WDM_DISPLAY` | `display` |
+//! | `WDM_BROWSER_PATH` | `browser_paths`, separated like `PATH`; added to the file's |
+//!
+//! `GITHUB_TOKEN` and the standard proxy variables are read under their
+//! usual names.
+//!
+//! ```toml
+//! # Relative to the directory of the file.
+//! install_dir = ".drivers"
+//! # Or install into `.webdrivers/` next to `Cargo.toml`.
+//! local = false
+//! # Pins the drivers, see `lockfile`.
+//! lockfile = "webdriver-lock.json"
+//! offline = false
+//! # Use a compatible driver from CHROMEDRIVER, GECKODRIVER, ... or PATH.
+//! reuse_system_driver = false
+//! proxy = "http://proxy.corp:3128"
+//! no_proxy = "localhost,.corp"
+//! # A mirror preset, see `MirrorPreset`.
+//! mirror = "npmmirror"
+//! # Extra browser locations, see `add_browser_search_path`.
+//! browser_paths = ["/opt/chrome"]
+//! # Display for started drivers on Linux: "inherit", "xvfb", "xvfb :42"
+//! # or an X display such as ":99". See `DisplayMode`.
+//! display = "xvfb"
+//!
+//! # Download base per driver.
+//! [mirrors]
+//! chromedriver = "https://mirror.corp/cft"
+//!
+//! [endpoints]
+//! chromedriver_versions = "https://mirror.corp/cft/known-good-versions-with-downloads.json"
+//! github_api = "https://github.corp/api/v3"
+//! edgedriver = "https://mirror.corp/edgedriver"
+//!
+//! # Browser version or version spec per browser.
+//! [versions]
+//! chrome = "126"
+//! firefox = "=0.35.0"
+//! ```
+
+use crate::browser::Browser;
+use crate::cache::Cache;
+use crate::display::DisplayMode;
+use crate::downloader::DownloadOptions;
+use crate::endpoints::{downloaded_drivers, Endpoints};
+use crate::error::WebDriverError;
+use crate::http::{NetworkPolicy, ProxyConfig};
+use crate::setup::SetupOptions;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the configuration file.
+pub const CONFIG_FILE: &str = "webdriver-manager.toml";
+
+/// Path of the configuration file, instead of searching for one.
+pub const CONFIG_ENV: &str = "WDM_CONFIG";
+/// Overrides `install_dir`.
+pub const CACHE_DIR_ENV: &str = "WDM_CACHE_DIR";
+/// Overrides `local`.
+pub const LOCAL_ENV: &str = "WDM_LOCAL";
+/// Overrides `lockfile`.
+pub const LOCKFILE_ENV: &str = "WDM_LOCKFILE";
+/// Overrides `offline`.
+pub const OFFLINE_ENV: &str = "WDM_OFFLINE";
+/// Overrides `reuse_system_driver`.
+pub const REUSE_SYSTEM_DRIVER_ENV: &str = "WDM_REUSE_SYSTEM_DRIVER";
+/// Overrides `proxy`.
+pub const PROXY_ENV: &str = "WDM_PROXY";
+/// Overrides `no_proxy`.
+pub const NO_PROXY_ENV: &str = "WDM_NO_PROXY";
+/// Overrides `mirror`.
+pub const MIRROR_ENV: &str = "WDM_MIRROR";
+/// Overrides `endpoints.chromedriver_versions`.
+pub const CHROMEDRIVER_ENDPOINT_ENV: &str = "WDM_CHROMEDRIVER_ENDPOINT";
+/// Overrides `endpoints.chromedriver_channels`.
+pub const CHROMEDRIVER_CHANNELS_ENDPOINT_ENV: &str = "WDM_CHROMEDRIVER_CHANNELS_ENDPOINT";
+/// Overrides `endpoints.chromedriver_builds`.
+pub const CHROMEDRIVER_BUILDS_ENDPOINT_ENV: &str = "WDM_CHROMEDRIVER_BUILDS_ENDPOINT";
+/// Overrides `endpoints.chromedriver_milestones`.
+pub const CHROMEDRIVER_MILESTONES_ENDPOINT_ENV: &str = "WDM_CHROMEDRIVER_MILESTONES_ENDPOINT";
+/// Overrides `endpoints.chromedriver_legacy`.
+pub const CHROMEDRIVER_LEGACY_ENDPOINT_ENV: &str = "WDM_CHROMEDRIVER_LEGACY_ENDPOINT";
+/// Overrides `endpoints.edgedriver`.
+pub const EDGEDRIVER_ENDPOINT_ENV: &str = "WDM_EDGEDRIVER_ENDPOINT";
+/// Overrides `endpoints.github_api`.
+pub const GITHUB_API_ENV: &str = "WDM_GITHUB_API";
+/// Overrides `display`.
+pub const DISPLAY_ENV: &str = "WDM_DISPLAY";
+/// Extends `browser_paths`, separated like `PATH` (`;` on Windows, `:`
+/// elsewhere).
+pub const BROWSER_PATH_ENV: &str = "WDM_BROWSER_PATH";
+
+const BROWSERS: &[Browser] = &[
+    Browser::Chrome,
+    Browser::Chromium,
+    Browser::Brave,
+    Browser::Edge,
+    Browser::Firefox,
+    Browser::Safari,
+    Browser::InternetExplorer,
+];
+
+/// The environment variable overriding the version for `browser`, such as
+/// `WDM_CHROME_VERSION`.
+pub fn version_env(browser: Browser) -> String {
+    format!("WDM_{}_VERSION", browser.as_str().to_uppercase())
+}
+
+/// The environment variable overriding the download base of `driver`, such
+/// as `WDM_CHROMEDRIVER_DOWNLOAD_BASE`.
+pub fn download_base_env(driver: &str) -> String {
+    format!("WDM_{}_DOWNLOAD_BASE", driver.to_uppercase())
+}
+
+/// The settings of a `webdriver-manager.toml` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Root of the driver cache, instead of the user cache.
+    pub install_dir: Option<PathBuf>,
+    /// Installs into the project-local cache, see [`Cache::project`].
+    /// `install_dir` takes precedence.
+    pub local: Option<bool>,
+    /// Lockfile pinning the drivers, see [`crate::lockfile`].
+    pub lockfile: Option<PathBuf>,
+    /// Disables network requests.
+    pub offline: Option<bool>,
+    /// Uses a compatible preinstalled driver, see
+    /// [`SetupOptions::reuse_system_driver`].
+    pub reuse_system_driver: Option<bool>,
+    /// Proxy URL for all requests.
+    pub proxy: Option<String>,
+    /// Hosts bypassing the proxy, comma separated.
+    pub no_proxy: Option<String>,
+    /// Name of a mirror preset, such as `npmmirror`.
+    pub mirror: Option<String>,
+    /// Extra locations to look for browsers in, see
+    /// [`Config::register_browser_paths`].
+    pub browser_paths: Vec<PathBuf>,
+    /// Display handling for started drivers on Linux, see
+    /// [`Config::display_mode`].
+    pub display: Option<String>,
+    /// Download base URL per driver name.
+    pub mirrors: BTreeMap<String, String>,
+    /// Metadata endpoint overrides.
+    pub endpoints: EndpointsConfig,
+    /// Browser version or version spec per browser name.
+    pub versions: BTreeMap<String, String>,
+}
+
+/// The `[endpoints]` table; see [`Endpoints`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointsConfig {
+    pub chromedriver_versions: Option<String>,
+    pub chromedriver_channels: Option<String>,
+    pub chromedriver_builds: Option<String>,
+    pub chromedriver_milestones: Option<String>,
+    pub chromedriver_legacy: Option<String>,
+    pub edgedriver: Option<String>,
+    pub github_api: Option<String>,
+}
+
+impl Config {
+    /// Loads the nearest configuration file, or the defaults without one,
+    /// and applies the environment variable overrides.
+    pub fn load() -> Result<Self, WebDriverError> {
+        let config = match Self::find() {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        Ok(config.with_env())
+    }
+
+    /// The configuration file [`Config::load`] reads, if there is one:
+    /// [`CONFIG_ENV`], or the nearest `webdriver-manager.toml`.
+    pub fn find() -> Option<PathBuf> {
+        if let Some(path) = env_value(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let cwd = std::env::current_dir().ok();
+        let project = cwd
+            .iter()
+            .flat_map(|dir| dir.ancestors())
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file());
+        project.or_else(|| {
+            let path = dirs::config_dir()?.join("webdriver_manager").join(CONFIG_FILE);
+            path.is_file().then_some(path)
+        })
+    }
+
+    /// Reads the configuration file at `path`. A relative `install_dir`,
+    /// `lockfile` or browser path is taken relative to the file.
+    pub fn from_file(path: &Path) -> Result<Self, WebDriverError> {
+        let text = std::fs::read_to_string(path).map_err(|e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        let mut config: Config = toml::from_str(&text).map_err(|e| WebDriverError::InvalidConfig {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })?;
+
+        if let Some(base) = path.parent() {
+            let paths = [&mut config.install_dir, &mut config.lockfile].into_iter().flatten();
+            for path in paths.chain(&mut config.browser_paths) {
+                if path.is_relative() {
+                    *path = base.join(&*path);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Applies the `WDM_*` environment variables on top of these settings.
+    pub fn with_env(self) -> Self {
+        self.with_vars(env_value)
+    }
+
+    fn with_vars(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+        if let Some(dir) = var(CACHE_DIR_ENV) {
+            self.install_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(local) = var(LOCAL_ENV) {
+            self.local = Some(is_truthy(&local));
+        }
+        if let Some(lockfile) = var(LOCKFILE_ENV) {
+            self.lockfile = Some(PathBuf::from(lockfile));
+        }
+        if let Some(offline) = var(OFFLINE_ENV) {
+            self.offline = Some(is_truthy(&offline));
+        }
+        if let Some(reuse) = var(REUSE_SYSTEM_DRIVER_ENV) {
+            self.reuse_system_driver = Some(is_truthy(&reuse));
+        }
+        if let Some(proxy) = var(PROXY_ENV) {
+            self.proxy = Some(proxy);
+        }
+        if let Some(no_proxy) = var(NO_PROXY_ENV) {
+            self.no_proxy = Some(no_proxy);
+        }
+        if let Some(mirror) = var(MIRROR_ENV) {
+            self.mirror = Some(mirror);
+        }
+        for driver in downloaded_drivers() {
+            if let Some(base) = var(&download_base_env(driver)) {
+                self.mirrors.insert(driver.to_string(), base);
+            }
+        }
+        let endpoints = [
+            (CHROMEDRIVER_ENDPOINT_ENV, &mut self.endpoints.chromedriver_versions),
+            (CHROMEDRIVER_CHANNELS_ENDPOINT_ENV, &mut self.endpoints.chromedriver_channels),
+            (CHROMEDRIVER_BUILDS_ENDPOINT_ENV, &mut self.endpoints.chromedriver_builds),
+            (CHROMEDRIVER_MILESTONES_ENDPOINT_ENV, &mut self.endpoints.chromedriver_milestones),
+            (CHROMEDRIVER_LEGACY_ENDPOINT_ENV, &mut self.endpoints.chromedriver_legacy),
+            (EDGEDRIVER_ENDPOINT_ENV, &mut self.endpoints.edgedriver),
+            (GITHUB_API_ENV, &mut self.endpoints.github_api),
+        ];
+        for (name, endpoint) in endpoints {
+            if let Some(url) = var(name) {
+                *endpoint = Some(url);
+            }
+        }
+        if let Some(display) = var(DISPLAY_ENV) {
+            self.display = Some(display);
+        }
+        if let Some(paths) = var(BROWSER_PATH_ENV) {
+            self.browser_paths.extend(std::env::split_paths(&paths));
+        }
+        for &browser in BROWSERS {
+            if let Some(version) = var(&version_env(browser)) {
+                // Replace any alias of the browser in the file.
+                self.versions.retain(|name, _| name.parse::<Browser>().ok() != Some(browser));
+                self.versions.insert(browser.as_str().to_string(), version);
+            }
+        }
+        self
+    }
+
+    /// The version configured for `browser`. Keys may use any browser name
+    /// [`Browser`] parses, such as `ie` or `internet explorer`.
+    pub fn version_for(&self, browser: Browser) -> Option<&str> {
+        self.versions
+            .iter()
+            .find(|(name, _)| name.parse::<Browser>().is_ok_and(|b| b == browser))
+            .map(|(_, version)| version.as_str())
+    }
+
+    /// The configured cache, if `install_dir` or `local` is set.
+    pub fn cache(&self) -> Option<Cache> {
+        match &self.install_dir {
+            Some(dir) => Some(Cache::new(dir)),
+            None => (self.local == Some(true)).then(Cache::project),
+        }
+    }
+
+    /// The configured endpoint overrides.
+    pub fn endpoints(&self) -> Result<Endpoints, WebDriverError> {
+        let mut endpoints = Endpoints::new();
+        if let Some(mirror) = &self.mirror {
+            endpoints = endpoints.mirror(mirror.parse()?);
+        }
+        for (driver, base) in &self.mirrors {
+            endpoints = endpoints.download_base(driver, base);
+        }
+        endpoints.chromedriver_versions = self.endpoints.chromedriver_versions.clone();
+        endpoints.chromedriver_channels = self.endpoints.chromedriver_channels.clone();
+        endpoints.chromedriver_builds = self.endpoints.chromedriver_builds.clone();
+        endpoints.chromedriver_milestones = self.endpoints.chromedriver_milestones.clone();
+        endpoints.chromedriver_legacy = self.endpoints.chromedriver_legacy.clone();
+        endpoints.edgedriver = self.endpoints.edgedriver.clone();
+        endpoints.github_api = self.endpoints.github_api.clone();
+        Ok(endpoints)
+    }
+
+    /// The configured [`DisplayMode`] for started drivers, to pass to
+    /// [`crate::display::prepare_display`]. Without a `display` setting the
+    /// environment is inherited.
+    pub fn display_mode(&self) -> Result<DisplayMode, WebDriverError> {
+        self.display.as_deref().map_or(Ok(DisplayMode::Inherit), str::parse)
+    }
+
+    /// Adds `browser_paths` to the locations browsers are looked up in, see
+    /// [`crate::browser::add_browser_search_path`]. [`crate::setup`] and the
+    /// command line tool do this for the loaded configuration.
+    pub fn register_browser_paths(&self) {
+        for path in &self.browser_paths {
+            crate::browser::add_browser_search_path(path);
+        }
+    }
+
+    /// Download options with the configured endpoints, proxy and network policy.
+    pub fn download_options(&self) -> Result<DownloadOptions, WebDriverError> {
+        let mut options = DownloadOptions::new().endpoints(self.endpoints()?);
+        if let Some(url) = &self.proxy {
+            let mut proxy = ProxyConfig::new(url);
+            if let Some(hosts) = &self.no_proxy {
+                proxy = proxy.no_proxy(hosts);
+            }
+            options = options.proxy(proxy);
+        }
+        if self.offline == Some(true) {
+            options = options.network(NetworkPolicy::Offline);
+        }
+        Ok(options)
+    }
+
+    /// The [`SetupOptions`] for `browser`.
+    pub fn setup_options(&self, browser: Browser) -> Result<SetupOptions, WebDriverError> {
+        Ok(SetupOptions {
+            browser_path: None,
+            version: self.version_for(browser).map(str::to_string),
+            cache: self.cache(),
+            download_options: self.download_options()?,
+            lockfile: self.lockfile.clone(),
+            update_lockfile: false,
+            reuse_system_driver: self.reuse_system_driver == Some(true),
+        })
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"
+install_dir = ".drivers"
+lockfile = "webdriver-lock.json"
+offline = true
+reuse_system_driver = true
+proxy = "http://proxy.corp:3128"
+mirror = "npmmirror"
+display = "xvfb"
+
+[mirrors]
+chromedriver = "https://mirror.corp/cft/"
+
+[endpoints]
+github_api = "https://github.corp/api/v3"
+
+[versions]
+chrome = "126"
+"internet explorer" = "=4.14.0"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.cache().unwrap().root(), dir.path().join(".drivers"));
+        assert_eq!(config.lockfile, Some(dir.path().join("webdriver-lock.json")));
+        assert_eq!(config.version_for(Browser::Chrome), Some("126"));
+        assert_eq!(config.version_for(Browser::InternetExplorer), Some("=4.14.0"));
+        assert_eq!(config.version_for(Browser::Firefox), None);
+        assert_eq!(config.display_mode().unwrap(), DisplayMode::xvfb());
+
+        assert!(config.setup_options(Browser::Chrome).unwrap().reuse_system_driver);
+        let options = config.download_options().unwrap();
+        assert_eq!(options.network, NetworkPolicy::Offline);
+        assert_eq!(options.proxy.unwrap().url, "http://proxy.corp:3128");
+        assert_eq!(options.endpoints.github_api_url(), "https://github.corp/api/v3");
+        assert_eq!(
+            options.endpoints.download_base_for("chromedriver").as_deref(),
+            Some("https://mirror.corp/cft")
+        );
+        assert_eq!(
+            options.endpoints.download_base_for("geckodriver").as_deref(),
+            Some("https://cdn.npmmirror.com/binaries/geckodriver")
+        );
+
+        // The environment wins over the file.
+        let vars = |name: &str| match name {
+            "WDM_OFFLINE" => Some("false".to_string()),
+            "WDM_REUSE_SYSTEM_DRIVER" => Some("no".to_string()),
+            "WDM_CACHE_DIR" => Some("/ci/drivers".to_string()),
+            "WDM_LOCKFILE" => Some("/ci/webdriver-lock.json".to_string()),
+            "WDM_IE_VERSION" => Some("=4.8.0".to_string()),
+            "WDM_FIREFOX_VERSION" => Some("128".to_string()),
+            "WDM_GECKODRIVER_DOWNLOAD_BASE" => Some("https://ci.corp/geckodriver".to_string()),
+            "WDM_GITHUB_API" => Some("https://ci.corp/api".to_string()),
+            "WDM_BROWSER_PATH" => Some("/opt/chrome".to_string()),
+            "WDM_DISPLAY" => Some(":42".to_string()),
+            _ => None,
+        };
+        let config = config.with_vars(vars);
+        assert_eq!(config.offline, Some(false));
+        assert_eq!(config.reuse_system_driver, Some(false));
+        assert_eq!(config.install_dir, Some(PathBuf::from("/ci/drivers")));
+        assert_eq!(config.lockfile, Some(PathBuf::from("/ci/webdriver-lock.json")));
+        assert_eq!(config.version_for(Browser::InternetExplorer), Some("=4.8.0"));
+        assert_eq!(config.version_for(Browser::Firefox), Some("128"));
+        assert_eq!(config.version_for(Browser::Chrome), Some("126"));
+        assert_eq!(version_env(Browser::Chrome), "WDM_CHROME_VERSION");
+        assert_eq!(download_base_env("IEDriverServer"), "WDM_IEDRIVERSERVER_DOWNLOAD_BASE");
+        assert_eq!(config.browser_paths, [PathBuf::from("/opt/chrome")]);
+        assert_eq!(config.display_mode().unwrap(), DisplayMode::Display(":42".into()));
+        let endpoints = config.endpoints().unwrap();
+        assert_eq!(endpoints.github_api_url(), "https://ci.corp/api");
+        // The variable beats the file and the file beats the preset.
+        assert_eq!(endpoints.download_base_for("geckodriver").as_deref(), Some("https://ci.corp/geckodriver"));
+        assert_eq!(endpoints.download_base_for("chromedriver").as_deref(), Some("https://mirror.corp/cft"));
+
+        let local = Config::default().with_vars(|name| (name == "WDM_LOCAL").then(|| "1".to_string()));
+        assert_eq!(local.cache(), Some(Cache::project()));
+
+        std::fs::write(&path, "offlline = true\n").unwrap();
+        assert!(matches!(Config::from_file(&path), Err(WebDriverError::InvalidConfig { .. })));
+    }
+}
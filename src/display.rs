@@ -0,0 +1,321 @@
+//! Display environment handling for drivers started on Linux.
+//!
+//! Browsers launched by a driver need either an X11 or a Wayland display
+//! unless they run headless. On bare CI runners neither is available, which
+//! makes sessions fail in ways that are hard to diagnose. The helpers here
+//! detect the current display and prepare the environment of the driver
+//! process, optionally starting an Xvfb server. The `display` setting of
+//! [`crate::config::Config`] picks the [`DisplayMode`]. Preparing a driver
+//! command needs the `tokio` feature, the command being a
+//! `tokio::process::Command`.
+
+use crate::error::WebDriverError;
+use std::str::FromStr;
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "tokio")]
+use std::process::Stdio;
+#[cfg(feature = "tokio")]
+use std::time::Instant;
+#[cfg(feature = "tokio")]
+use tokio::process::{Child, Command};
+
+/// How long a started Xvfb server gets to open its display.
+pub const XVFB_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Interval between checks for the Xvfb display socket.
+#[cfg(feature = "tokio")]
+const XVFB_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The display server available to started drivers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayServer {
+    X11(String),
+    Wayland(String),
+    /// The platform provides its own window server (Windows, macOS).
+    Native,
+    /// No display could be found; browsers must run headless.
+    None,
+}
+
+/// How the display environment of a started driver should be managed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Leave `DISPLAY`/`WAYLAND_DISPLAY` untouched.
+    #[default]
+    Inherit,
+    /// Use the given X display, e.g. an Xvfb instance managed by CI.
+    Display(String),
+    /// Start an Xvfb server on `display` when no display is available.
+    Xvfb { display: String, screen: String },
+}
+
+impl DisplayMode {
+    /// Xvfb on `:99` with a 1920x1080 screen of 24 bit depth.
+    pub fn xvfb() -> Self {
+        DisplayMode::Xvfb {
+            display: ":99".to_string(),
+            screen: "1920x1080x24".to_string(),
+        }
+    }
+}
+
+impl FromStr for DisplayMode {
+    type Err = WebDriverError;
+
+    /// Parses `inherit`, `xvfb`, `xvfb <display>` or an X display such as `:99`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("inherit") {
+            return Ok(DisplayMode::Inherit);
+        }
+        if let Some(rest) = s.strip_prefix("xvfb").or_else(|| s.strip_prefix("Xvfb")) {
+            return match (rest.trim(), DisplayMode::xvfb()) {
+                ("", mode) => Ok(mode),
+                (display, DisplayMode::Xvfb { screen, .. }) if display.starts_with(':') => Ok(DisplayMode::Xvfb {
+                    display: display.to_string(),
+                    screen,
+                }),
+                _ => Err(WebDriverError::Custom(format!("Unknown display mode: '{}'", s))),
+            };
+        }
+        if s.contains(':') {
+            Ok(DisplayMode::Display(s.to_string()))
+        } else {
+            Err(WebDriverError::Custom(format!("Unknown display mode: '{}'", s)))
+        }
+    }
+}
+
+/// The result of preparing a driver's display environment.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct PreparedDisplay {
+    pub server: DisplayServer,
+    /// Set when no display is available, so callers can request headless
+    /// browsers in their capabilities.
+    pub headless_hint: bool,
+    /// The Xvfb server started for the driver, if any. It is killed when
+    /// this value is dropped.
+    pub xvfb: Option<Child>,
+}
+
+/// Detects the display server from the current environment.
+pub fn detect_display() -> DisplayServer {
+    if !cfg!(target_os = "linux") {
+        return DisplayServer::Native;
+    }
+    display_from_env(
+        std::env::var("WAYLAND_DISPLAY").ok(),
+        std::env::var("DISPLAY").ok(),
+    )
+}
+
+fn display_from_env(wayland: Option<String>, x11: Option<String>) -> DisplayServer {
+    match (wayland.filter(|v| !v.is_empty()), x11.filter(|v| !v.is_empty())) {
+        (Some(wayland), _) => DisplayServer::Wayland(wayland),
+        (None, Some(display)) => DisplayServer::X11(display),
+        (None, None) => DisplayServer::None,
+    }
+}
+
+/// Prepares the environment of `command` (the driver process) according to
+/// `mode`. Only Linux is affected; Windows and macOS always use their own
+/// window server.
+#[cfg(feature = "tokio")]
+pub async fn prepare_display(
+    mode: &DisplayMode,
+    command: &mut Command,
+) -> Result<PreparedDisplay, WebDriverError> {
+    prepare_display_for(detect_display(), mode, command).await
+}
+
+#[cfg(feature = "tokio")]
+async fn prepare_display_for(
+    detected: DisplayServer,
+    mode: &DisplayMode,
+    command: &mut Command,
+) -> Result<PreparedDisplay, WebDriverError> {
+    let (server, xvfb) = match (mode, detected) {
+        (_, server @ DisplayServer::Native) | (DisplayMode::Inherit, server) => (server, None),
+        (DisplayMode::Display(display), _) => {
+            command.env("DISPLAY", display).env_remove("WAYLAND_DISPLAY");
+            (DisplayServer::X11(display.clone()), None)
+        }
+        (DisplayMode::Xvfb { display, screen }, DisplayServer::None) => {
+            let child = start_xvfb(display, screen).await?;
+            command.env("DISPLAY", display);
+            (DisplayServer::X11(display.clone()), Some(child))
+        }
+        (DisplayMode::Xvfb { .. }, other) => (other, None),
+    };
+
+    Ok(PreparedDisplay {
+        headless_hint: server == DisplayServer::None,
+        server,
+        xvfb,
+    })
+}
+
+/// Starts `Xvfb <display> -screen 0 <screen>` in the background and waits
+/// until it accepts connections.
+#[cfg(feature = "tokio")]
+async fn start_xvfb(display: &str, screen: &str) -> Result<Child, WebDriverError> {
+    let command_line = format!("Xvfb {} -screen 0 {}", display, screen);
+    let mut command = Command::new("Xvfb");
+    command
+        .args([display, "-screen", "0", screen, "-nolisten", "tcp"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let mut child = command.spawn().map_err(|e| WebDriverError::CommandExecutionError {
+        command: command_line.clone(),
+        source: e,
+    })?;
+    if let Some(socket) = x11_socket(display) {
+        wait_for_socket(&mut child, &socket, XVFB_READY_TIMEOUT)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: command_line,
+                source: e,
+            })?;
+    }
+    Ok(child)
+}
+
+/// The Unix socket of a local X display, `/tmp/.X11-unix/X<n>` for `:<n>`
+/// or `:<n>.<screen>`.
+#[cfg(feature = "tokio")]
+fn x11_socket(display: &str) -> Option<PathBuf> {
+    let number = display.strip_prefix(':')?.split('.').next()?;
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(Path::new("/tmp/.X11-unix").join(format!("X{}", number)))
+}
+
+/// Waits until `socket` exists, failing when `child` exits first or the
+/// timeout passes.
+#[cfg(feature = "tokio")]
+async fn wait_for_socket(child: &mut Child, socket: &Path, timeout: Duration) -> std::io::Result<()> {
+    let started = Instant::now();
+    while !socket.exists() {
+        if let Some(status) = child.try_wait()? {
+            return Err(std::io::Error::other(format!("exited with {} before opening the display", status)));
+        }
+        if started.elapsed() >= timeout {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("display socket '{}' did not appear within {:?}", socket.display(), timeout),
+            ));
+        }
+        tokio::time::sleep(XVFB_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_from_env() {
+        assert_eq!(
+            display_from_env(Some("wayland-0".into()), Some(":0".into())),
+            DisplayServer::Wayland("wayland-0".into())
+        );
+        assert_eq!(display_from_env(Some(String::new()), Some(":99".into())), DisplayServer::X11(":99".into()));
+        assert_eq!(display_from_env(None, None), DisplayServer::None);
+    }
+
+    #[cfg(feature = "tokio")]
+    fn env_of(command: &Command, name: &str) -> Option<Option<String>> {
+        command
+            .as_std()
+            .get_envs()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.map(|v| v.to_string_lossy().into_owned()))
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_prepare_display() {
+        let mode = DisplayMode::Display(":42".into());
+
+        // Windows and macOS keep their own window server.
+        let mut command = Command::new("chromedriver");
+        let prepared = prepare_display_for(DisplayServer::Native, &mode, &mut command).await.unwrap();
+        assert_eq!(prepared.server, DisplayServer::Native);
+        assert_eq!(env_of(&command, "DISPLAY"), None);
+
+        let mut command = Command::new("chromedriver");
+        let prepared = prepare_display_for(DisplayServer::Wayland("wayland-0".into()), &mode, &mut command)
+            .await
+            .unwrap();
+        assert_eq!(prepared.server, DisplayServer::X11(":42".into()));
+        assert_eq!(env_of(&command, "DISPLAY"), Some(Some(":42".into())));
+        assert_eq!(env_of(&command, "WAYLAND_DISPLAY"), Some(None));
+
+        // An existing display is used instead of starting Xvfb.
+        let mut command = Command::new("chromedriver");
+        let prepared = prepare_display_for(DisplayServer::X11(":0".into()), &DisplayMode::xvfb(), &mut command)
+            .await
+            .unwrap();
+        assert_eq!(prepared.server, DisplayServer::X11(":0".into()));
+        assert!(prepared.xvfb.is_none());
+
+        let mut command = Command::new("chromedriver");
+        let prepared = prepare_display_for(DisplayServer::None, &DisplayMode::Inherit, &mut command)
+            .await
+            .unwrap();
+        assert!(prepared.headless_hint);
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[tokio::test]
+    async fn test_wait_for_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("X99");
+
+        let mut exited = Command::new("true").spawn().unwrap();
+        let err = wait_for_socket(&mut exited, &socket, XVFB_READY_TIMEOUT).await.unwrap_err();
+        assert!(err.to_string().contains("exited"));
+
+        let mut running = Command::new("sleep").arg("5").kill_on_drop(true).spawn().unwrap();
+        let err = wait_for_socket(&mut running, &socket, Duration::from_millis(100)).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        let created = socket.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::fs::write(created, b"").unwrap();
+        });
+        wait_for_socket(&mut running, &socket, XVFB_READY_TIMEOUT).await.unwrap();
+    }
+
+    #[test]
+    fn test_display_mode_from_str() {
+        assert_eq!("inherit".parse::<DisplayMode>().unwrap(), DisplayMode::Inherit);
+        assert_eq!("xvfb".parse::<DisplayMode>().unwrap(), DisplayMode::xvfb());
+        assert_eq!(
+            "xvfb :7".parse::<DisplayMode>().unwrap(),
+            DisplayMode::Xvfb {
+                display: ":7".into(),
+                screen: "1920x1080x24".into()
+            }
+        );
+        assert_eq!(":99".parse::<DisplayMode>().unwrap(), DisplayMode::Display(":99".into()));
+        assert!("headless".parse::<DisplayMode>().is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_x11_socket() {
+        assert_eq!(x11_socket(":99.0"), Some(PathBuf::from("/tmp/.X11-unix/X99")));
+        assert_eq!(x11_socket("host:1"), None);
+    }
+}
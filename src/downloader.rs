@@ -1,43 +1,671 @@
 //! [TODO] Description...
 
-use crate::error::WebDriverError;
-use std::path::{Path, PathBuf};
-use tokio::fs::{self, File};
-use tokio::io::AsyncWriteExt;
+use crate::arch::{check_architecture, Arch};
+use crate::cache::{artifact_key, CacheStore, MetadataCache};
+use crate::endpoints::Endpoints;
+use crate::error::{ErrorKind, WebDriverError};
+use crate::events::{percentage, InstallEvent, InstallEventSender};
+#[cfg(feature = "tokio")]
+use crate::http::build_client;
+use crate::http::{HttpClient, HttpRequest, HttpResponse, NetworkPolicy, ProxyConfig, RootCertificate};
+use crate::lock::{InstallLock, DEFAULT_LOCK_TIMEOUT};
+use crate::observer::{CacheKind, InstallObserver};
+use crate::runtime::fs::{self, File};
+use crate::runtime::{self, CancellationToken};
+use crate::source::Credentials;
+use futures_util::future::Either;
+use futures_util::StreamExt;
+use ::http::header::{HeaderMap, HeaderName, HeaderValue};
+use ::http::StatusCode;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
+/// The step of an installation a [`DownloadProgress`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadPhase {
+    Downloading,
+    Extracting,
+    Finished,
+}
+
+/// A progress report of a download or installation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub phase: DownloadPhase,
+    /// Bytes downloaded so far.
+    pub downloaded: u64,
+    /// Size of the download, when the server announced it.
+    pub total: Option<u64>,
+}
+
+/// Callback receiving [`DownloadProgress`] reports.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(DownloadProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(DownloadProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn report(&self, phase: DownloadPhase, downloaded: u64, total: Option<u64>) {
+        (self.0)(DownloadProgress { phase, downloaded, total });
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Options controlling how driver archives are downloaded and extracted.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    /// Directory in which the temporary staging directory is created.
+    /// Defaults to the parent of the install directory (a sibling of it), so
+    /// large archives don't end up on a small system temp volume.
+    pub temp_dir: Option<PathBuf>,
+    /// When enabled, extracted files get a fixed modification time and the
+    /// installation reports a stable digest of the extracted tree.
+    pub deterministic: bool,
+    /// Credentials sent along with the download request.
+    pub credentials: Option<Credentials>,
+    /// Cache consulted before downloading, and filled after a download.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+    /// Receives progress reports while downloading and installing.
+    pub progress: Option<ProgressCallback>,
+    /// Receives the lifecycle events of the installation.
+    pub events: Option<InstallEventSender>,
+    /// Proxy for metadata and download requests. Without one, the proxy
+    /// environment variables are honored.
+    pub proxy: Option<ProxyConfig>,
+    /// A preconfigured client used for all requests instead of building one.
+    /// Takes precedence over [`DownloadOptions::proxy`]. Required without
+    /// the `tokio` feature.
+    pub client: Option<Arc<dyn HttpClient>>,
+    /// Root certificates trusted in addition to the system ones. Ignored
+    /// with a custom [`DownloadOptions::client`].
+    pub root_certificates: Vec<RootCertificate>,
+    /// Headers sent with every metadata and download request, e.g. the
+    /// `Authorization` header of an internal mirror. Ignored with a custom
+    /// [`DownloadOptions::client`].
+    pub headers: HeaderMap,
+    /// Overrides of the metadata endpoints and download locations.
+    pub endpoints: Endpoints,
+    /// Aborts the installation when cancelled. Partial downloads and
+    /// extracted files are removed.
+    pub cancellation: Option<CancellationToken>,
+    /// Expected hex encoded SHA-256 digest of the downloaded archive.
+    pub sha256: Option<String>,
+    /// Verifies the download against a `<url>.sha256` file published next to
+    /// it, when the server has one.
+    pub sidecar_checksum: bool,
+    /// Strips the `com.apple.quarantine` attribute from the installed driver
+    /// on macOS, so Gatekeeper doesn't block it.
+    pub remove_quarantine: bool,
+    /// Architecture the driver binary must be built for. Checked before the
+    /// driver is run for the first time.
+    pub expected_arch: Option<Arch>,
+    /// Directory the downloaded archive is copied to before extraction, so it
+    /// can be cached and installed again offline.
+    pub keep_archive: Option<PathBuf>,
+    /// How long to wait for a concurrent install into the same directory.
+    /// Defaults to [`DEFAULT_LOCK_TIMEOUT`].
+    pub lock_timeout: Option<Duration>,
+    /// Whether metadata and archives may be fetched over the network.
+    pub network: NetworkPolicy,
+    /// Cache of metadata documents such as the chromedriver version list.
+    /// Offline, cached metadata is used regardless of its age.
+    pub metadata_cache: Option<MetadataCache>,
+    /// How long connecting or a single read may stall before the request
+    /// fails. Ignored with a custom [`DownloadOptions::client`].
+    pub timeout: Option<Duration>,
+    /// Token for the GitHub API, raising its rate limit. Defaults to the
+    /// `GITHUB_TOKEN` environment variable.
+    pub github_token: Option<String>,
+    /// Receives timings, byte counts, retries and cache hits.
+    pub observer: Option<Arc<dyn InstallObserver>>,
+    /// How often a download failing with a transient error (see
+    /// [`WebDriverError::is_retryable`]) is retried. Defaults to
+    /// [`DEFAULT_RETRIES`]. Rate limited downloads are only retried when the
+    /// server's `Retry-After` asks to wait at most a minute.
+    pub retries: Option<u32>,
+}
+
+/// Environment variable holding a token for the GitHub API.
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// How often a failed download is retried by default.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+// Delay before the first retry of a download, growing linearly with every
+// further one.
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+// Rate limited downloads are retried when the server asks to wait at most
+// this long.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+impl DownloadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the directory used for the temporary staging directory.
+    pub fn temp_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(path.into());
+        self
+    }
+
+    /// Enables or disables the deterministic install mode.
+    pub fn deterministic(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled;
+        self
+    }
+
+    /// Sets the credentials sent along with the download request.
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets the cache used to store and reuse downloaded archives.
+    pub fn cache_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.cache_store = Some(store);
+        self
+    }
+
+    /// Sets the observer receiving the metrics of the installation.
+    pub fn observer(mut self, observer: Arc<dyn InstallObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Sets a callback receiving progress reports, e.g. to drive a progress bar.
+    pub fn on_progress(mut self, callback: impl Fn(DownloadProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Draws an indicatif progress bar while downloading and extracting.
+    #[cfg(feature = "progress-bar")]
+    pub fn progress_bar(mut self) -> Self {
+        self.progress = Some(crate::progress_bar::progress_bar_callback());
+        self
+    }
+
+    /// Sets the channel the lifecycle events of the installation are sent to.
+    pub fn events(mut self, sender: InstallEventSender) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Sends all requests through `proxy`.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Uses `client` for all requests, e.g. a reqwest client with custom TLS
+    /// settings or one of another HTTP stack.
+    pub fn client(mut self, client: impl HttpClient + 'static) -> Self {
+        self.client = Some(Arc::new(client));
+        self
+    }
+
+    /// Sends the header `name` with every request. `Authorization` values
+    /// are marked sensitive, keeping them out of debug output.
+    pub fn header(mut self, name: HeaderName, mut value: HeaderValue) -> Self {
+        if name == ::http::header::AUTHORIZATION || name == ::http::header::PROXY_AUTHORIZATION {
+            value.set_sensitive(true);
+        }
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system root certificates.
+    pub fn root_certificate(mut self, certificate: RootCertificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Sets the endpoint overrides, e.g. to use an internal mirror.
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sets a token that aborts the installation, e.g. on Ctrl+C.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets the expected SHA-256 digest (hex) of the download. A mismatch
+    /// fails the installation before anything is extracted.
+    pub fn sha256(mut self, digest: impl Into<String>) -> Self {
+        self.sha256 = Some(digest.into());
+        self
+    }
+
+    /// Enables verification against published `.sha256` sidecar files.
+    pub fn sidecar_checksum(mut self, enabled: bool) -> Self {
+        self.sidecar_checksum = enabled;
+        self
+    }
+
+    /// Sets the architecture the installed driver binary must be built for.
+    pub fn expected_arch(mut self, arch: Arch) -> Self {
+        self.expected_arch = Some(arch);
+        self
+    }
+
+    /// Keeps a copy of the downloaded archive in `dir`, under the file name
+    /// from the download URL (e.g. `chromedriver-linux64.zip`).
+    pub fn keep_archive(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.keep_archive = Some(dir.into());
+        self
+    }
+
+    /// Sets how long to wait for another process installing into the same
+    /// directory.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables removing the macOS quarantine attribute after installation.
+    pub fn remove_quarantine(mut self, enabled: bool) -> Self {
+        self.remove_quarantine = enabled;
+        self
+    }
+
+    /// Sets whether network requests are allowed. Offline installs only use
+    /// the [`DownloadOptions::cache_store`] and local files.
+    pub fn network(mut self, policy: NetworkPolicy) -> Self {
+        self.network = policy;
+        self
+    }
+
+    /// Caches metadata documents in `cache`, so back-to-back resolutions
+    /// don't download them again.
+    pub fn metadata_cache(mut self, cache: MetadataCache) -> Self {
+        self.metadata_cache = Some(cache);
+        self
+    }
+
+    /// Sets how long connecting or a single read may stall.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how often a download failing with a transient error is retried.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Authenticates GitHub API requests with `token`.
+    pub fn github_token(mut self, token: impl Into<String>) -> Self {
+        self.github_token = Some(token.into());
+        self
+    }
+
+    /// The configured GitHub token, or the one in `GITHUB_TOKEN`.
+    pub(crate) fn github_token_or_env(&self) -> Option<String> {
+        self.github_token
+            .clone()
+            .or_else(|| std::env::var(GITHUB_TOKEN_ENV).ok())
+            .filter(|token| !token.trim().is_empty())
+    }
+
+    /// The digest a download of `url` has to match: the configured one, or
+    /// the one from the sidecar file when enabled and available.
+    async fn expected_sha256(&self, url: &str) -> Result<Option<String>, WebDriverError> {
+        if self.sha256.is_some() || !self.sidecar_checksum || local_source_path(url).is_some() {
+            return Ok(self.sha256.clone());
+        }
+        let sidecar_url = format!("{}.sha256", url);
+        let response = self.http_client_for(&sidecar_url)?.send(HttpRequest::get(&sidecar_url)).await?;
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+        // Sidecar files look like `<digest>  <file name>`.
+        let body = response.text().await?;
+        Ok(body.split_whitespace().next().map(str::to_string))
+    }
+
+    /// The HTTP client for requests made on behalf of these options.
+    pub(crate) fn http_client(&self) -> Result<Arc<dyn HttpClient>, WebDriverError> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            #[cfg(feature = "tokio")]
+            None => Ok(Arc::new(build_client(self.proxy.as_ref(), &self.root_certificates, &self.headers, self.timeout)?)),
+            #[cfg(not(feature = "tokio"))]
+            None => Err(WebDriverError::Custom(
+                "No HTTP client; enable the `tokio` feature or set DownloadOptions::client".to_string(),
+            )),
+        }
+    }
+
+    /// The HTTP client for a request to `url`, unless the network policy
+    /// forbids requests.
+    pub(crate) fn http_client_for(&self, url: &str) -> Result<Arc<dyn HttpClient>, WebDriverError> {
+        if self.network == NetworkPolicy::Offline {
+            return Err(WebDriverError::OfflineUnavailable { url: url.to_string() });
+        }
+        self.http_client()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Runs `future` to completion, unless the installation is cancelled first.
+    async fn cancellable<T>(&self, future: impl Future<Output = T>) -> Result<T, WebDriverError> {
+        match &self.cancellation {
+            Some(token) => {
+                // The cancellation is polled first, so it wins over a ready
+                // output.
+                let cancelled = std::pin::pin!(token.cancelled());
+                match futures_util::future::select(cancelled, std::pin::pin!(future)).await {
+                    Either::Left(_) => Err(WebDriverError::Cancelled),
+                    Either::Right((output, _)) => Ok(output),
+                }
+            }
+            None => Ok(future.await),
+        }
+    }
+
+    fn report(&self, phase: DownloadPhase, downloaded: u64, total: Option<u64>) {
+        if let Some(progress) = &self.progress {
+            progress.report(phase, downloaded, total);
+        }
+    }
+
+    /// Sends an install event. A dropped receiver is not an error, the
+    /// installation doesn't depend on anyone listening.
+    pub(crate) fn emit(&self, event: InstallEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.unbounded_send(event);
+        }
+    }
+
+    /// Passes a measurement to the observer, if there is one.
+    pub(crate) fn observe(&self, measurement: impl FnOnce(&dyn InstallObserver)) {
+        if let Some(observer) = &self.observer {
+            measurement(observer.as_ref());
+        }
+    }
+
+    /// Resolves the directory the staging directory should be created in.
+    fn staging_parent(&self, install_path: &Path) -> PathBuf {
+        match &self.temp_dir {
+            Some(dir) => dir.clone(),
+            None => install_path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+}
+
 pub async fn download_and_unzip(
     url: &str,
     install_path: &Path,
     driver_name: &str,
 ) -> Result<PathBuf, WebDriverError> {
+    download_and_unzip_with_options(url, install_path, driver_name, &DownloadOptions::default()).await
+}
+
+/// The outcome of installing a driver archive.
+#[derive(Debug, Clone)]
+pub struct DriverInstallation {
+    /// Path to the driver executable.
+    pub driver_path: PathBuf,
+    /// Directory the archive was extracted into.
+    pub install_dir: PathBuf,
+    /// Hex encoded SHA-256 digest over the extracted tree (relative paths and
+    /// file contents). Only computed in deterministic mode, where it can be
+    /// used directly as a cache key.
+    pub digest: Option<String>,
+    /// The copy of the archive kept by [`DownloadOptions::keep_archive`].
+    pub kept_archive: Option<PathBuf>,
+}
+
+/// Modification time applied to every extracted file in deterministic mode
+/// (1980-01-01T00:00:00Z, the earliest timestamp a zip archive can hold).
+const DETERMINISTIC_MTIME: i64 = 315_532_800;
+
+/// Free space kept on top of the expected size when checking disk space.
+const DISK_SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+
+/// Extracted drivers are estimated at this multiple of the archive size.
+const EXTRACTION_RATIO: u64 = 3;
+
+/// Same as [`download_and_unzip`], but with explicit [`DownloadOptions`].
+pub async fn download_and_unzip_with_options(
+    url: &str,
+    install_path: &Path,
+    driver_name: &str,
+    options: &DownloadOptions,
+) -> Result<PathBuf, WebDriverError> {
+    install_archive(url, install_path, driver_name, options)
+        .await
+        .map(|installation| installation.driver_path)
+}
 
-    // --- 1. Create a temporary directory for the download.
-    let temp_dir = tempfile::Builder::new()
-        .prefix("webdriver-manager-")
-        .tempdir()
-        .map_err(|e| WebDriverError::IoError {
-            path: PathBuf::from("temp"),
+/// Downloads and extracts a driver archive, returning a [`DriverInstallation`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(url = %url, install_path = %install_path.display()), err)
+)]
+pub async fn install_archive(
+    url: &str,
+    install_path: &Path,
+    driver_name: &str,
+    options: &DownloadOptions,
+) -> Result<DriverInstallation, WebDriverError> {
+
+    // Concurrent installs into the same directory would corrupt each other.
+    let _lock = InstallLock::acquire(install_path, options.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT)).await?;
+
+    // --- 1. Stage the download at a path derived from the URL, so an
+    // interrupted install leaves a ".part" file the next one resumes.
+    let archive_path = partial_archive_path(&options.staging_parent(install_path), url);
+    if let Some(partial_dir) = archive_path.parent() {
+        fs::create_dir_all(partial_dir).await.map_err(|e| WebDriverError::IoError {
+            path: partial_dir.to_path_buf(),
             source: e,
         })?;
-    let temp_path = temp_dir.path();
-    let archive_path = temp_path.join("driver.zip");
+    }
+    // Installs into other directories may share the staging directory.
+    let _staging_lock = InstallLock::acquire(&archive_path, options.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT)).await?;
+    // The complete archive is only needed until it's extracted.
+    let _archive = RemoveOnDrop(archive_path.clone());
+    let url_format = ArchiveFormat::from_url(url);
+
+    // --- 2. Download the archive to the staging directory, unless the cache has it.
+    // The shared cache is best-effort: when it's unreachable, the archive is
+    // downloaded as if it weren't configured.
+    match &options.cache_store {
+        Some(store) => {
+            let key = artifact_key(url);
+            let hit = match store.fetch(&key, &archive_path).await {
+                Ok(hit) => hit,
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                Err(e) => {
+                    warn!(key = %key, error = %e, "artifact cache lookup failed, downloading");
+                    false
+                }
+            };
+            options.observe(|observer| observer.cache_lookup(CacheKind::Artifact, hit));
+            if hit {
+                debug!(key = %key, "archive found in the artifact cache");
+                // A shared cache is checked like a download.
+                if let Some(expected) = &options.expected_sha256(url).await? {
+                    let actual = sha256_file_async(&archive_path).await?;
+                    verify_sha256(&archive_path, expected, &actual).await?;
+                }
+            } else {
+                download_file_for_install(url, &archive_path, options, Some(install_path)).await?;
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                if let Err(e) = store.store(&key, &archive_path).await {
+                    warn!(key = %key, error = %e, "storing the archive in the artifact cache failed");
+                }
+            }
+        }
+        None => {
+            download_file_for_install(url, &archive_path, options, Some(install_path)).await?;
+        }
+    }
 
-    // --- 2. Download the zip file to the temporary directory.
-    download_file(url, &archive_path).await?;
+    // The archive is kept before extraction; it is useful even when the
+    // installation fails later on.
+    let kept_archive = match &options.keep_archive {
+        Some(dir) => Some(keep_archive_copy(&archive_path, dir, url).await?),
+        None => None,
+    };
 
-    // --- 3. Unzip the file into the final installation directory.
-    unzip_file(&archive_path, install_path).await?;
+    // --- 3. Extract the archive into the final installation directory.
+    // Downloads with a known size were checked before the transfer already;
+    // this covers cached archives and servers not sending a length.
+    let archive_size = fs::metadata(&archive_path).await.map(|m| m.len()).unwrap_or(0);
+    check_disk_space(install_path, archive_size.saturating_mul(EXTRACTION_RATIO).saturating_add(DISK_SPACE_MARGIN))?;
+    options.report(DownloadPhase::Extracting, archive_size, Some(archive_size));
+    options.emit(InstallEvent::Extracting);
+    if options.is_cancelled() {
+        return Err(WebDriverError::Cancelled);
+    }
+    // Without an archive extension in the URL the content decides; anything
+    // that isn't a known archive is taken to be the bare executable.
+    let format = match url_format {
+        Some(format) => Some(format),
+        None => ArchiveFormat::detect(&archive_path)?,
+    };
+    let extracted = match format {
+        Some(format) => {
+            format
+                .extract_cancellable(&archive_path, install_path, options.cancellation.clone())
+                .await?
+        }
+        None => install_raw_binary(&archive_path, install_path, driver_name).await?,
+    };
 
     // --- 4. Find the driver executable within the unzipped files.
     // This is necessary because archives might contain a top-level directory.
-    find_driver_executable(install_path, driver_name)
+    let driver_path = find_driver_executable(install_path, driver_name)?;
+    make_executable(&driver_path)?;
+    if let Some(expected) = options.expected_arch {
+        check_architecture(&driver_path, expected)?;
+    }
+    if options.remove_quarantine {
+        remove_quarantine(&driver_path).await?;
+    }
+
+    // --- 5. Normalize the extracted tree and digest it in deterministic mode.
+    let digest = if options.deterministic {
+        Some(normalize_and_digest(install_path, extracted).await?)
+    } else {
+        None
+    };
+
+    options.report(DownloadPhase::Finished, archive_size, Some(archive_size));
+
+    Ok(DriverInstallation {
+        driver_path,
+        install_dir: install_path.to_path_buf(),
+        digest,
+        kept_archive,
+    })
+}
+
+/// Name of the directory below the staging parent holding in-progress
+/// downloads of [`install_archive`].
+pub(crate) const PARTIAL_DIR: &str = ".partial";
+
+/// Where [`install_archive`] stages the download of `url`: a stable name
+/// derived from its [`artifact_key`], so a retry finds the `.part` file.
+fn partial_archive_path(staging_parent: &Path, url: &str) -> PathBuf {
+    staging_parent.join(PARTIAL_DIR).join(artifact_key(url).replace('/', "-"))
+}
+
+/// Removes a file when dropped, whichever way the function using it returns.
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Copies the downloaded archive into `dir`, named after the last segment of
+/// the download URL.
+async fn keep_archive_copy(archive_path: &Path, dir: &Path, url: &str) -> Result<PathBuf, WebDriverError> {
+    let url_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit(['/', '\\']).next())
+        .filter(|name| !name.is_empty() && *name != "..");
+    let file_name = match url_name {
+        Some(name) => std::ffi::OsString::from(name),
+        None => archive_path.file_name().unwrap_or_default().to_os_string(),
+    };
+    let kept = dir.join(file_name);
+
+    fs::create_dir_all(dir).await.map_err(|e| WebDriverError::IoError {
+        path: dir.to_path_buf(),
+        source: e,
+    })?;
+    fs::copy(archive_path, &kept).await.map_err(|e| WebDriverError::IoError {
+        path: kept.clone(),
+        source: e,
+    })?;
+    Ok(kept)
 }
 
 /// Downloads a file from a given URL and saves it to a destination path.
 /// 
-/// This function streams the response body to a file asynchronously.
-pub async fn download_file(url: &str, dest_path: &Path) -> Result<(), WebDriverError> {
+/// This function streams the response body to a file asynchronously, chunk
+/// by chunk, so the archive is never held in memory as a whole. Returns the
+/// number of bytes written.
+///
+/// `file://` URLs and plain paths are copied from the local file system,
+/// for air-gapped machines with pre-fetched archives.
+pub async fn download_file(url: &str, dest_path: &Path) -> Result<u64, WebDriverError> {
+    download_file_with_options(url, dest_path, &DownloadOptions::default()).await
+}
+
+/// Same as [`download_file`], but with explicit [`DownloadOptions`].
+pub async fn download_file_with_options(
+    url: &str,
+    dest_path: &Path,
+    options: &DownloadOptions,
+) -> Result<u64, WebDriverError> {
+    download_file_for_install(url, dest_path, options, None).await
+}
+
+/// Downloads like [`download_file_with_options`]. When the archive will be
+/// extracted into `install_path`, the room for the extracted files is
+/// checked as soon as the size is known, before any bytes are transferred.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(url = %url, dest = %dest_path.display()), err)
+)]
+async fn download_file_for_install(
+    url: &str,
+    dest_path: &Path,
+    options: &DownloadOptions,
+    install_path: Option<&Path>,
+) -> Result<u64, WebDriverError> {
 
     // Ensure parent directory exists.
     if let Some(parent) = dest_path.parent() {
@@ -49,23 +677,454 @@ pub async fn download_file(url: &str, dest_path: &Path) -> Result<(), WebDriverE
             })?;
     }
 
-    // Make the GET request.
-    let response = reqwest::get(url).await?.error_for_status()?;
+    let expected_sha256 = options.expected_sha256(url).await?;
+
+    if let Some(source_path) = local_source_path(url) {
+        let copied = copy_local_file(&source_path, dest_path, options).await?;
+        if let Some(expected) = &expected_sha256 {
+            verify_sha256(dest_path, expected, &sha256_file_async(dest_path).await?).await?;
+        }
+        return Ok(copied);
+    }
+
+    // The download goes to a ".part" file first. If one is left over from an
+    // interrupted download, only the missing bytes are requested.
+    let part_path = part_path(dest_path);
+    let resume_from = fs::metadata(&part_path).await.map(|m| m.len()).unwrap_or(0);
+    if resume_from > 0 {
+        debug!(resume_from, "resuming an interrupted download");
+    }
+    let started = Instant::now();
 
-    // Create the destination file.
-    let mut dest_file = File::create(dest_path).await.map_err(|e| WebDriverError::IoError { 
-        path: dest_path.to_path_buf(), 
-        source: e, 
+    // Make the GET request. A connection dropped while streaming the body is
+    // retried like a failed request, resuming after the bytes on disk.
+    let client = options.http_client_for(url)?;
+    let send = async |range_start: u64| {
+        let mut request = HttpRequest::get(url);
+        if let Some(credentials) = &options.credentials {
+            request = credentials.apply(request)?;
+        }
+        if range_start > 0 {
+            let range = HeaderValue::try_from(format!("bytes={}-", range_start)).expect("a valid Range header");
+            request = request.header(::http::header::RANGE, range);
+        }
+        client.send(request).await
+    };
+    let max_retries = options.retries.unwrap_or(DEFAULT_RETRIES);
+    let mut retries = 0;
+    let mut range_start = resume_from;
+    let mut received = 0;
+    let (written, hasher) = loop {
+        let (error, retry_after) = match options.cancellable(send(range_start)).await? {
+            Ok(response) if range_start > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+                // The partial file doesn't fit the remote file (anymore), start
+                // over. That isn't a failure, so it doesn't count as a retry.
+                range_start = 0;
+                continue;
+            }
+            Ok(response) => {
+                let retry_after = retry_after(response.headers());
+                match response.error_for_status() {
+                    Ok(response) => {
+                        // Servers without range support answer with the whole file.
+                        let offset = if response.status() == StatusCode::PARTIAL_CONTENT { range_start } else { 0 };
+                        if let Some(remaining) = response.content_length() {
+                            check_disk_space(dest_path, remaining.saturating_add(DISK_SPACE_MARGIN))?;
+                            if let Some(install_path) = install_path {
+                                let archive_size = remaining.saturating_add(offset);
+                                check_disk_space(install_path, archive_size.saturating_mul(EXTRACTION_RATIO).saturating_add(DISK_SPACE_MARGIN))?;
+                            }
+                        }
+                        match receive_body(response, offset, &part_path, expected_sha256.is_some(), options, &mut received).await {
+                            Ok(done) => break done,
+                            Err(e) => (e, None),
+                        }
+                    }
+                    Err(e) => (WebDriverError::from(e), retry_after),
+                }
+            }
+            Err(e) => (WebDriverError::from(e), None),
+        };
+        if !error.is_retryable() || retries >= max_retries {
+            return Err(error);
+        }
+        // Waiting out a rate limit only helps when the server says how long
+        // it lasts, and only when that's soon.
+        let delay = match error.kind() {
+            ErrorKind::RateLimited => match retry_after.filter(|delay| *delay <= MAX_RETRY_AFTER) {
+                Some(delay) => delay,
+                None => return Err(error),
+            },
+            _ => RETRY_DELAY * (retries + 1),
+        };
+        warn!(url = %url, error = %error, "download failed, retrying");
+        options.cancellable(runtime::sleep(delay)).await?;
+        retries += 1;
+        options.observe(|observer| observer.retried(url, retries));
+        range_start = fs::metadata(&part_path).await.map(|m| m.len()).unwrap_or(0);
+    };
+
+    fs::rename(&part_path, dest_path).await.map_err(|e| WebDriverError::IoError {
+        path: dest_path.to_path_buf(),
+        source: e,
     })?;
 
+    if let (Some(expected), Some(hasher)) = (&expected_sha256, hasher) {
+        verify_sha256(dest_path, expected, &format!("{:x}", hasher.finalize())).await?;
+    }
+
+    let elapsed = started.elapsed();
+    options.observe(|observer| observer.downloaded(url, received, elapsed));
+    info!(url = %url, bytes = received, elapsed_ms = elapsed.as_millis() as u64, "downloaded");
+    Ok(written)
+}
+
+/// Streams the body of `response` into the `.part` file, appending to the
+/// first `offset` bytes already there. Returns the size of the file and,
+/// with `hash`, the digest of the whole file. `received` counts the bytes
+/// transferred, also when the stream breaks off.
+async fn receive_body(
+    response: HttpResponse,
+    offset: u64,
+    part_path: &Path,
+    hash: bool,
+    options: &DownloadOptions,
+    received: &mut u64,
+) -> Result<(u64, Option<Sha256>), WebDriverError> {
+    // Create the destination file, or append to the partial one.
+    let io_err = |e| WebDriverError::IoError {
+        path: part_path.to_path_buf(),
+        source: e,
+    };
+    let mut dest_file = if offset > 0 {
+        File::append(part_path).await
+    } else {
+        File::create(part_path).await
+    }
+    .map_err(io_err)?;
+
+    // The digest is computed while streaming; a resumed download starts
+    // with the bytes already on disk.
+    let mut hasher = hash.then(Sha256::new);
+    if let Some(hasher) = hasher.as_mut().filter(|_| offset > 0) {
+        let partial = part_path.to_path_buf();
+        *hasher = runtime::spawn_blocking(move || {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut std::fs::File::open(partial)?, &mut hasher)?;
+            Ok(hasher)
+        })
+        .await
+        .map_err(io_err)?;
+    }
+
     // Stream the content to the file.
-    let content = response.bytes().await?;
-    dest_file.write_all(&content).await.map_err(|e| WebDriverError::IoError { 
-        path: dest_path.to_path_buf(), 
-        source: e, 
+    let total = response.content_length().map(|len| len + offset);
+    let mut stream = response.bytes_stream();
+    let mut written: u64 = offset;
+    let mut last_pct = percentage(written, total);
+    options.report(DownloadPhase::Downloading, written, total);
+    options.emit(InstallEvent::Downloading { pct: last_pct });
+    loop {
+        let next = match options.cancellable(stream.next()).await {
+            Ok(next) => next,
+            Err(e) => {
+                // A cancelled download is not resumed, drop what we have.
+                drop(dest_file);
+                let _ = fs::remove_file(part_path).await;
+                return Err(e);
+            }
+        };
+        let Some(chunk) = next else {
+            break;
+        };
+        let chunk = chunk?;
+        dest_file.write_all(&chunk).await.map_err(io_err)?;
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&chunk);
+        }
+        written += chunk.len() as u64;
+        *received += chunk.len() as u64;
+        options.report(DownloadPhase::Downloading, written, total);
+
+        // Only send an event when the percentage changes, not for every chunk.
+        let pct = percentage(written, total);
+        if pct != last_pct {
+            options.emit(InstallEvent::Downloading { pct });
+            last_pct = pct;
+        }
+    }
+    dest_file.flush().await.map_err(io_err)?;
+    Ok((written, hasher))
+}
+
+/// The delay a `Retry-After` header in seconds asks for.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers.get(::http::header::RETRY_AFTER)?.to_str().ok()?;
+    secs.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Fails with [`WebDriverError::InsufficientDiskSpace`] when the volume
+/// holding `path` has less than `required` bytes available. The path doesn't
+/// have to exist yet. When the space can't be determined, the check passes.
+fn check_disk_space(path: &Path, required: u64) -> Result<(), WebDriverError> {
+    let Some(existing) = path.ancestors().find(|p| p.exists()) else {
+        return Ok(());
+    };
+    match fs4::available_space(existing) {
+        Ok(available) if available < required => Err(WebDriverError::InsufficientDiskSpace {
+            path: existing.to_path_buf(),
+            required,
+            available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Fails with [`WebDriverError::ChecksumMismatch`] when the digests differ,
+/// removing the file so it can't be used by accident.
+async fn verify_sha256(path: &Path, expected: &str, actual: &str) -> Result<(), WebDriverError> {
+    if expected.trim().eq_ignore_ascii_case(actual) {
+        debug!(path = %path.display(), sha256 = actual, "checksum verified");
+        return Ok(());
+    }
+    warn!(path = %path.display(), expected = expected.trim(), actual, "checksum mismatch");
+    let _ = fs::remove_file(path).await;
+    Err(WebDriverError::ChecksumMismatch {
+        path: path.to_path_buf(),
+        expected: expected.trim().to_lowercase(),
+        actual: actual.to_string(),
+    })
+}
+
+/// The local path behind a `file://` URL or a plain path, `None` for remote URLs.
+pub(crate) fn local_source_path(url: &str) -> Option<PathBuf> {
+    let Some(rest) = url.strip_prefix("file://") else {
+        return (!url.contains("://")).then(|| PathBuf::from(url));
+    };
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+
+    // file:///C:/drivers/chromedriver.zip
+    let is_drive_path = path.len() > 2 && path.as_bytes()[2] == b':' && path.starts_with('/');
+    Some(PathBuf::from(if is_drive_path { &path[1..] } else { path }))
+}
+
+/// Copies a pre-fetched archive in place of a download.
+async fn copy_local_file(source: &Path, dest_path: &Path, options: &DownloadOptions) -> Result<u64, WebDriverError> {
+    let copied = fs::copy(source, dest_path).await.map_err(|e| WebDriverError::IoError {
+        path: source.to_path_buf(),
+        source: e,
     })?;
+    options.report(DownloadPhase::Downloading, copied, Some(copied));
+    options.emit(InstallEvent::Downloading { pct: Some(100) });
+    Ok(copied)
+}
 
-    Ok(())
+/// Fetches a metadata document from a URL or a local file.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(options), err))]
+pub(crate) async fn fetch_text(url: &str, options: &DownloadOptions) -> Result<String, WebDriverError> {
+    if let Some(path) = local_source_path(url) {
+        return fs::read_to_string(&path).await.map_err(|e| WebDriverError::IoError { path, source: e });
+    }
+    let started = Instant::now();
+    let text = match &options.metadata_cache {
+        Some(cache) => fetch_cached_text(url, cache, options).await?,
+        None => options.http_client_for(url)?.send(HttpRequest::get(url)).await?.error_for_status()?.text().await?,
+    };
+    options.observe(|observer| observer.metadata_fetched(url, started.elapsed()));
+    Ok(text)
+}
+
+/// Fetches and parses a JSON document from a URL or a local file.
+pub(crate) async fn fetch_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+    options: &DownloadOptions,
+) -> Result<T, WebDriverError> {
+    let body = fetch_text(url, options).await?;
+    serde_json::from_str(&body).map_err(|e| WebDriverError::json_parse(url, body.as_bytes(), e))
+}
+
+/// Fetches a metadata document through `cache`. A fresh cached copy is used
+/// as is; a stale one is revalidated with `If-None-Match`, and still used
+/// when offline or when the server can't be reached.
+async fn fetch_cached_text(url: &str, cache: &MetadataCache, options: &DownloadOptions) -> Result<String, WebDriverError> {
+    let cached = cache.get(url);
+    if let Some(cached) = &cached
+        && (cached.is_fresh(cache.time_to_live()) || options.network == NetworkPolicy::Offline)
+    {
+        debug!("metadata cache hit");
+        options.observe(|observer| observer.cache_lookup(CacheKind::Metadata, true));
+        return Ok(cached.body.clone());
+    }
+
+    let client = options.http_client_for(url)?;
+    let mut request = HttpRequest::get(url);
+    if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref())
+        && let Ok(etag) = HeaderValue::from_str(etag)
+    {
+        request = request.header(::http::header::IF_NONE_MATCH, etag);
+    }
+    let response = match client.send(request).await.and_then(|response| response.error_for_status()) {
+        Ok(response) => response,
+        Err(e) => return cached.map(|cached| cached.body).ok_or(e.into()),
+    };
+
+    if response.status() == StatusCode::NOT_MODIFIED
+        && let Some(cached) = cached
+    {
+        cache.touch(url);
+        debug!("metadata not modified");
+        options.observe(|observer| observer.cache_lookup(CacheKind::Metadata, true));
+        return Ok(cached.body);
+    }
+
+    let etag = response
+        .headers()
+        .get(::http::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().await?;
+    options.observe(|observer| observer.cache_lookup(CacheKind::Metadata, false));
+    // A cache that can't be written doesn't fail the resolution.
+    let _ = cache.store(url, &body, etag.as_deref());
+    Ok(body)
+}
+
+/// The path a download is written to until it is complete: `<dest>.part`.
+fn part_path(dest_path: &Path) -> PathBuf {
+    let mut part = dest_path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// The archive formats driver downloads come in.
+///
+/// `.tar.xz` and `.tar.bz2` need the `xz` and `bzip2` features; without them
+/// extracting such an archive fails with
+/// [`WebDriverError::UnsupportedArchiveFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarXz,
+    TarBz2,
+}
+
+impl ArchiveFormat {
+    /// Recognizes the format from the file name at the end of a URL or path.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let name = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+        [
+            (".zip", ArchiveFormat::Zip),
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".tar.xz", ArchiveFormat::TarXz),
+            (".txz", ArchiveFormat::TarXz),
+            (".tar.bz2", ArchiveFormat::TarBz2),
+            (".tbz2", ArchiveFormat::TarBz2),
+        ]
+        .into_iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, format)| format)
+    }
+
+    /// Recognizes the format of a downloaded file from its magic bytes.
+    /// Returns `None` for anything that is not a known archive.
+    pub fn detect(path: &Path) -> Result<Option<Self>, WebDriverError> {
+        let mut magic = Vec::with_capacity(6);
+        std::fs::File::open(path)
+            .and_then(|file| file.take(6).read_to_end(&mut magic))
+            .map_err(|e| WebDriverError::IoError {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+
+        Ok(match magic.as_slice() {
+            [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some(ArchiveFormat::Zip),
+            [0x1f, 0x8b, ..] => Some(ArchiveFormat::TarGz),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00] => Some(ArchiveFormat::TarXz),
+            [b'B', b'Z', b'h', ..] => Some(ArchiveFormat::TarBz2),
+            _ => None,
+        })
+    }
+
+    /// The canonical file extension, without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarXz => "tar.xz",
+            ArchiveFormat::TarBz2 => "tar.bz2",
+        }
+    }
+
+    /// Extracts an archive of this format and returns the paths of all
+    /// extracted entries.
+    pub async fn extract(&self, archive_path: &Path, extract_to: &Path) -> Result<Vec<PathBuf>, WebDriverError> {
+        self.extract_cancellable(archive_path, extract_to, None).await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(format = ?self, archive = %archive_path.display()), err)
+    )]
+    async fn extract_cancellable(
+        &self,
+        archive_path: &Path,
+        extract_to: &Path,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<Vec<PathBuf>, WebDriverError> {
+        match self {
+            ArchiveFormat::Zip => extract_zip(archive_path, extract_to, cancellation).await,
+            _ => extract_tar(*self, archive_path, extract_to, cancellation).await,
+        }
+    }
+
+    /// Wraps the archive file in the decompressor of a tar format.
+    fn tar_decoder(&self, file: std::fs::File) -> Result<Box<dyn Read + Send>, WebDriverError> {
+        match self {
+            ArchiveFormat::TarGz => Ok(Box::new(flate2::read::GzDecoder::new(file))),
+            #[cfg(feature = "xz")]
+            ArchiveFormat::TarXz => Ok(Box::new(liblzma::read::XzDecoder::new(file))),
+            #[cfg(feature = "bzip2")]
+            ArchiveFormat::TarBz2 => Ok(Box::new(bzip2::read::BzDecoder::new(file))),
+            #[cfg(not(feature = "xz"))]
+            ArchiveFormat::TarXz => Err(WebDriverError::UnsupportedArchiveFormat(
+                "tar.xz (enable the `xz` feature)".to_string(),
+            )),
+            #[cfg(not(feature = "bzip2"))]
+            ArchiveFormat::TarBz2 => Err(WebDriverError::UnsupportedArchiveFormat(
+                "tar.bz2 (enable the `bzip2` feature)".to_string(),
+            )),
+            ArchiveFormat::Zip => Err(WebDriverError::UnsupportedArchiveFormat("zip is not a tar format".to_string())),
+        }
+    }
+}
+
+/// Installs a downloaded bare executable under the driver name and makes it
+/// executable.
+async fn install_raw_binary(
+    download_path: &Path,
+    install_path: &Path,
+    driver_name: &str,
+) -> Result<Vec<PathBuf>, WebDriverError> {
+    let io_err = |path: &Path| {
+        let path = path.to_path_buf();
+        move |e| WebDriverError::IoError { path, source: e }
+    };
+
+    fs::create_dir_all(install_path).await.map_err(io_err(install_path))?;
+    let driver_path = install_path.join(executable_file_name(driver_name));
+    fs::copy(download_path, &driver_path).await.map_err(io_err(&driver_path))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755))
+            .await
+            .map_err(io_err(&driver_path))?;
+    }
+
+    Ok(vec![driver_path])
 }
 
 /// Decompresses a .zip archive to a specified directory.
@@ -73,11 +1132,20 @@ pub async fn download_file(url: &str, dest_path: &Path) -> Result<(), WebDriverE
 /// The core zip logic is synchronous, so we wrap it in `spawn_blocking` to
 /// avoid blocking the Tokio runtime.
 pub async fn unzip_file(archive_path: &Path, extract_to: &Path) -> Result<(), WebDriverError> {
+    extract_zip(archive_path, extract_to, None).await.map(|_| ())
+}
+
+/// Extracts a .zip archive and returns the paths of all extracted entries.
+async fn extract_zip(
+    archive_path: &Path,
+    extract_to: &Path,
+    cancellation: Option<CancellationToken>,
+) -> Result<Vec<PathBuf>, WebDriverError> {
 
     let archive_path_buf = archive_path.to_path_buf();
     let extract_to_buf = extract_to.to_path_buf();
 
-    tokio::task::spawn_blocking(move || {
+    runtime::spawn_blocking(move || {
         let file = std::fs::File::open(&archive_path_buf).map_err(|e| WebDriverError::IoError { 
             path: archive_path_buf.clone(), 
             source: e, 
@@ -94,31 +1162,36 @@ pub async fn unzip_file(archive_path: &Path, extract_to: &Path) -> Result<(), We
             source: e,
         })?;
 
+        let mut extracted = Vec::with_capacity(archive.len());
         for i in 0..archive.len() {
+            if cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                remove_extracted(&extracted);
+                return Err(WebDriverError::Cancelled);
+            }
             let mut file = archive.by_index(i).map_err(|e| WebDriverError::ZipError {
                 path: archive_path_buf.clone(),
                 source: e,
             })?;
 
-            let outpath = match file.enclosed_name() {
-                Some(path) => extract_to_buf.join(path),
-                None => continue,
-            };
+            let outpath = safe_entry_path(&extract_to_buf, Path::new(file.name()))?;
+            if let Some(mode) = file.unix_mode() {
+                check_entry_type(Path::new(file.name()), mode)?;
+            }
 
             if file.name().ends_with('/') {
                 std::fs::create_dir_all(&outpath).map_err(|e| WebDriverError::IoError {
-                    path: outpath,
+                    path: outpath.clone(),
                     source: e,
                 })?;
 
             } else {
-                if let Some(p) = outpath.parent() {
-                    if !p.exists() {
-                        std::fs::create_dir_all(p).map_err(|e| WebDriverError::IoError {
-                            path: p.to_path_buf(),
-                            source: e,
-                        })?;
-                    }
+                if let Some(p) = outpath.parent()
+                    && !p.exists()
+                {
+                    std::fs::create_dir_all(p).map_err(|e| WebDriverError::IoError {
+                        path: p.to_path_buf(),
+                        source: e,
+                    })?;
                 }
 
                 let mut outfile = std::fs::File::create(&outpath).map_err(|e| WebDriverError::IoError {
@@ -127,7 +1200,7 @@ pub async fn unzip_file(archive_path: &Path, extract_to: &Path) -> Result<(), We
                 })?;
 
                 std::io::copy(&mut file, &mut outfile).map_err(|e| WebDriverError::IoError {
-                    path: outpath,
+                    path: outpath.clone(),
                     source: e,
                 })?;
             }
@@ -136,41 +1209,247 @@ pub async fn unzip_file(archive_path: &Path, extract_to: &Path) -> Result<(), We
             #[cfg(unix)]
             {
                 use std::os::unix::fs::PermissionsExt;
+                // Only the permission bits; never setuid, setgid or sticky.
                 if let Some(mode) = file.unix_mode() {
-                    std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode)).mapp_err(|e| WebDriverError::IoError {
-                        path: outpath,
+                    std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode & 0o777)).map_err(|e| WebDriverError::IoError {
+                        path: outpath.clone(),
                         source: e,
                     })?;
                 }
             }
+
+            extracted.push(outpath);
         }
-        Ok(())
+        Ok(extracted)
+    })
+    .await
+
+}
+
+/// Decompresses a .tar.gz archive to a specified directory.
+pub async fn untar_gz_file(archive_path: &Path, extract_to: &Path) -> Result<(), WebDriverError> {
+    ArchiveFormat::TarGz.extract(archive_path, extract_to).await.map(|_| ())
+}
+
+/// Extracts a tar archive and returns the paths of all extracted entries.
+async fn extract_tar(
+    format: ArchiveFormat,
+    archive_path: &Path,
+    extract_to: &Path,
+    cancellation: Option<CancellationToken>,
+) -> Result<Vec<PathBuf>, WebDriverError> {
+
+    let archive_path_buf = archive_path.to_path_buf();
+    let extract_to_buf = extract_to.to_path_buf();
+
+    runtime::spawn_blocking(move || {
+        let io_err = |path: &Path| {
+            let path = path.to_path_buf();
+            move |e| WebDriverError::IoError { path, source: e }
+        };
+
+        let file = std::fs::File::open(&archive_path_buf).map_err(io_err(&archive_path_buf))?;
+        let mut archive = tar::Archive::new(format.tar_decoder(file)?);
+
+        std::fs::create_dir_all(&extract_to_buf).map_err(io_err(&extract_to_buf))?;
+
+        let mut extracted = Vec::new();
+        for entry in archive.entries().map_err(io_err(&archive_path_buf))? {
+            if cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                remove_extracted(&extracted);
+                return Err(WebDriverError::Cancelled);
+            }
+            let mut entry = entry.map_err(io_err(&archive_path_buf))?;
+            let entry_path = entry.path().map_err(io_err(&archive_path_buf))?.into_owned();
+
+            match entry.header().entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous | tar::EntryType::Directory => {}
+                // Metadata records, not files.
+                tar::EntryType::XGlobalHeader | tar::EntryType::XHeader => continue,
+                _ => return Err(unsafe_entry(&entry_path, "links and special files are not extracted")),
+            }
+
+            let outpath = safe_entry_path(&extract_to_buf, &entry_path)?;
+            if let Some(parent) = outpath.parent() {
+                std::fs::create_dir_all(parent).map_err(io_err(parent))?;
+            }
+            entry.unpack(&outpath).map_err(io_err(&outpath))?;
+            extracted.push(outpath);
+        }
+        Ok(extracted)
+    })
+    .await
+}
+
+/// Resolves where an archive entry is extracted to. Entries that would end
+/// up outside `root` are rejected: absolute paths, `..` components, and
+/// paths through symlinks that already exist below `root`.
+fn safe_entry_path(root: &Path, name: &Path) -> Result<PathBuf, WebDriverError> {
+    let mut relative = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return Err(unsafe_entry(name, "the path escapes the destination")),
+        }
+    }
+    let path = root.join(&relative);
+
+    if path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+        return Err(unsafe_entry(name, "the destination is a symlink"));
+    }
+
+    // The closest existing ancestor must resolve to a directory below root.
+    let canonical_root = dunce::canonicalize(root).map_err(|e| WebDriverError::IoError {
+        path: root.to_path_buf(),
+        source: e,
+    })?;
+    let ancestor = path.ancestors().skip(1).find(|p| p.exists()).unwrap_or(root);
+    let escapes = dunce::canonicalize(ancestor).map_or(true, |p| !p.starts_with(&canonical_root));
+    if escapes {
+        return Err(unsafe_entry(name, "the path escapes the destination"));
+    }
+    Ok(path)
+}
+
+/// Rejects symlinks, devices, FIFOs and sockets based on the Unix mode of a
+/// zip entry. Entries without a file type are regular files.
+fn check_entry_type(name: &Path, mode: u32) -> Result<(), WebDriverError> {
+    const S_IFMT: u32 = 0o170_000;
+    const S_IFREG: u32 = 0o100_000;
+    const S_IFDIR: u32 = 0o040_000;
+    match mode & S_IFMT {
+        0 | S_IFREG | S_IFDIR => Ok(()),
+        _ => Err(unsafe_entry(name, "links and special files are not extracted")),
+    }
+}
+
+fn unsafe_entry(name: &Path, reason: &str) -> WebDriverError {
+    WebDriverError::UnsafeArchiveEntry {
+        path: name.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Removes the entries of a partially extracted archive, files first and
+/// directories once they are empty. Failures are ignored; this is cleanup
+/// after an error that is already being reported.
+fn remove_extracted(extracted: &[PathBuf]) {
+    for path in extracted.iter().rev() {
+        if path.is_dir() {
+            let _ = std::fs::remove_dir(path);
+        } else {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Sets a fixed modification time on every extracted entry and computes
+/// the [`tree_digest`] of `install_path`.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+async fn normalize_and_digest(
+    install_path: &Path,
+    extracted: Vec<PathBuf>,
+) -> Result<String, WebDriverError> {
+    let install_path = install_path.to_path_buf();
+    runtime::spawn_blocking(move || {
+        let mtime = filetime::FileTime::from_unix_time(DETERMINISTIC_MTIME, 0);
+        for path in &extracted {
+            filetime::set_file_mtime(path, mtime).map_err(|e| WebDriverError::IoError {
+                path: path.clone(),
+                source: e,
+            })?;
+        }
+        digest_tree(&install_path)
     })
     .await
-    .unwrap() // Propagate panics from the blocking task.
+}
+
+/// Computes a SHA-256 digest over the tree below `install_path`, as
+/// reported by [`DriverInstallation::digest`].
+///
+/// Entries are hashed in sorted order using their path relative to
+/// `install_path` (with `/` separators), so the digest does not depend on
+/// archive entry order, the host platform, or the install location.
+pub(crate) async fn tree_digest(install_path: &Path) -> Result<String, WebDriverError> {
+    let install_path = install_path.to_path_buf();
+    runtime::spawn_blocking(move || digest_tree(&install_path)).await
+}
+
+fn digest_tree(install_path: &Path) -> Result<String, WebDriverError> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(install_path).min_depth(1) {
+        let entry = entry.map_err(|e| WebDriverError::IoError {
+            path: install_path.to_path_buf(),
+            source: e.into(),
+        })?;
+        entries.push(entry.into_path());
+    }
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for path in &entries {
+        let relative = relative_slash_path(path, install_path);
+        if path.is_file() {
+            hasher.update(b"F ");
+            hasher.update(relative.as_bytes());
+            hasher.update(b"\0");
+            hasher.update(sha256_file(path)?.as_bytes());
+        } else {
+            hasher.update(b"D ");
+            hasher.update(relative.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Computes the hex encoded SHA-256 digest of a file.
+pub(crate) fn sha256_file(path: &Path) -> Result<String, WebDriverError> {
+    let mut file = std::fs::File::open(path).map_err(|e| WebDriverError::IoError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| WebDriverError::IoError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// [`sha256_file`] for async callers, hashing on a blocking thread.
+pub(crate) async fn sha256_file_async(path: &Path) -> Result<String, WebDriverError> {
+    let path = path.to_path_buf();
+    runtime::spawn_blocking(move || sha256_file(&path)).await
+}
 
+/// Returns `path` relative to `base` with `/` separators on every platform.
+pub(crate) fn relative_slash_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 /// Searches a directory for the driver executable file.
-fn find_driver_executable(search_path: &Path, driver_name: &str) -> Result<PathBuf, WebDriverError> {
+pub(crate) fn find_driver_executable(search_path: &Path, driver_name: &str) -> Result<PathBuf, WebDriverError> {
 
-    let driver_exe_name = if cfg!(target_os = "windows") {
-        format!("{}.exe", driver_name)
-    } else {
-        driver_name.to_string()
-    };
+    let driver_exe_name = executable_file_name(driver_name);
 
     for entry in WalkDir::new(search_path) {
         let entry = entry.map_err(|e| WebDriverError::IoError {
             path: e.path().unwrap_or(search_path).to_path_buf(),
             source: e.into_io_error().unwrap_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::Other, "WalkDir error")
+                std::io::Error::other("WalkDir error")
             }),
         })?;
-        if let Some(file_name) = entry.path().file_name().and_then(|n| n.to_str()) {
-            if file_name == driver_exe_name {
-                return Ok(entry.path().to_path_buf());
-            }
+        if let Some(file_name) = entry.path().file_name().and_then(|n| n.to_str())
+            && file_name == driver_exe_name
+        {
+            return Ok(entry.path().to_path_buf());
         }
     }
 
@@ -178,4 +1457,803 @@ fn find_driver_executable(search_path: &Path, driver_name: &str) -> Result<PathB
         "Could not find '{}' in the extracted files.",
         driver_exe_name
     )))
-}
\ No newline at end of file
+}
+
+/// Adds the execute bits to the driver binary. Archives without Unix modes
+/// (e.g. zips created on Windows) otherwise leave it non-executable.
+fn make_executable(path: &Path) -> Result<(), WebDriverError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let io_err = |e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let mode = std::fs::metadata(path).map_err(io_err)?.permissions().mode();
+        if mode & 0o111 != 0o111 {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode | 0o555)).map_err(io_err)?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Removes the `com.apple.quarantine` extended attribute on macOS. A file
+/// without the attribute is fine; other platforms have nothing to do.
+async fn remove_quarantine(path: &Path) -> Result<(), WebDriverError> {
+    if !cfg!(target_os = "macos") {
+        return Ok(());
+    }
+
+    let mut command = std::process::Command::new("xattr");
+    command.arg("-d").arg("com.apple.quarantine").arg(path);
+    let output = runtime::output(&mut command).await.map_err(|e| WebDriverError::CommandExecutionError {
+        command: format!("{:?}", command),
+        source: e,
+    })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() || stderr.contains("No such xattr") {
+        Ok(())
+    } else {
+        Err(WebDriverError::Custom(format!(
+            "Failed to remove the quarantine attribute from '{}': {}",
+            path.display(),
+            stderr.trim()
+        )))
+    }
+}
+
+/// The file name of a driver executable on this platform.
+fn executable_file_name(driver_name: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.exe", driver_name)
+    } else {
+        driver_name.to_string()
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{HttpError, HttpResponse};
+    #[cfg(feature = "tokio")]
+    use crate::test_server::{Response, TestServer};
+
+    #[test]
+    fn test_staging_dir_defaults_to_install_sibling() {
+        let options = DownloadOptions::new();
+        let install_path = Path::new("/opt/drivers/chromedriver");
+        assert_eq!(options.staging_parent(install_path), PathBuf::from("/opt/drivers"));
+
+        let options = DownloadOptions::new().temp_dir("/var/tmp/wdm");
+        assert_eq!(options.staging_parent(install_path), PathBuf::from("/var/tmp/wdm"));
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_digest_is_stable() {
+        // Create the same tree twice, in a different order.
+        let mut digests = Vec::new();
+        for order in [["b.txt", "a/c.txt"], ["a/c.txt", "b.txt"]] {
+            let root = tempfile::tempdir().unwrap();
+            let mut extracted = Vec::new();
+            for name in order {
+                let path = root.path().join(name);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, name).unwrap();
+                extracted.push(path);
+            }
+
+            digests.push(normalize_and_digest(root.path(), extracted).await.unwrap());
+
+            let modified = std::fs::metadata(root.path().join("b.txt")).unwrap().modified().unwrap();
+            assert_eq!(filetime::FileTime::from_system_time(modified).unix_seconds(), DETERMINISTIC_MTIME);
+        }
+
+        assert_eq!(digests[0].len(), 64);
+        assert_eq!(digests[0], digests[1]);
+
+        // The tree on disk has the same digest.
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("a")).unwrap();
+        std::fs::write(root.path().join("a/c.txt"), "a/c.txt").unwrap();
+        std::fs::write(root.path().join("b.txt"), "b.txt").unwrap();
+        assert_eq!(tree_digest(root.path()).await.unwrap(), digests[0]);
+    }
+
+    /// Serves `body` over HTTP on a local port and returns its URL.
+    #[cfg(feature = "tokio")]
+    async fn serve(body: Vec<u8>) -> String {
+        serve_with_ranges(body, false).await.0
+    }
+
+    /// Like [`serve`], optionally answering `Range` requests. Also returns
+    /// the server, to inspect the received requests.
+    #[cfg(feature = "tokio")]
+    async fn serve_with_ranges(body: Vec<u8>, ranges: bool) -> (String, TestServer) {
+        let server = TestServer::start(move |request| {
+            let start = request
+                .header("range")
+                .and_then(|range| range.strip_prefix("bytes="))
+                .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok())
+                .filter(|_| ranges);
+            match start {
+                Some(start) if start >= body.len() => Response::status("416 Range Not Satisfiable"),
+                Some(start) => Response::ok(&body[start..])
+                    .with_status("206 Partial Content")
+                    .header("Content-Range", format!("bytes {}-{}/{}", start, body.len() - 1, body.len())),
+                None => Response::ok(body.clone()),
+            }
+        })
+        .await;
+        (server.url("/driver.zip"), server)
+    }
+
+    #[tokio::test]
+    async fn test_archive_formats() {
+        assert_eq!(ArchiveFormat::from_url("https://x/geckodriver-v0.36.0-linux64.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_url("https://x/driver.TAR.XZ?sig=1"), Some(ArchiveFormat::TarXz));
+        assert_eq!(ArchiveFormat::from_url("https://x/driver.tbz2"), Some(ArchiveFormat::TarBz2));
+        assert_eq!(ArchiveFormat::from_url("https://x/chromedriver"), None);
+
+        // A tar.gz round trip through the shared tar extraction.
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("driver.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(&archive_path).unwrap(), Default::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(6);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, "geckodriver", &b"binary"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let extract_to = dir.path().join("out");
+        let extracted = ArchiveFormat::TarGz.extract(&archive_path, &extract_to).await.unwrap();
+        assert_eq!(extracted, vec![extract_to.join("geckodriver")]);
+
+        #[cfg(not(feature = "xz"))]
+        assert!(matches!(
+            ArchiveFormat::TarXz.extract(&archive_path, &extract_to).await,
+            Err(WebDriverError::UnsupportedArchiveFormat(_))
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_install_raw_binary() {
+        let url = serve(b"\x7fELF raw driver".to_vec()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("install");
+
+        let url = url.replace("driver.zip", "chromedriver");
+        let installation = install_archive(&url, &install_path, "chromedriver", &DownloadOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(installation.driver_path, install_path.join(executable_file_name("chromedriver")));
+        assert_eq!(std::fs::read(&installation.driver_path).unwrap(), b"\x7fELF raw driver");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&installation.driver_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+    }
+
+    /// A shared cache that is down.
+    #[derive(Debug, Default)]
+    struct UnreachableStore {
+        calls: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CacheStore for UnreachableStore {
+        async fn fetch(&self, _key: &str, _dest: &Path) -> Result<bool, WebDriverError> {
+            self.calls.lock().unwrap().push("fetch");
+            Err(WebDriverError::Custom("connection refused".into()))
+        }
+
+        async fn store(&self, _key: &str, _src: &Path) -> Result<(), WebDriverError> {
+            self.calls.lock().unwrap().push("store");
+            Err(WebDriverError::Custom("connection refused".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_cache_store_is_skipped() {
+        let client = MemoryClient {
+            body: b"\x7fELF raw driver".to_vec(),
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("install");
+        let store = Arc::new(UnreachableStore::default());
+        let options = DownloadOptions::new().client(client).cache_store(store.clone());
+
+        let url = "https://mirror.example/chromedriver";
+        let installation = install_archive(url, &install_path, "chromedriver", &options).await.unwrap();
+        assert_eq!(std::fs::read(&installation.driver_path).unwrap(), b"\x7fELF raw driver");
+        assert_eq!(*store.calls.lock().unwrap(), ["fetch", "store"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_cached_archive_is_checked_against_the_sidecar() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/chromedriver.sha256" => {
+                Response::ok(format!("{:x}  chromedriver\n", Sha256::digest(b"\x7fELF raw driver")))
+            }
+            _ => Response::ok(&b"\x7fELF raw driver"[..]),
+        })
+        .await;
+        let url = server.url("/chromedriver");
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(crate::cache::LocalCacheStore::new(dir.path().join("store")));
+        let options = DownloadOptions::new().sidecar_checksum(true).cache_store(store.clone());
+
+        let tampered = dir.path().join("tampered");
+        std::fs::write(&tampered, b"\x7fELF evil driver").unwrap();
+        store.store(&artifact_key(&url), &tampered).await.unwrap();
+        let result = install_archive(&url, &dir.path().join("install"), "chromedriver", &options).await;
+        assert!(matches!(result, Err(WebDriverError::ChecksumMismatch { .. })), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn test_extraction_rejects_unsafe_entries() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let extract_to = dir.path().join("out");
+        let options = zip::write::SimpleFileOptions::default();
+
+        let zip_slip = dir.path().join("slip.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_slip).unwrap());
+        writer.start_file("../evil", options).unwrap();
+        writer.write_all(b"evil").unwrap();
+        writer.finish().unwrap();
+
+        let zip_link = dir.path().join("link.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_link).unwrap());
+        writer.add_symlink("chromedriver", "/etc/passwd", options).unwrap();
+        writer.finish().unwrap();
+
+        let tar_link = dir.path().join("link.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(&tar_link).unwrap(), Default::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "geckodriver", "/etc/passwd").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        for (format, archive) in [
+            (ArchiveFormat::Zip, &zip_slip),
+            (ArchiveFormat::Zip, &zip_link),
+            (ArchiveFormat::TarGz, &tar_link),
+        ] {
+            let result = format.extract(archive, &extract_to).await;
+            assert!(matches!(result, Err(WebDriverError::UnsafeArchiveEntry { .. })), "{:?}", archive);
+        }
+        assert!(!dir.path().join("evil").exists());
+        assert!(safe_entry_path(&extract_to, Path::new("/etc/passwd")).is_err());
+        assert_eq!(safe_entry_path(&extract_to, Path::new("./a/b")).unwrap(), extract_to.join("a/b"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_installed_driver_is_executable() {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("chromedriver-linux64.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(0o644);
+        for name in ["chromedriver", "LICENSE"] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let install_path = dir.path().join("install");
+        let url = archive.to_str().unwrap();
+        let installation = install_archive(url, &install_path, "chromedriver", &DownloadOptions::default())
+            .await
+            .unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&installation.driver_path), 0o755);
+        assert_eq!(mode(&install_path.join("LICENSE")), 0o644);
+    }
+
+    #[test]
+    fn test_check_disk_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not/yet/created");
+        assert!(check_disk_space(&missing, 1).is_ok());
+        assert!(matches!(
+            check_disk_space(&missing, u64::MAX),
+            Err(WebDriverError::InsufficientDiskSpace { path, .. }) if path == dir.path()
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_install_space_is_checked_before_downloading() {
+        let dir = tempfile::tempdir().unwrap();
+        let available = fs4::available_space(dir.path()).unwrap();
+        // Fits the staging volume, but not three times over once extracted.
+        let announced = available / 2;
+        let server = TestServer::start(move |_| Response::ok(vec![0u8; 16]).content_length(announced)).await;
+
+        let install_path = dir.path().join("chromedriver").join("1.0.0").join("linux64");
+        let error = install_archive(&server.url("/driver.zip"), &install_path, "chromedriver", &DownloadOptions::new())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, WebDriverError::InsufficientDiskSpace { required, .. } if *required > announced * 2),
+            "{error}"
+        );
+        // Nothing was written to the staging directory.
+        let archive_path = partial_archive_path(&dir.path().join("chromedriver").join("1.0.0"), &server.url("/driver.zip"));
+        assert_eq!(std::fs::metadata(part_path(&archive_path)).map_or(0, |m| m.len()), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_keep_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve(b"\x7fELF raw driver".to_vec()).await.replace("driver.zip", "chromedriver?token=1");
+        let options = DownloadOptions::new().keep_archive(dir.path().join("archives"));
+
+        let installation = install_archive(&url, &dir.path().join("install"), "chromedriver", &options).await.unwrap();
+        let kept = installation.kept_archive.unwrap();
+        assert_eq!(kept, dir.path().join("archives").join("chromedriver"));
+        assert_eq!(std::fs::read(&kept).unwrap(), b"\x7fELF raw driver");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_file_streams_to_disk() {
+        let body: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let url = serve(body.clone()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("nested").join("driver.zip");
+
+        let written = download_file(&url, &dest).await.unwrap();
+        assert_eq!(written, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[test]
+    fn test_custom_client_takes_precedence() {
+        let options = DownloadOptions::new().proxy(ProxyConfig::new("not a url"));
+        assert!(options.http_client().is_err());
+        assert!(options.client(MemoryClient::default()).http_client().is_ok());
+    }
+
+    /// Serves `body` from memory and records the requests.
+    #[derive(Debug, Default)]
+    struct MemoryClient {
+        body: Vec<u8>,
+        requests: std::sync::Mutex<Vec<HttpRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for MemoryClient {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+            let url = request.url.clone();
+            self.requests.lock().unwrap().push(request);
+            let mut headers = HeaderMap::new();
+            headers.insert(::http::header::CONTENT_LENGTH, self.body.len().into());
+            let chunks: Vec<_> = self.body.chunks(4).map(|chunk| Ok(bytes::Bytes::copy_from_slice(chunk))).collect();
+            Ok(HttpResponse::new(url, StatusCode::OK, headers, futures_util::stream::iter(chunks)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_through_a_custom_client() {
+        let client = Arc::new(MemoryClient {
+            body: b"driver archive".to_vec(),
+            ..Default::default()
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let options = DownloadOptions::new().client(client.clone()).credentials(Credentials::Basic {
+            username: "user".into(),
+            password: Some("pass".into()),
+        });
+
+        let url = "https://mirror.example/driver.zip";
+        assert_eq!(download_file_with_options(url, &dest, &options).await.unwrap(), 14);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"driver archive");
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests[0].url, url);
+        assert_eq!(requests[0].headers["authorization"], "Basic dXNlcjpwYXNz");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_headers_are_sent() {
+        let (url, server) = serve_with_ranges(vec![1u8; 100], false).await;
+        let dir = tempfile::tempdir().unwrap();
+        let options = DownloadOptions::new()
+            .header(::http::header::AUTHORIZATION, HeaderValue::from_static("Bearer secret"))
+            .header(HeaderName::from_static("x-jfrog-art-api"), HeaderValue::from_static("key"));
+
+        download_file_with_options(&url, &dir.path().join("driver.zip"), &options).await.unwrap();
+        let request = &server.requests()[0];
+        assert_eq!(request.header("authorization"), Some("bearer secret"));
+        assert_eq!(request.header("x-jfrog-art-api"), Some("key"));
+        assert!(!format!("{:?}", options).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_download_copies_local_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("prefetched.zip");
+        std::fs::write(&source, b"archive").unwrap();
+
+        let file_url = format!("file://{}", source.display());
+        for url in [source.to_string_lossy().to_string(), file_url] {
+            let dest = dir.path().join("out").join("driver.zip");
+            assert_eq!(download_file(&url, &dest).await.unwrap(), 7);
+            assert_eq!(std::fs::read(&dest).unwrap(), b"archive");
+        }
+
+        assert_eq!(
+            local_source_path("file:///C:/drivers/chromedriver.zip"),
+            Some(PathBuf::from("C:/drivers/chromedriver.zip"))
+        );
+        assert_eq!(local_source_path("https://example.com/chromedriver.zip"), None);
+    }
+
+    /// Serves `body` with `etag`, answering matching `If-None-Match`
+    /// requests with 304. Returns the URL and the number of full responses.
+    #[cfg(feature = "tokio")]
+    async fn serve_with_etag(body: &'static str, etag: &'static str) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        let full_responses = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = full_responses.clone();
+        let server = TestServer::start(move |request| {
+            if request.header("if-none-match") == Some(etag) {
+                return Response::status("304 Not Modified");
+            }
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Response::ok(body).header("ETag", etag)
+        })
+        .await;
+        (server.url("/versions.json"), full_responses)
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_fetch_json_uses_the_metadata_cache() {
+        let (url, full_responses) = serve_with_etag(r#"{"versions":[]}"#, "\"v1\"").await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MetadataCache::new(dir.path());
+
+        let options = DownloadOptions::new().metadata_cache(cache.clone());
+        for _ in 0..2 {
+            let json: serde_json::Value = fetch_json(&url, &options).await.unwrap();
+            assert_eq!(json["versions"], serde_json::json!([]));
+        }
+        assert_eq!(full_responses.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Expired documents are revalidated instead of downloaded again.
+        let options = DownloadOptions::new().metadata_cache(cache.ttl(Duration::ZERO));
+        let _: serde_json::Value = fetch_json(&url, &options).await.unwrap();
+        assert_eq!(full_responses.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Offline, even stale metadata is used.
+        let options = options.network(NetworkPolicy::Offline);
+        let _: serde_json::Value = fetch_json(&url, &options).await.unwrap();
+        let uncached: Result<serde_json::Value, _> = fetch_json("https://example.com/other.json", &options).await;
+        assert!(matches!(uncached, Err(WebDriverError::OfflineUnavailable { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_json_reports_the_body() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Versions {
+            versions: Vec<String>,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("versions.json");
+        let body = format!(r#"{{"milestones": [{}]}}"#, vec!["1"; 200].join(","));
+        std::fs::write(&path, &body).unwrap();
+
+        let url = path.display().to_string();
+        let error = fetch_json::<Versions>(&url, &DownloadOptions::new()).await.unwrap_err();
+        let WebDriverError::JsonParseError { url: error_url, snippet, .. } = &error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(error_url, &url);
+        assert!(snippet.starts_with(r#"{"milestones": [1,1"#));
+        assert!(snippet.ends_with('…') && snippet.chars().count() == 201);
+        assert!(error.to_string().contains("(body: {\"milestones\""));
+    }
+
+    #[tokio::test]
+    async fn test_offline_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("prefetched.zip");
+        std::fs::write(&source, b"archive").unwrap();
+        let options = DownloadOptions::new().network(NetworkPolicy::Offline);
+
+        let dest = dir.path().join("out").join("driver.zip");
+        let result = download_file_with_options("https://example.com/chromedriver.zip", &dest, &options).await;
+        assert!(matches!(result, Err(WebDriverError::OfflineUnavailable { url }) if url == "https://example.com/chromedriver.zip"));
+        assert_eq!(download_file_with_options(&source.to_string_lossy(), &dest, &options).await.unwrap(), 7);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_cancelled_download_removes_partial_file() {
+        let url = serve(vec![7u8; 4 * 1024 * 1024]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+
+        // Cancel as soon as the first chunk arrived.
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        let options = DownloadOptions::new().cancellation(token).on_progress(move |progress| {
+            if progress.downloaded > 0 {
+                cancel.cancel();
+            }
+        });
+
+        let result = download_file_with_options(&url, &dest, &options).await;
+        assert!(matches!(result, Err(WebDriverError::Cancelled)));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_checksum_is_verified() {
+        let url = serve(b"archive".to_vec()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let digest = format!("{:x}", Sha256::digest(b"archive"));
+
+        let options = DownloadOptions::new().sha256(digest.to_uppercase());
+        download_file_with_options(&url, &dest, &options).await.unwrap();
+
+        let options = DownloadOptions::new().sha256("00".repeat(32));
+        let result = download_file_with_options(&url, &dest, &options).await;
+        assert!(matches!(result, Err(WebDriverError::ChecksumMismatch { actual, .. }) if actual == digest));
+        assert!(!dest.exists());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let body: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+
+        // A range capable server only sends the missing bytes.
+        let (url, server) = serve_with_ranges(body.clone(), true).await;
+        std::fs::write(part_path(&dest), &body[..40_000]).unwrap();
+        assert_eq!(download_file(&url, &dest).await.unwrap(), body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part_path(&dest).exists());
+        assert_eq!(server.requests()[0].header("range"), Some("bytes=40000-"));
+
+        // Without range support the whole file is downloaded again.
+        let (url, _) = serve_with_ranges(body.clone(), false).await;
+        std::fs::write(part_path(&dest), b"stale").unwrap();
+        download_file(&url, &dest).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_interrupted_install_is_resumed() {
+        let body = b"\x7fELF raw driver, long enough to be split in two".to_vec();
+        let sha256 = format!("{:x}", Sha256::digest(&body));
+        let dir = tempfile::tempdir().unwrap();
+        let install_path = dir.path().join("chromedriver").join("1.0.0").join("linux64");
+        let (_, server) = serve_with_ranges(body.clone(), true).await;
+        let url = server.url("/1.0.0/chromedriver");
+
+        // What an install that was killed halfway leaves behind.
+        let archive_path = partial_archive_path(&dir.path().join("chromedriver").join("1.0.0"), &url);
+        std::fs::create_dir_all(archive_path.parent().unwrap()).unwrap();
+        std::fs::write(part_path(&archive_path), &body[..20]).unwrap();
+
+        let options = DownloadOptions::new().sha256(sha256);
+        let installation = install_archive(&url, &install_path, "chromedriver", &options).await.unwrap();
+        assert_eq!(std::fs::read(&installation.driver_path).unwrap(), body);
+        assert_eq!(server.requests()[0].header("range"), Some("bytes=20-"));
+        assert!(!archive_path.exists() && !part_path(&archive_path).exists());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_is_observed() {
+        let body = vec![3u8; 10_000];
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let observer = Arc::new(crate::observer::tests::RecordingObserver::default());
+        let options = DownloadOptions::new().observer(observer.clone());
+
+        // A partial file as large as the remote one is refused and the
+        // download starts over, which isn't a retry of a failed request.
+        let (url, server) = serve_with_ranges(body.clone(), true).await;
+        std::fs::write(part_path(&dest), &body).unwrap();
+        download_file_with_options(&url, &dest, &options).await.unwrap();
+
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(*observer.0.lock().unwrap(), [format!("downloaded {} 10000", url)]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let failures = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = failures.clone();
+        let server = TestServer::start(move |_| {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                Response::status("503 Service Unavailable")
+            } else {
+                Response::ok(vec![5u8; 1000])
+            }
+        })
+        .await;
+        let url = server.url("/driver.zip");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let observer = Arc::new(crate::observer::tests::RecordingObserver::default());
+        let options = DownloadOptions::new().observer(observer.clone());
+
+        assert_eq!(download_file_with_options(&url, &dest, &options).await.unwrap(), 1000);
+        assert_eq!(
+            *observer.0.lock().unwrap(),
+            [format!("retried {} 1", url), format!("retried {} 2", url), format!("downloaded {} 1000", url)]
+        );
+
+        // Once the retries are used up, the error is returned.
+        failures.store(0, std::sync::atomic::Ordering::SeqCst);
+        let error = download_file_with_options(&url, &dest, &options.clone().retries(1)).await.unwrap_err();
+        assert!(error.is_retryable(), "{error}");
+        assert_eq!(server.requests().len(), 5);
+
+        // Errors that retrying can't fix are returned right away.
+        let missing = TestServer::start(|_| Response::status("404 Not Found")).await;
+        assert!(download_file_with_options(&missing.url("/driver.zip"), &dest, &options).await.is_err());
+        assert_eq!(missing.requests().len(), 1);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_broken_off_download_is_resumed() {
+        let body: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let first = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let server = TestServer::start({
+            let body = body.clone();
+            move |request| match request.header("range").and_then(|range| range.strip_prefix("bytes=")) {
+                Some(range) => {
+                    let start = range.trim_end_matches('-').parse::<usize>().unwrap();
+                    Response::ok(&body[start..]).with_status("206 Partial Content")
+                }
+                // The connection drops after half of the announced body.
+                None if first.swap(false, std::sync::atomic::Ordering::SeqCst) => {
+                    Response::ok(&body[..500]).content_length(1000)
+                }
+                None => Response::ok(body.clone()),
+            }
+        })
+        .await;
+        let url = server.url("/driver.zip");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let observer = Arc::new(crate::observer::tests::RecordingObserver::default());
+        let options = DownloadOptions::new().observer(observer.clone());
+
+        assert_eq!(download_file_with_options(&url, &dest, &options).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("range"), Some("bytes=500-"));
+        assert_eq!(
+            *observer.0.lock().unwrap(),
+            [format!("retried {} 1", url), format!("downloaded {} 1000", url)]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_rate_limits_are_waited_out_when_short() {
+        let limited = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let server = TestServer::start({
+            let limited = limited.clone();
+            move |_| {
+                if limited.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                    Response::status("429 Too Many Requests").header("Retry-After", 0)
+                } else {
+                    Response::ok(vec![6u8; 100])
+                }
+            }
+        })
+        .await;
+        let url = server.url("/driver.zip");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+
+        assert_eq!(download_file_with_options(&url, &dest, &DownloadOptions::new()).await.unwrap(), 100);
+        assert_eq!(server.requests().len(), 2);
+
+        // Without a reset time, or a distant one, the rate limit is returned.
+        for retry_after in [None, Some(3600)] {
+            let server = TestServer::start(move |_| match retry_after {
+                Some(secs) => Response::status("429 Too Many Requests").header("Retry-After", secs),
+                None => Response::status("429 Too Many Requests"),
+            })
+            .await;
+            let error = download_file_with_options(&server.url("/driver.zip"), &dest, &DownloadOptions::new())
+                .await
+                .unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::RateLimited);
+            assert_eq!(server.requests().len(), 1);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_refused_resume_does_not_use_up_retries() {
+        let body = vec![4u8; 1000];
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("driver.zip");
+        let (url, server) = serve_with_ranges(body.clone(), true).await;
+        std::fs::write(part_path(&dest), &body).unwrap();
+
+        // Without any retries left, restarting the refused resume still works.
+        let options = DownloadOptions::new().retries(0);
+        assert_eq!(download_file_with_options(&url, &dest, &options).await.unwrap(), 1000);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_progress_is_reported() {
+        let body = vec![7u8; 100_000];
+        let url = serve(body.clone()).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let options = DownloadOptions::new().on_progress(move |progress| sink.lock().unwrap().push(progress));
+        download_file_with_options(&url, &dir.path().join("driver.zip"), &options).await.unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.first().unwrap().downloaded, 0);
+        let last = reports.last().unwrap();
+        assert_eq!((last.phase, last.downloaded, last.total), (DownloadPhase::Downloading, 100_000, Some(100_000)));
+        assert!(reports.windows(2).all(|w| w[0].downloaded <= w[1].downloaded));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_download_events_are_sent() {
+        let url = serve(vec![1u8; 50_000]).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let (sender, mut receiver) = crate::events::install_event_channel();
+        let options = DownloadOptions::new().events(sender);
+        download_file_with_options(&url, &dir.path().join("driver.zip"), &options).await.unwrap();
+        drop(options);
+
+        let mut events = Vec::new();
+        while let Some(event) = receiver.next().await {
+            events.push(event);
+        }
+        assert_eq!(events.first(), Some(&InstallEvent::Downloading { pct: Some(0) }));
+        assert_eq!(events.last(), Some(&InstallEvent::Downloading { pct: Some(100) }));
+    }
+}
@@ -1,19 +1,336 @@
 //! [TODO] Description...
 
 use crate::error::WebDriverError;
-use crate::browser::get_browser_version;
-use crate::downloader::{download_and_unzip};
+use crate::events::InstallEvent;
+use crate::arch::Arch;
+use crate::browser::{
+    browser_arch, channel_browser_name, chromium_version, find_browser_path, get_browser_version,
+    get_windows_browser_version_from_wsl, Browser, BrowserChannel,
+};
+use crate::downloader::{download_and_unzip_with_options, fetch_json, fetch_text, DownloadOptions};
+use crate::source::DriverSource;
+use crate::version::{Version, VersionReq, VersionSpec};
 use crate::WebDriverManager;
+use crate::runtime::{self, OnceCell};
 use async_trait::async_trait;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
 
 // The main URL for the new JSON endpoints.
-const CHROMEDRIVER_URLS_ENDPOINT: &str = 
+pub(crate) const CHROMEDRIVER_URLS_ENDPOINT: &str = 
     "https://googlechromelabs.github.io/chrome-for-testing/known-good-versions-with-downloads.json";
 
+// The newest version of every Chrome release channel.
+pub(crate) const CHROMEDRIVER_CHANNELS_ENDPOINT: &str =
+    "https://googlechromelabs.github.io/chrome-for-testing/last-known-good-versions-with-downloads.json";
+
+// The newest patch release of every Chrome build (`major.minor.build`).
+pub(crate) const CHROMEDRIVER_BUILDS_ENDPOINT: &str =
+    "https://googlechromelabs.github.io/chrome-for-testing/latest-patch-versions-per-build-with-downloads.json";
+
+// The newest release of every Chrome milestone (major version).
+pub(crate) const CHROMEDRIVER_MILESTONES_ENDPOINT: &str =
+    "https://googlechromelabs.github.io/chrome-for-testing/latest-versions-per-milestone-with-downloads.json";
+
+// The storage of the chromedriver builds before Chrome for Testing.
+pub(crate) const CHROMEDRIVER_LEGACY_ENDPOINT: &str = "https://chromedriver.storage.googleapis.com";
+
+// Prefix of the Chrome for Testing download URLs.
+const CFT_DOWNLOAD_BASE: &str = "https://storage.googleapis.com/chrome-for-testing-public";
+
+// The newest chromedriver per milestone, in the format of the per-milestone
+// JSON without the downloads. Regenerated from that JSON at release time and
+// only used when no endpoint can be reached.
+const BUNDLED_MILESTONES: &str = include_str!("chromedriver_milestones.json");
+
+// The first Chrome major version published through Chrome for Testing.
+const FIRST_CFT_MAJOR: u32 = 115;
+
+/// How a browser version is mapped to a chromedriver release.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolutionStrategy {
+    /// The newest known-good version sharing the browser's
+    /// `major.minor.build`.
+    #[default]
+    KnownGoodVersions,
+    /// The entry of the browser's exact `major.minor.build` in the
+    /// latest-patch-versions-per-build list. Versions without a build fall
+    /// back to the known-good lookup.
+    LatestPatchPerBuild,
+}
+
 /// Public struct for managing Chromedriver.
-pub struct ChromeDriver;
+#[derive(Debug, Clone, Default)]
+pub struct ChromeDriver {
+    browser: Browser,
+    channel: BrowserChannel,
+    source: DriverSource,
+    download_options: DownloadOptions,
+    wsl_windows_browser: bool,
+    browser_path: Option<PathBuf>,
+    arch: Option<Arch>,
+    strategy: ResolutionStrategy,
+    metadata: MetadataMemo,
+    // The architecture of the browser, read from its binary at most once.
+    browser_arch: Arc<OnceLock<Option<Arch>>>,
+}
+
+/// The parsed Chrome for Testing documents, fetched at most once per manager
+/// so resolving, looking up the URL and installing share one request.
+#[derive(Debug, Clone, Default)]
+struct MetadataMemo {
+    known_good: Arc<OnceCell<KnownGoodVersions>>,
+    channels: Arc<OnceCell<LastKnownGoodVersions>>,
+    builds: Arc<OnceCell<LatestPatchVersionsPerBuild>>,
+    milestones: Arc<OnceCell<LatestVersionsPerMilestone>>,
+}
+
+impl ChromeDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A manager installing chromedriver for a Chromium-based browser other
+    /// than Chrome, such as [`Browser::Brave`].
+    pub fn for_browser(browser: Browser) -> Self {
+        Self {
+            browser,
+            ..Self::default()
+        }
+    }
+
+    /// Targets a Chrome release channel (Beta, Dev or Canary) instead of
+    /// the stable release.
+    pub fn with_channel(mut self, channel: BrowserChannel) -> Self {
+        self.channel = channel;
+        self.browser_arch = Arc::default();
+        self
+    }
+
+    /// Picks the chromedriver build for the architecture of the browser at
+    /// `path` instead of the detected browser. Set automatically from
+    /// [`crate::SetupOptions::browser_path`].
+    pub fn with_browser_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.browser_path = Some(path.into());
+        self.browser_arch = Arc::default();
+        self
+    }
+
+    /// Inside WSL, drives the browser installed on the Windows host instead
+    /// of a Linux one: the browser is looked up below `/mnt/c` and the
+    /// `win64` chromedriver is installed.
+    pub fn with_wsl_windows_browser(mut self, enabled: bool) -> Self {
+        self.wsl_windows_browser = enabled;
+        self
+    }
+
+    /// Installs the chromedriver build for `arch` instead of the architecture
+    /// of the detected browser, e.g. `Arch::X86_64` on Apple Silicon to
+    /// force `mac-x64`.
+    pub fn with_arch(mut self, arch: Arch) -> Self {
+        self.arch = Some(arch);
+        self
+    }
+
+    /// Sets how browser versions are mapped to chromedriver releases.
+    pub fn with_resolution_strategy(mut self, strategy: ResolutionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets the source the driver archives are downloaded from.
+    pub fn with_source(mut self, source: DriverSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Sets the options used when downloading and extracting the driver.
+    pub fn with_download_options(mut self, options: DownloadOptions) -> Self {
+        self.download_options = options;
+        // The endpoints may have changed.
+        self.metadata = MetadataMemo::default();
+        self
+    }
+
+    /// The newest chromedriver of a Chrome milestone (major version), for
+    /// callers that only know e.g. "we test Chrome 126". Milestones below
+    /// 115 are looked up in the legacy storage.
+    pub async fn get_driver_version_for_milestone(&self, major: u32) -> Result<String, WebDriverError> {
+        if major < FIRST_CFT_MAJOR {
+            return get_legacy_chromedriver_version(&self.download_options, &major.to_string()).await;
+        }
+
+        let options = &self.download_options;
+        let response = self
+            .metadata
+            .milestones
+            .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_milestones_url(), options).await })
+            .await?;
+        response
+            .milestones
+            .get(&major.to_string())
+            .map(|latest| latest.version.clone())
+            .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+                browser_version: major.to_string(),
+                platform: self.driver_platform(),
+            })
+    }
+
+    /// Resolves `(driver_version, url)` for a browser version or another
+    /// [`VersionSpec`], taking the configured source into account.
+    async fn resolve(&self, spec: &str) -> Result<(String, String), WebDriverError> {
+        let platform = self.platform()?;
+        let browser_version = match spec.parse::<VersionSpec>()? {
+            VersionSpec::Browser(browser_version) => browser_version,
+            VersionSpec::Exact(driver_version) => {
+                let url = if is_legacy_version(&driver_version) {
+                    let legacy_platform = legacy_platform(platform, &driver_version).ok_or_else(|| {
+                        WebDriverError::DriverUrlNotFound {
+                            driver_version: driver_version.clone(),
+                            platform: platform.to_string(),
+                        }
+                    })?;
+                    format!(
+                        "{}/{}/chromedriver_{}.zip",
+                        self.download_options.endpoints.chromedriver_legacy_url(),
+                        driver_version,
+                        legacy_platform
+                    )
+                } else {
+                    get_chromedriver_exact_download_url(&self.download_options, &self.metadata, &driver_version, platform)
+                        .await?
+                };
+                return Ok(self.apply_source(driver_version, url, platform));
+            }
+            VersionSpec::Range(req) => {
+                let (driver_version, url) =
+                    get_chromedriver_range_download_url(&self.download_options, &self.metadata, &req, platform).await?;
+                return Ok(self.apply_source(driver_version, url, platform));
+            }
+            latest => {
+                let channel = match latest {
+                    VersionSpec::LatestBeta => "Beta",
+                    VersionSpec::LatestDev => "Dev",
+                    VersionSpec::LatestCanary => "Canary",
+                    _ => "Stable",
+                };
+                let (driver_version, url) =
+                    get_chromedriver_latest_download_url(&self.download_options, &self.metadata, channel, platform)
+                        .await?;
+                return Ok(self.apply_source(driver_version, url, platform));
+            }
+        };
+        let chromium_version = chromium_version(self.browser, &browser_version);
+        if is_legacy_version(&chromium_version) {
+            return self.resolve_legacy(&chromium_version, platform).await;
+        }
+        let resolved = match cft_channel_name(self.channel) {
+            Some(channel) => {
+                get_chromedriver_channel_download_url(
+                    &self.download_options,
+                    &self.metadata,
+                    channel,
+                    &chromium_version,
+                    platform,
+                )
+                .await
+            }
+            None if self.strategy == ResolutionStrategy::LatestPatchPerBuild => {
+                get_chromedriver_build_download_url(&self.download_options, &self.metadata, &chromium_version, platform)
+                    .await
+            }
+            None => {
+                get_chromedriver_download_url(&self.download_options, &self.metadata, &chromium_version, platform)
+                    .await
+            }
+        };
+        let (driver_version, url) = match resolved {
+            // The bundled snapshot is the last resort when the endpoints are unreachable.
+            Err(WebDriverError::NetworkError(e)) => {
+                bundled_download_url(&chromium_version, platform).ok_or(WebDriverError::NetworkError(e))?
+            }
+            resolved => resolved?,
+        };
+        Ok(self.apply_source(driver_version, url, platform))
+    }
+
+    /// Points an upstream download URL at the configured source.
+    fn apply_source(&self, driver_version: String, url: String, platform: &str) -> (String, String) {
+        match &self.source {
+            DriverSource::Upstream => {
+                let url = self.download_options.endpoints.rewrite_download_url(self.get_driver_name(), &url);
+                (driver_version, url)
+            }
+            DriverSource::Repository(repository) => {
+                let url = repository.artifact_url(self.get_driver_name(), &driver_version, platform, "zip");
+                (driver_version, url)
+            }
+        }
+    }
+
+    /// Resolves a Chrome version below 115 through the legacy
+    /// `LATEST_RELEASE_<version>` files.
+    async fn resolve_legacy(&self, browser_version: &str, platform: &str) -> Result<(String, String), WebDriverError> {
+        let legacy_platform = legacy_platform(platform, browser_version).ok_or_else(|| {
+            WebDriverError::DriverVersionNotFound {
+                browser_version: browser_version.to_string(),
+                platform: platform.to_string(),
+            }
+        })?;
+        let driver_version = get_legacy_chromedriver_version(&self.download_options, browser_version).await?;
+
+        let url = match &self.source {
+            DriverSource::Upstream => format!(
+                "{}/{}/chromedriver_{}.zip",
+                self.download_options.endpoints.chromedriver_legacy_url(),
+                driver_version,
+                legacy_platform
+            ),
+            DriverSource::Repository(repository) => {
+                repository.artifact_url(self.get_driver_name(), &driver_version, platform, "zip")
+            }
+        };
+        Ok((driver_version, url))
+    }
+
+    /// The platform identifier of the chromedriver build to install. The
+    /// architecture of the installed browser decides, so a 32-bit Chrome on
+    /// 64-bit Windows gets `win32` and an Intel-only Chrome on Apple Silicon
+    /// (running under Rosetta) gets `mac-x64`. The host architecture is the
+    /// fallback. The browser is only looked up and inspected once.
+    fn platform(&self) -> Result<&'static str, WebDriverError> {
+        if self.wsl_windows_browser {
+            return Ok("win64");
+        }
+        if let Some(arch) = self.arch {
+            return chromedriver_platform_for(std::env::consts::OS, arch);
+        }
+
+        let arch = self.browser_arch.get_or_init(|| {
+            let browser_name = channel_browser_name(self.browser.as_str(), self.channel);
+            self.browser_path
+                .clone()
+                .or_else(|| find_browser_path(&browser_name))
+                .and_then(|path| browser_arch(&path))
+        });
+        match arch {
+            Some(arch) => chromedriver_platform_for(std::env::consts::OS, *arch),
+            None => chromedriver_platform(),
+        }
+    }
+
+    /// The file name of the driver inside the archive.
+    fn executable_name(&self) -> &str {
+        if self.wsl_windows_browser {
+            "chromedriver.exe"
+        } else {
+            self.get_driver_name()
+        }
+    }
+}
 
 #[async_trait]
 impl WebDriverManager for ChromeDriver {
@@ -21,44 +338,111 @@ impl WebDriverManager for ChromeDriver {
         "chromedriver"
     }
 
+    fn driver_platform(&self) -> String {
+        self.platform().unwrap_or("unknown").to_string()
+    }
+
     async fn get_browser_version(
         &self, browser_path: Option<&Path>,
     ) -> Result<String, WebDriverError> {
-        get_browser_version("chrome", browser_path).await
+        let browser_name = channel_browser_name(self.browser.as_str(), self.channel);
+        if self.wsl_windows_browser {
+            return get_windows_browser_version_from_wsl(&browser_name, browser_path).await;
+        }
+        get_browser_version(&browser_name, browser_path).await
     }
 
-    async fn get_driver_version(&self, browser_version: &str) -> Result<String, WebDriverError> {
-        let (driver_version, _url) = get_chromedriver_download_url(browser_version).await?;
-        Ok(driver_version)
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        let (driver_version, _url) = self.resolve(browser_version).await?;
+        driver_version.parse()
     }
 
     async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
         let browser_version = driver_version;
-        let (_driver_version, url) = get_chromedriver_download_url(browser_version).await?;
+        let (_driver_version, url) = self.resolve(browser_version).await?;
         Ok(url)
     }
 
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        self.download_options.emit(InstallEvent::ResolvingVersion);
+        let (driver_version, url) = self.resolve(browser_version).await?;
+        Ok((driver_version.parse()?, url))
+    }
+
     async fn download_and_install(
         &self,
         driver_version: &str,
         install_path: &Path,
     ) -> Result<PathBuf, WebDriverError> {
-        let (_driver_version, url) = get_chromedriver_download_url(driver_version).await?;
+        let (_driver_version, url) = self.resolve_download(driver_version).await?;
+        self.install_download(driver_version, &url, install_path).await
+    }
+
+    async fn install_download(
+        &self,
+        driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let mut options = self.download_options.clone();
+        if let Some(credentials) = self.source.credentials() {
+            options.credentials = Some(credentials.clone());
+        }
+        if options.expected_arch.is_none() {
+            // An exact spec (`=126.0.6478.182`) names the version directly.
+            let chromium_version = chromium_version(self.browser, driver_version.trim_start_matches('='));
+            options.expected_arch = self.platform().ok().and_then(|platform| {
+                if is_legacy_version(&chromium_version) {
+                    legacy_platform(platform, &chromium_version).and_then(platform_arch)
+                } else {
+                    platform_arch(platform)
+                }
+            });
+        }
 
-        let driver_name = self.get_driver_name();
-        let driver_path = download_and_unzip(&url, install_path, driver_name).await?;
+        let driver_name = self.executable_name();
+        let driver_path = download_and_unzip_with_options(url, install_path, driver_name, &options).await?;
 
+        options.emit(InstallEvent::Verifying);
         self.verify_driver(&driver_path).await?;
+
+        options.emit(InstallEvent::Done { driver_path: driver_path.clone() });
         Ok(driver_path)
     }
 
+    /// The Chrome for Testing releases with a chromedriver for the platform.
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        let platform = self.platform()?;
+        let options = &self.download_options;
+        let response = self
+            .metadata
+            .known_good
+            .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_versions_url(), options).await })
+            .await?;
+
+        let mut versions: Vec<Version> = response
+            .versions
+            .iter()
+            .filter(|v| platform_download_url(v, platform).is_ok())
+            .filter_map(|v| v.version.parse().ok())
+            .collect();
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// Chromedriver supports the Chrome release with the same major version.
+    async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+        let browser_version = chromium_version(self.browser, browser_version);
+        browser_version.parse::<Version>().is_ok_and(|browser| driver_version.major == browser.major)
+    }
+
     async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
 
-        let mut command = tokio::process::Command::new(driver_path);
+        let mut command = Command::new(driver_path);
         command.arg("--version");
 
-        let output = command
-            .output()
+        let output = runtime::output(&mut command)
             .await
             .map_err(|e| WebDriverError::CommandExecutionError { 
                 command: format!("{:?}", command), 
@@ -106,7 +490,7 @@ struct VersionDownloads {
 
 /// Represents a single version entry in the main JSON response.
 #[derive(Debug, Deserialize)]
-struct Version {
+struct KnownGoodVersion {
     version: String,
     downloads: VersionDownloads,
 }
@@ -114,59 +498,349 @@ struct Version {
 /// The top-level structure of the JSON response.
 #[derive(Debug, Deserialize)]
 struct KnownGoodVersions {
-    versions: Vec<Version>,
+    versions: Vec<KnownGoodVersion>,
+}
+
+/// The top-level structure of the per-channel JSON response, keyed by
+/// channel name ("Stable", "Beta", "Dev", "Canary").
+#[derive(Debug, Deserialize)]
+struct LastKnownGoodVersions {
+    channels: HashMap<String, KnownGoodVersion>,
+}
+
+/// The top-level structure of the per-build JSON response, keyed by
+/// `major.minor.build`.
+#[derive(Debug, Deserialize)]
+struct LatestPatchVersionsPerBuild {
+    builds: HashMap<String, KnownGoodVersion>,
+}
+
+/// The top-level structure of the per-milestone JSON response, keyed by
+/// major version.
+#[derive(Debug, Deserialize)]
+struct LatestVersionsPerMilestone {
+    milestones: HashMap<String, KnownGoodVersion>,
+}
+
+/// An entry of the bundled milestone snapshot.
+#[derive(Debug, Deserialize)]
+struct BundledMilestone {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BundledMilestones {
+    milestones: HashMap<String, BundledMilestone>,
 }
 
-/// Fetches the driver download URL for a specific *browser* version.
+/// Resolves `(driver_version, url)` from the bundled milestone snapshot, for
+/// browsers whose milestone it contains.
+fn bundled_download_url(browser_version: &str, platform: &str) -> Option<(String, String)> {
+    let major = browser_version.parse::<Version>().ok()?.major;
+    let snapshot: BundledMilestones = serde_json::from_str(BUNDLED_MILESTONES).ok()?;
+    let version = &snapshot.milestones.get(&major.to_string())?.version;
+    let url = format!("{0}/{1}/{2}/chromedriver-{2}.zip", CFT_DOWNLOAD_BASE, version, platform);
+    Some((version.clone(), url))
+}
+
+/// The Chrome for Testing name of a pre-release channel.
+fn cft_channel_name(channel: BrowserChannel) -> Option<&'static str> {
+    match channel {
+        BrowserChannel::Beta => Some("Beta"),
+        BrowserChannel::Dev => Some("Dev"),
+        BrowserChannel::Canary => Some("Canary"),
+        _ => None,
+    }
+}
+
+/// Determines the platform identifier used by Google's JSON endpoints for
+/// the host.
+fn chromedriver_platform() -> Result<&'static str, WebDriverError> {
+    match Arch::host() {
+        Some(arch) => chromedriver_platform_for(std::env::consts::OS, arch),
+        None => Err(WebDriverError::UnsupportedPlatform(format!(
+            "{}-{}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))),
+    }
+}
+
+/// Determines the platform identifier used by Google's JSON endpoints for a
+/// browser of the given architecture.
+fn chromedriver_platform_for(os: &str, arch: Arch) -> Result<&'static str, WebDriverError> {
+    match (os, arch) {
+        ("windows", Arch::X86_64) => Ok("win64"),
+        ("windows", Arch::X86) => Ok("win32"),
+        ("macos", Arch::X86_64) => Ok("mac-x64"),
+        ("macos", Arch::Aarch64) => Ok("mac-arm64"),
+        ("linux", Arch::X86_64) => Ok("linux64"),
+        _ => Err(WebDriverError::UnsupportedPlatform(format!("{}-{}", os, arch))),
+    }
+}
+
+/// The architecture of the chromedriver builds of a platform identifier.
+fn platform_arch(platform: &str) -> Option<Arch> {
+    match platform {
+        "win32" => Some(Arch::X86),
+        "win64" | "mac-x64" | "linux64" | "mac64" => Some(Arch::X86_64),
+        "mac-arm64" | "mac_arm64" | "mac64_m1" => Some(Arch::Aarch64),
+        _ => None,
+    }
+}
+
+/// Fetches the driver download URL for a specific *browser* version and
+/// platform identifier.
 /// 
 /// It queries the Google JSON endpoints, finds the closest matching version,
 /// and returns `(driver_version, url)`
 async fn get_chromedriver_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
     browser_version: &str,
+    platform: &str,
 ) -> Result<(String, String), WebDriverError> {
 
-    // Determine the platform identifier used by Google's JSON endpoints.
-    let platform = match (std::env::consts::OS, std::env::consts::ARCH) {
-        ("windows", "x86_64") => "win64",
-        ("windows", "x86") => "win32",
-        ("macos", "x86_64") => "mac-x64",
-        ("macos", "aarch64") => "mac-arm64",
-        ("linux", "x86_64") => "linux64",
-        _ => {
-            return Err(WebDriverError::UnsupportedPlatform(format!(
-                "{}-{}",
-                std::env::consts::OS,
-                std::env::consts::ARCH
-            )))
+    // Fetch the JSON data, once per manager.
+    let response = metadata
+        .known_good
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_versions_url(), options).await })
+        .await?;
+
+    let parsed_version: Version = browser_version
+        .parse()
+        .map_err(|_| WebDriverError::BrowserVersionParsingError {
+            output: browser_version.to_string(),
+        })?;
+
+    let best_match = select_known_good_version(&response.versions, &parsed_version)
+        .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+            browser_version: browser_version.to_string(),
+            platform: platform.to_string(),
+        })?;
+
+    let url = platform_download_url(best_match, platform)?;
+    Ok((best_match.version.clone(), url))
+}
+
+/// Whether a Chrome version predates Chrome for Testing.
+fn is_legacy_version(browser_version: &str) -> bool {
+    browser_version
+        .parse::<Version>()
+        .is_ok_and(|version| version.major < FIRST_CFT_MAJOR)
+}
+
+/// The platform identifier of the legacy builds for a Chrome for Testing
+/// platform. There were no 64-bit Windows builds; the 32-bit build runs there.
+fn legacy_platform(platform: &str, browser_version: &str) -> Option<&'static str> {
+    let major = browser_version.parse::<Version>().ok()?.major;
+    match platform {
+        "linux64" => Some("linux64"),
+        "mac-x64" => Some("mac64"),
+        // Apple Silicon builds were named "mac64_m1" before Chrome 106.
+        "mac-arm64" if major < 106 => Some("mac64_m1"),
+        "mac-arm64" => Some("mac_arm64"),
+        "win32" | "win64" => Some("win32"),
+        _ => None,
+    }
+}
+
+/// Looks up the newest legacy chromedriver for a browser version. The
+/// `LATEST_RELEASE_<major.minor.build>` file (Chrome 73 and later) is tried
+/// first, then `LATEST_RELEASE_<major>`.
+async fn get_legacy_chromedriver_version(
+    options: &DownloadOptions,
+    browser_version: &str,
+) -> Result<String, WebDriverError> {
+    let version: Version = browser_version
+        .parse()
+        .map_err(|_| WebDriverError::BrowserVersionParsingError {
+            output: browser_version.to_string(),
+        })?;
+    let mut candidates = vec![version.truncate(1)];
+    if version.precision() >= 3 && version.major >= 73 {
+        candidates.insert(0, version.truncate(3));
+    }
+
+    let base = options.endpoints.chromedriver_legacy_url();
+    let mut last_error = None;
+    for candidate in candidates {
+        match fetch_text(&format!("{}/LATEST_RELEASE_{}", base, candidate), options).await {
+            Ok(body) if !body.trim().is_empty() => return Ok(body.trim().to_string()),
+            Ok(_) => {}
+            Err(e) => last_error = Some(e),
         }
-    };
+    }
+    Err(match last_error {
+        Some(e @ WebDriverError::OfflineUnavailable { .. }) => e,
+        _ => WebDriverError::DriverVersionNotFound {
+            browser_version: browser_version.to_string(),
+            platform: chromedriver_platform().unwrap_or("unknown").to_string(),
+        },
+    })
+}
 
-    // Fetch the JSON data.
-    let response: KnownGoodVersions = reqwest::get(CHROMEDRIVER_URLS_ENDPOINT)
-        .await?
-        .json()
+/// Fetches the driver download URL for a browser of a pre-release channel.
+///
+/// Beta, Dev and Canary builds are often newer than anything in the
+/// known-good list, so the channel's latest build is used when its major
+/// version matches the browser. Otherwise the regular lookup is used.
+async fn get_chromedriver_channel_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
+    channel: &str,
+    browser_version: &str,
+    platform: &str,
+) -> Result<(String, String), WebDriverError> {
+    let response = metadata
+        .channels
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_channels_url(), options).await })
         .await?;
 
-    // The browser version might be "115.0.5790.171". Then you only need "115.0.5790".
-    let major_browser_version = browser_version
-        .rsplitn(2, '.')
-        .nth(1)
-        .ok_or_else(|| WebDriverError::BrowserVersionParsingError {
-            output: browser_version.to_string(),
+    match select_channel_version(response, channel, browser_version) {
+        Some(latest) => Ok((latest.version.clone(), platform_download_url(latest, platform)?)),
+        None => get_chromedriver_download_url(options, metadata, browser_version, platform).await,
+    }
+}
+
+/// Fetches the download URL of exactly `driver_version` from the known-good
+/// list.
+async fn get_chromedriver_exact_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
+    driver_version: &str,
+    platform: &str,
+) -> Result<String, WebDriverError> {
+    let response = metadata
+        .known_good
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_versions_url(), options).await })
+        .await?;
+
+    let exact = response
+        .versions
+        .iter()
+        .find(|v| v.version == driver_version)
+        .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+            browser_version: driver_version.to_string(),
+            platform: platform.to_string(),
         })?;
+    platform_download_url(exact, platform)
+}
 
-    // Find the latest version in the JSON that matches the major version of the browser.
-    let best_match = response
+/// Fetches the newest chromedriver in a version range that has a download
+/// for the platform. Only Chrome for Testing releases (115 and later) are
+/// considered.
+async fn get_chromedriver_range_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
+    req: &VersionReq,
+    platform: &str,
+) -> Result<(String, String), WebDriverError> {
+    let response = metadata
+        .known_good
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_versions_url(), options).await })
+        .await?;
+
+    response
         .versions
         .iter()
-        .filter(|v| v.version.starts_with(major_browser_version))
-        .last() // The list is sorted, so the last one is the newest patch.
+        .filter_map(|v| Some((v.version.parse::<Version>().ok()?, v)))
+        .filter(|(version, _)| req.matches(version))
+        .filter_map(|(version, v)| Some((version, v, platform_download_url(v, platform).ok()?)))
+        .max_by_key(|(version, _, _)| *version)
+        .map(|(_, v, url)| (v.version.clone(), url))
         .ok_or_else(|| WebDriverError::DriverVersionNotFound {
-            browser_version: browser_version.to_string(),
+            browser_version: req.to_string(),
+            platform: platform.to_string(),
+        })
+}
+
+/// Fetches the download URL of the newest chromedriver of a release channel,
+/// without a browser version.
+async fn get_chromedriver_latest_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
+    channel: &str,
+    platform: &str,
+) -> Result<(String, String), WebDriverError> {
+    let response = metadata
+        .channels
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_channels_url(), options).await })
+        .await?;
+
+    let latest = response
+        .channels
+        .get(channel)
+        .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+            browser_version: format!("latest {}", channel),
             platform: platform.to_string(),
         })?;
+    Ok((latest.version.clone(), platform_download_url(latest, platform)?))
+}
+
+/// Fetches the driver download URL for the exact build of the browser from
+/// the latest-patch-versions-per-build list.
+async fn get_chromedriver_build_download_url(
+    options: &DownloadOptions,
+    metadata: &MetadataMemo,
+    browser_version: &str,
+    platform: &str,
+) -> Result<(String, String), WebDriverError> {
+    let response = metadata
+        .builds
+        .get_or_try_init(|| async { fetch_json(&options.endpoints.chromedriver_builds_url(), options).await })
+        .await?;
+
+    match select_build_version(response, browser_version) {
+        Some(latest) => Ok((latest.version.clone(), platform_download_url(latest, platform)?)),
+        None => get_chromedriver_download_url(options, metadata, browser_version, platform).await,
+    }
+}
+
+/// Returns the entry of the browser's `major.minor.build`, if the browser
+/// version has one.
+fn select_build_version<'a>(
+    response: &'a LatestPatchVersionsPerBuild,
+    browser_version: &str,
+) -> Option<&'a KnownGoodVersion> {
+    let version = browser_version.parse::<Version>().ok().filter(|v| v.precision() >= 3)?;
+    response.builds.get(&version.truncate(3).to_string())
+}
+
+/// Finds the newest known-good version matching the browser.
+///
+/// The browser version might be "115.0.5790.171". Then only "115.0.5790" has
+/// to match. A bare major version ("115") matches every "115.x" release.
+fn select_known_good_version<'a>(
+    versions: &'a [KnownGoodVersion],
+    browser_version: &Version,
+) -> Option<&'a KnownGoodVersion> {
+    let wanted = browser_version.truncate(3);
 
-    // From that version, find the download URL for our specific platform.
+    versions
+        .iter()
+        .filter_map(|v| Some((v.version.parse::<Version>().ok()?, v)))
+        .filter(|(version, _)| wanted.matches(version))
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, v)| v)
+}
+
+/// Returns the latest version of `channel` if it has the same major version
+/// as the browser.
+fn select_channel_version<'a>(
+    response: &'a LastKnownGoodVersions,
+    channel: &str,
+    browser_version: &str,
+) -> Option<&'a KnownGoodVersion> {
+    let major = browser_version.parse::<Version>().ok()?.truncate(1);
+    response
+        .channels
+        .get(channel)
+        .filter(|latest| latest.version.parse().is_ok_and(|version| major.matches(&version)))
+}
+
+/// Finds the chromedriver download URL of a version for our specific platform.
+fn platform_download_url(best_match: &KnownGoodVersion, platform: &str) -> Result<String, WebDriverError> {
     let download = best_match
         .downloads
         .chromedriver
@@ -182,8 +856,7 @@ async fn get_chromedriver_download_url(
             platform: platform.to_string(),
         })?;
 
-    Ok((best_match.version.clone(), download.url.clone()))
-    
+    Ok(download.url.clone())
 }
 
 // --- Tests ---
@@ -192,12 +865,286 @@ async fn get_chromedriver_download_url(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chromedriver_platform_for_browser_arch() {
+        assert_eq!(chromedriver_platform_for("windows", Arch::X86).unwrap(), "win32");
+        assert_eq!(chromedriver_platform_for("windows", Arch::X86_64).unwrap(), "win64");
+        assert_eq!(chromedriver_platform_for("macos", Arch::X86_64).unwrap(), "mac-x64");
+        assert!(chromedriver_platform_for("linux", Arch::Aarch64).is_err());
+
+        let manager = ChromeDriver::new().with_arch(Arch::X86_64);
+        assert_eq!(manager.platform().unwrap(), chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_platform_of_configured_browser_is_cached() {
+        let elf = |machine: u8| {
+            let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1, 0];
+            elf.resize(18, 0);
+            elf.extend([machine, 0x00]);
+            elf
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let browser = dir.path().join("chrome");
+        std::fs::write(&browser, elf(0x3e)).unwrap();
+
+        let manager = ChromeDriver::new().with_browser_path(&browser);
+        assert_eq!(manager.platform().unwrap(), "linux64");
+
+        // The binary isn't read again, also not by clones.
+        std::fs::write(&browser, elf(0xb7)).unwrap();
+        assert_eq!(manager.clone().platform().unwrap(), "linux64");
+        // There is no chromedriver for Linux on ARM.
+        assert!(ChromeDriver::new().with_browser_path(&browser).platform().is_err());
+    }
+
+    #[test]
+    fn test_select_known_good_version() {
+        let json = r#"{"versions": [
+            {"version": "13.0.782.0", "downloads": {}},
+            {"version": "130.0.6723.58", "downloads": {}},
+            {"version": "130.0.6723.116", "downloads": {}},
+            {"version": "130.0.6723.69", "downloads": {}}
+        ]}"#;
+        let response: KnownGoodVersions = serde_json::from_str(json).unwrap();
+        let select = |browser: &str| {
+            select_known_good_version(&response.versions, &browser.parse().unwrap()).map(|v| v.version.as_str())
+        };
+
+        assert_eq!(select("130.0.6723.91"), Some("130.0.6723.116"));
+        assert_eq!(select("130"), Some("130.0.6723.116"));
+        assert_eq!(select("13"), Some("13.0.782.0"));
+        assert_eq!(select("131.0.6778.85"), None);
+    }
+
+    #[test]
+    fn test_select_channel_version() {
+        let json = r#"{
+            "timestamp": "2025-07-23T10:09:59.925Z",
+            "channels": {
+                "Stable": {"channel": "Stable", "version": "138.0.7204.168", "revision": "1465706", "downloads": {}},
+                "Canary": {"channel": "Canary", "version": "140.0.7319.0", "revision": "1488000",
+                    "downloads": {"chromedriver": [{"platform": "linux64", "url": "https://example.com/chromedriver-linux64.zip"}]}}
+            }
+        }"#;
+        let response: LastKnownGoodVersions = serde_json::from_str(json).unwrap();
+
+        let canary = select_channel_version(&response, "Canary", "140.0.7320.2").unwrap();
+        assert_eq!(canary.version, "140.0.7319.0");
+        assert_eq!(platform_download_url(canary, "linux64").unwrap(), "https://example.com/chromedriver-linux64.zip");
+
+        // A browser a major version behind falls back to the known-good lookup.
+        assert!(select_channel_version(&response, "Canary", "139.0.7258.5").is_none());
+        assert!(select_channel_version(&response, "Beta", "139.0.7258.5").is_none());
+    }
+
+    #[test]
+    fn test_select_build_version() {
+        let json = r#"{
+            "timestamp": "2025-07-23T10:09:59.925Z",
+            "builds": {
+                "138.0.7204": {"version": "138.0.7204.168", "revision": "1465706", "downloads": {}},
+                "139.0.7258": {"version": "139.0.7258.5", "revision": "1477651", "downloads": {}}
+            }
+        }"#;
+        let response: LatestPatchVersionsPerBuild = serde_json::from_str(json).unwrap();
+
+        assert_eq!(select_build_version(&response, "138.0.7204.101").unwrap().version, "138.0.7204.168");
+        assert_eq!(select_build_version(&response, "139.0.7258.66").unwrap().version, "139.0.7258.5");
+        assert!(select_build_version(&response, "138.0.7205.1").is_none());
+        assert!(select_build_version(&response, "138").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_latest_channel_specs() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("last-known-good-versions-with-downloads.json");
+        let platform = chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+        let json = format!(
+            r#"{{"channels": {{
+                "Stable": {{"version": "138.0.7204.168", "downloads": {{"chromedriver": [
+                    {{"platform": "{0}", "url": "https://example.com/138/chromedriver.zip"}}]}}}},
+                "Canary": {{"version": "140.0.7319.0", "downloads": {{"chromedriver": [
+                    {{"platform": "{0}", "url": "https://example.com/140/chromedriver.zip"}}]}}}}
+            }}}}"#,
+            platform
+        );
+        std::fs::write(&json_path, json).unwrap();
+
+        let endpoints = crate::endpoints::Endpoints::new().chromedriver_channels(json_path.to_string_lossy());
+        let manager = ChromeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+
+        assert_eq!(manager.get_driver_version("latest-stable").await.unwrap().to_string(), "138.0.7204.168");
+        assert_eq!(
+            manager.get_download_url(&VersionSpec::LatestCanary.to_string()).await.unwrap(),
+            "https://example.com/140/chromedriver.zip"
+        );
+        assert!(matches!(
+            manager.get_driver_version("dev").await,
+            Err(WebDriverError::DriverVersionNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_driver_version_for_milestone() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("latest-versions-per-milestone-with-downloads.json");
+        std::fs::write(
+            &json_path,
+            r#"{"timestamp": "2025-07-23T10:09:59.925Z", "milestones": {
+                "126": {"milestone": "126", "version": "126.0.6478.182", "revision": "1300313", "downloads": {}}
+            }}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("LATEST_RELEASE_110"), "110.0.5481.77").unwrap();
+
+        let endpoints = crate::endpoints::Endpoints::new()
+            .chromedriver_milestones(json_path.to_string_lossy())
+            .chromedriver_legacy(dir.path().to_string_lossy());
+        let manager = ChromeDriver::new().with_download_options(DownloadOptions::new().endpoints(endpoints));
+
+        assert_eq!(manager.get_driver_version_for_milestone(126).await.unwrap(), "126.0.6478.182");
+        assert_eq!(manager.get_driver_version_for_milestone(110).await.unwrap(), "110.0.5481.77");
+        assert!(manager.get_driver_version_for_milestone(127).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_chrome_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("LATEST_RELEASE_114.0.5735"), "114.0.5735.90").unwrap();
+        std::fs::write(dir.path().join("LATEST_RELEASE_112"), "112.0.5615.49\n").unwrap();
+
+        let base = dir.path().to_string_lossy().to_string();
+        let endpoints = crate::endpoints::Endpoints::new().chromedriver_legacy(base.clone());
+        let manager = ChromeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+        let platform = chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+        let legacy = legacy_platform(platform, "114").unwrap();
+
+        assert_eq!(manager.get_driver_version("114.0.5735.199").await.unwrap().to_string(), "114.0.5735.90");
+        assert_eq!(manager.get_driver_version("112.0.5615.165").await.unwrap().to_string(), "112.0.5615.49");
+        assert_eq!(
+            manager.get_download_url("114.0.5735.199").await.unwrap(),
+            format!("{}/114.0.5735.90/chromedriver_{}.zip", base, legacy)
+        );
+        assert!(matches!(
+            manager.get_driver_version("100.0.4896.60").await,
+            Err(WebDriverError::DriverVersionNotFound { .. })
+        ));
+
+        assert_eq!(legacy_platform("win64", "109.0"), Some("win32"));
+        assert_eq!(legacy_platform("mac-arm64", "105.0"), Some("mac64_m1"));
+        assert_eq!(legacy_platform("mac-arm64", "110.0"), Some("mac_arm64"));
+    }
+
+    #[tokio::test]
+    async fn test_exact_version_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("known-good-versions-with-downloads.json");
+        let platform = chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+        let json = format!(
+            r#"{{"versions": [
+                {{"version": "126.0.6478.126", "downloads": {{"chromedriver": [
+                    {{"platform": "{}", "url": "https://example.com/126.0.6478.126/chromedriver.zip"}}]}}}},
+                {{"version": "126.0.6478.182", "downloads": {{"chromedriver": []}}}}
+            ]}}"#,
+            platform
+        );
+        std::fs::write(&json_path, json).unwrap();
+
+        let endpoints = crate::endpoints::Endpoints::new().chromedriver_versions(json_path.to_string_lossy());
+        let manager = ChromeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+
+        let spec = VersionSpec::Exact("126.0.6478.126".to_string()).to_string();
+        assert_eq!(manager.get_driver_version(&spec).await.unwrap().to_string(), "126.0.6478.126");
+        assert_eq!(
+            manager.get_download_url(&spec).await.unwrap(),
+            "https://example.com/126.0.6478.126/chromedriver.zip"
+        );
+        assert!(matches!(
+            manager.get_download_url("=126.0.6478.182").await,
+            Err(WebDriverError::DriverUrlNotFound { .. })
+        ));
+        assert!(matches!(
+            manager.get_download_url("=126.0.6478.127").await,
+            Err(WebDriverError::DriverVersionNotFound { .. })
+        ));
+
+        let available = manager.list_available_versions().await.unwrap();
+        assert_eq!(available, ["126.0.6478.126".parse::<Version>().unwrap()]);
+
+        // Ranges skip versions without a download for the platform.
+        assert_eq!(manager.get_driver_version("126.*").await.unwrap().to_string(), "126.0.6478.126");
+        assert!(manager.get_driver_version(">=127").await.is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_bundled_fallback_when_unreachable() {
+        // Nothing listens on port 1, so the request fails to connect.
+        let endpoints = crate::endpoints::Endpoints::new().chromedriver_versions("http://127.0.0.1:1/versions.json");
+        let manager = ChromeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+        let platform = chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+
+        assert_eq!(manager.get_driver_version("126.0.6478.127").await.unwrap().to_string(), "126.0.6478.182");
+        assert_eq!(
+            manager.get_download_url("126.0.6478.127").await.unwrap(),
+            format!("{0}/126.0.6478.182/{1}/chromedriver-{1}.zip", CFT_DOWNLOAD_BASE, platform)
+        );
+        assert!(matches!(
+            manager.get_driver_version("999.0.1.2").await,
+            Err(WebDriverError::NetworkError(_))
+        ));
+
+        let snapshot: BundledMilestones = serde_json::from_str(BUNDLED_MILESTONES).unwrap();
+        assert!(snapshot.milestones.keys().all(|major| major.parse::<u32>().unwrap() >= FIRST_CFT_MAJOR));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_fetched_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("known-good-versions-with-downloads.json");
+        let platform = chromedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+        let json = format!(
+            r#"{{"versions": [{{"version": "138.0.7204.157", "downloads": {{"chromedriver": [
+                {{"platform": "{}", "url": "https://example.com/chromedriver.zip"}}
+            ]}}}}]}}"#,
+            platform
+        );
+        std::fs::write(&json_path, json).unwrap();
+
+        let endpoints = crate::endpoints::Endpoints::new().chromedriver_versions(json_path.to_string_lossy());
+        let manager = ChromeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+        assert_eq!(manager.get_driver_version("138.0.7204.158").await.unwrap().to_string(), "138.0.7204.157");
+
+        // Later calls don't read the document again.
+        std::fs::remove_file(&json_path).unwrap();
+        assert_eq!(manager.get_download_url("138").await.unwrap(), "https://example.com/chromedriver.zip");
+    }
+
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_get_download_url_for_known_version() {
         // Use a known good browser version to test the JSON endpoint logic.
         // This version should be new enough to likely remain in the JSON file for a long time.
         let browser_version = "138.0.7204.158";
-        let result = get_chromedriver_download_url(browser_version).await;
+        let result = get_chromedriver_download_url(
+            &DownloadOptions::default(),
+            &MetadataMemo::default(),
+            browser_version,
+            chromedriver_platform().unwrap(),
+        )
+        .await;
 
         println!("Test Result for browser version {}: {:?}", browser_version, result);
 
@@ -0,0 +1,189 @@
+//! Generic support for Chromium derivatives (Vivaldi, Arc, corporate forks, ...).
+//!
+//! A [`ChromiumBased`] manager takes the path of an arbitrary browser binary
+//! and a [`ChromiumVersionSource`] describing how to read the version of its
+//! Chromium engine. The engine version is then resolved against chromedriver
+//! exactly like Chrome itself.
+
+use crate::browser::{parse_version_token, plist_string_value};
+use crate::drivers::chromedriver::ChromeDriver;
+use crate::error::WebDriverError;
+use crate::version::Version;
+use crate::WebDriverManager;
+use crate::runtime::{self, fs};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How to read the Chromium engine version of a browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChromiumVersionSource {
+    /// Run the binary with this flag (e.g. `--version`) and parse the output.
+    CliFlag(String),
+    /// Read a string key from a macOS `Info.plist`.
+    Plist { path: PathBuf, key: String },
+    /// Read a file whose content is the version (e.g. Chromium's `VERSION`).
+    VersionFile(PathBuf),
+}
+
+/// How the version read from the [`ChromiumVersionSource`] relates to the
+/// Chromium engine version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChromiumVersionMapping {
+    /// The version is the Chromium version (`126.0.6478.127`).
+    #[default]
+    Identity,
+    /// Only the first component is the Chromium major version, as with
+    /// Brave's `127.1.68.131`. The newest chromedriver of that major is used.
+    MajorPrefix,
+}
+
+/// A chromedriver manager for an arbitrary Chromium-based browser.
+#[derive(Debug, Clone)]
+pub struct ChromiumBased {
+    binary: PathBuf,
+    version_source: ChromiumVersionSource,
+    mapping: ChromiumVersionMapping,
+    chromedriver: ChromeDriver,
+}
+
+impl ChromiumBased {
+    pub fn new(binary: impl Into<PathBuf>, version_source: ChromiumVersionSource) -> Self {
+        Self {
+            binary: binary.into(),
+            version_source,
+            mapping: ChromiumVersionMapping::default(),
+            chromedriver: ChromeDriver::new(),
+        }
+    }
+
+    /// Sets how the read version maps onto the Chromium version.
+    pub fn with_mapping(mut self, mapping: ChromiumVersionMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Sets the chromedriver manager used for resolution and installation,
+    /// e.g. to configure its source or download options.
+    pub fn with_chromedriver(mut self, chromedriver: ChromeDriver) -> Self {
+        self.chromedriver = chromedriver;
+        self
+    }
+
+    /// Reads the raw version using the configured source.
+    async fn read_version(&self, binary: &Path) -> Result<String, WebDriverError> {
+        match &self.version_source {
+            ChromiumVersionSource::CliFlag(flag) => {
+                let command = format!("'{}' {}", binary.to_string_lossy(), flag);
+                let output = runtime::output(Command::new(binary).arg(flag))
+                    .await
+                    .map_err(|e| WebDriverError::CommandExecutionError {
+                        command: command.clone(),
+                        source: e,
+                    })?;
+                let stdout = String::from_utf8(output.stdout).map_err(|e| {
+                    WebDriverError::CommandOutputParsingError { command, source: e }
+                })?;
+                parse_version_token(&stdout).ok_or(WebDriverError::BrowserVersionParsingError { output: stdout })
+            }
+            ChromiumVersionSource::Plist { path, key } => {
+                let content = read_to_string(path).await?;
+                plist_string_value(&content, key).ok_or(WebDriverError::BrowserVersionParsingError { output: content })
+            }
+            ChromiumVersionSource::VersionFile(path) => {
+                let content = read_to_string(path).await?;
+                parse_version_token(&content).ok_or(WebDriverError::BrowserVersionParsingError { output: content })
+            }
+        }
+    }
+}
+
+async fn read_to_string(path: &Path) -> Result<String, WebDriverError> {
+    fs::read_to_string(path)
+        .await
+        .map_err(|e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })
+}
+
+#[async_trait]
+impl WebDriverManager for ChromiumBased {
+    fn get_driver_name(&self) -> &str {
+        self.chromedriver.get_driver_name()
+    }
+
+    fn driver_platform(&self) -> String {
+        self.chromedriver.driver_platform()
+    }
+
+    /// Returns the Chromium engine version of the browser.
+    async fn get_browser_version(
+        &self, browser_path: Option<&Path>,
+    ) -> Result<String, WebDriverError> {
+        let binary = browser_path.unwrap_or(&self.binary);
+        let version = self.read_version(binary).await?;
+
+        Ok(match self.mapping {
+            ChromiumVersionMapping::Identity => version,
+            ChromiumVersionMapping::MajorPrefix => version.split('.').next().unwrap_or(&version).to_string(),
+        })
+    }
+
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        self.chromedriver.get_driver_version(browser_version).await
+    }
+
+    async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        self.chromedriver.get_download_url(driver_version).await
+    }
+
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        self.chromedriver.list_available_versions().await
+    }
+
+    async fn download_and_install(
+        &self,
+        driver_version: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        self.chromedriver.download_and_install(driver_version, install_path).await
+    }
+
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        self.chromedriver.resolve_download(browser_version).await
+    }
+
+    async fn install_download(
+        &self,
+        driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        self.chromedriver.install_download(driver_version, url, install_path).await
+    }
+
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+        self.chromedriver.verify_driver(driver_path).await
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_version_file_with_major_prefix_mapping() {
+        let dir = tempfile::tempdir().unwrap();
+        let version_file = dir.path().join("VERSION");
+        std::fs::write(&version_file, "127.1.68.131\n").unwrap();
+
+        let manager = ChromiumBased::new(dir.path().join("browser"), ChromiumVersionSource::VersionFile(version_file.clone()));
+        assert_eq!(manager.get_browser_version(None).await.unwrap(), "127.1.68.131");
+
+        let manager = manager.with_mapping(ChromiumVersionMapping::MajorPrefix);
+        assert_eq!(manager.get_browser_version(None).await.unwrap(), "127");
+    }
+}
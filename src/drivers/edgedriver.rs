@@ -0,0 +1,421 @@
+//! Management of Microsoft's msedgedriver for Microsoft Edge.
+//!
+//! msedgedriver is published at `https://msedgedriver.microsoft.com`, with
+//! one build per Edge release. `LATEST_RELEASE_<major>_<OS>` files name the
+//! newest driver of a milestone, `LATEST_STABLE` (and `_BETA`, `_DEV`,
+//! `_CANARY`) the newest driver of a channel, and the archives are at
+//! `<version>/edgedriver_<platform>.zip`.
+
+use crate::arch::Arch;
+use crate::browser::{
+    browser_arch, channel_browser_name, find_browser_path, get_browser_version, get_windows_browser_version_from_wsl,
+    BrowserChannel,
+};
+use crate::downloader::{download_and_unzip_with_options, fetch_text, DownloadOptions};
+use crate::error::WebDriverError;
+use crate::events::InstallEvent;
+use crate::version::{Version, VersionReq, VersionSpec};
+use crate::WebDriverManager;
+use crate::runtime;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+// The storage of the msedgedriver builds and their LATEST_* files.
+pub(crate) const EDGEDRIVER_ENDPOINT: &str = "https://msedgedriver.microsoft.com";
+
+// How many milestones below the stable one a version range is looked up in.
+const RANGE_LOOKBACK: u32 = 20;
+
+/// Public struct for managing msedgedriver.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeDriver {
+    channel: BrowserChannel,
+    arch: Option<Arch>,
+    download_options: DownloadOptions,
+    wsl_windows_browser: bool,
+    // The architecture of the browser, read from its binary at most once.
+    browser_arch: Arc<OnceLock<Option<Arch>>>,
+}
+
+impl EdgeDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Targets an Edge release channel (Beta, Dev or Canary) instead of the
+    /// stable release.
+    pub fn with_channel(mut self, channel: BrowserChannel) -> Self {
+        self.channel = channel;
+        self.browser_arch = Arc::default();
+        self
+    }
+
+    /// Inside WSL, drives the Edge installed on the Windows host instead of
+    /// a Linux one: the browser is looked up below `/mnt/c` and the `win64`
+    /// msedgedriver is installed.
+    pub fn with_wsl_windows_browser(mut self, enabled: bool) -> Self {
+        self.wsl_windows_browser = enabled;
+        self
+    }
+
+    /// Installs the msedgedriver build for `arch` instead of the architecture
+    /// of the detected browser.
+    pub fn with_arch(mut self, arch: Arch) -> Self {
+        self.arch = Some(arch);
+        self
+    }
+
+    /// Sets the options used when downloading and extracting the driver.
+    pub fn with_download_options(mut self, options: DownloadOptions) -> Self {
+        self.download_options = options;
+        self
+    }
+
+    /// The platform identifier of the msedgedriver build to install, after
+    /// the architecture of the installed browser like for chromedriver. The
+    /// browser is only looked up and inspected once.
+    fn platform(&self) -> Result<&'static str, WebDriverError> {
+        if self.wsl_windows_browser {
+            return Ok("win64");
+        }
+        let arch = self.arch.or_else(|| {
+            *self.browser_arch.get_or_init(|| {
+                find_browser_path(&channel_browser_name("edge", self.channel)).and_then(|path| browser_arch(&path))
+            })
+        });
+        match arch.or_else(Arch::host) {
+            Some(arch) => edgedriver_platform_for(std::env::consts::OS, arch),
+            None => Err(WebDriverError::UnsupportedPlatform(format!(
+                "{}-{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ))),
+        }
+    }
+
+    /// Resolves `(driver_version, url)` for a browser version or another
+    /// [`VersionSpec`].
+    async fn resolve(&self, spec: &str) -> Result<(String, String), WebDriverError> {
+        let platform = self.platform()?;
+        let driver_version = match spec.parse::<VersionSpec>()? {
+            VersionSpec::Exact(driver_version) => driver_version,
+            VersionSpec::Browser(browser_version) => {
+                let major = browser_version
+                    .parse::<Version>()
+                    .map_err(|_| WebDriverError::BrowserVersionParsingError { output: browser_version.clone() })?
+                    .major;
+                self.latest_release(&format!("LATEST_RELEASE_{}_{}", major, self.os_tag()))
+                    .await?
+                    .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+                        browser_version,
+                        platform: platform.to_string(),
+                    })?
+            }
+            VersionSpec::Range(req) => self.resolve_range(&req, platform).await?,
+            latest => {
+                let file = match latest {
+                    VersionSpec::LatestBeta => "LATEST_BETA",
+                    VersionSpec::LatestDev => "LATEST_DEV",
+                    VersionSpec::LatestCanary => "LATEST_CANARY",
+                    _ => "LATEST_STABLE",
+                };
+                self.latest_release(file).await?.ok_or_else(|| WebDriverError::DriverVersionNotFound {
+                    browser_version: latest.to_string(),
+                    platform: platform.to_string(),
+                })?
+            }
+        };
+        let url = format!(
+            "{}/{}/edgedriver_{}.zip",
+            self.download_options.endpoints.edgedriver_url(),
+            driver_version,
+            platform
+        );
+        let url = self.download_options.endpoints.rewrite_download_url(self.get_driver_name(), &url);
+        Ok((driver_version, url))
+    }
+
+    /// The newest driver of a milestone matching `req`. There is no list of
+    /// all releases, so the milestones up to the stable one are walked from
+    /// newest to oldest and only their newest build is considered.
+    async fn resolve_range(&self, req: &VersionReq, platform: &str) -> Result<String, WebDriverError> {
+        let not_found = || WebDriverError::DriverVersionNotFound {
+            browser_version: req.to_string(),
+            platform: platform.to_string(),
+        };
+        let stable = self.latest_release("LATEST_STABLE").await?.ok_or_else(not_found)?;
+        let stable_major = stable.parse::<Version>().map_err(|_| not_found())?.major;
+
+        for major in (stable_major.saturating_sub(RANGE_LOOKBACK)..=stable_major).rev() {
+            let file = format!("LATEST_RELEASE_{}_{}", major, self.os_tag());
+            // Milestones without a build for the OS have no file.
+            let Ok(Some(version)) = self.latest_release(&file).await else {
+                continue;
+            };
+            if version.parse().is_ok_and(|v| req.matches(&v)) {
+                return Ok(version);
+            }
+        }
+        Err(not_found())
+    }
+
+    /// The OS suffix of the `LATEST_RELEASE_<major>_<OS>` files.
+    fn os_tag(&self) -> &'static str {
+        if self.wsl_windows_browser {
+            os_tag_for("windows")
+        } else {
+            os_tag_for(std::env::consts::OS)
+        }
+    }
+
+    /// The file name of the driver inside the archive.
+    fn executable_name(&self) -> &str {
+        if self.wsl_windows_browser {
+            "msedgedriver.exe"
+        } else {
+            self.get_driver_name()
+        }
+    }
+
+    /// Reads one of the `LATEST_*` files.
+    async fn latest_release(&self, file: &str) -> Result<Option<String>, WebDriverError> {
+        let url = format!("{}/{}", self.download_options.endpoints.edgedriver_url(), file);
+        match fetch_text(&url, &self.download_options).await {
+            Ok(body) => Ok(parse_latest_release(&body)),
+            Err(WebDriverError::NetworkError(e)) if e.status() == Some(::http::StatusCode::NOT_FOUND) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl WebDriverManager for EdgeDriver {
+    fn get_driver_name(&self) -> &str {
+        "msedgedriver"
+    }
+
+    fn driver_platform(&self) -> String {
+        self.platform().unwrap_or("unknown").to_string()
+    }
+
+    async fn get_browser_version(
+        &self, browser_path: Option<&Path>,
+    ) -> Result<String, WebDriverError> {
+        let browser_name = channel_browser_name("edge", self.channel);
+        if self.wsl_windows_browser {
+            return get_windows_browser_version_from_wsl(&browser_name, browser_path).await;
+        }
+        get_browser_version(&browser_name, browser_path).await
+    }
+
+    /// msedgedriver is released with every Edge build; the newest driver of
+    /// the browser's milestone is used.
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        let (driver_version, _url) = self.resolve(browser_version).await?;
+        driver_version.parse()
+    }
+
+    async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        let (_driver_version, url) = self.resolve(driver_version).await?;
+        Ok(url)
+    }
+
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        self.download_options.emit(InstallEvent::ResolvingVersion);
+        let (driver_version, url) = self.resolve(browser_version).await?;
+        Ok((driver_version.parse()?, url))
+    }
+
+    async fn download_and_install(
+        &self,
+        driver_version: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let (_driver_version, url) = self.resolve_download(driver_version).await?;
+        self.install_download(driver_version, &url, install_path).await
+    }
+
+    async fn install_download(
+        &self,
+        _driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let mut options = self.download_options.clone();
+        if options.expected_arch.is_none() {
+            options.expected_arch = self.platform().ok().and_then(platform_arch);
+        }
+
+        let driver_name = self.executable_name();
+        let driver_path = download_and_unzip_with_options(url, install_path, driver_name, &options).await?;
+
+        options.emit(InstallEvent::Verifying);
+        self.verify_driver(&driver_path).await?;
+
+        options.emit(InstallEvent::Done { driver_path: driver_path.clone() });
+        Ok(driver_path)
+    }
+
+    /// msedgedriver supports the Edge release with the same major version.
+    async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+        browser_version.parse::<Version>().is_ok_and(|browser| driver_version.major == browser.major)
+    }
+
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+
+        let mut command = Command::new(driver_path);
+        command.arg("--version");
+
+        let output = runtime::output(&mut command)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: format!("{:?}", command),
+                source: e,
+            })?;
+
+        if !output.status.success() {
+            return Err(WebDriverError::VerificationError(
+                "Driver process exited with a non-zero status.".to_string(),
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|e| {
+            WebDriverError::CommandOutputParsingError {
+                command: format!("{:?}", command),
+                source: e,
+            }
+        })?;
+
+        // Prints e.g. "Microsoft Edge WebDriver 126.0.2592.87 (...)".
+        if !stdout.contains("Edge") {
+            return Err(WebDriverError::VerificationError(format!(
+                "Unexpected output during verification: {}",
+                stdout
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The OS suffix of the `LATEST_RELEASE_<major>_<OS>` files for `os`.
+fn os_tag_for(os: &str) -> &'static str {
+    match os {
+        "windows" => "WINDOWS",
+        "macos" => "MACOS",
+        _ => "LINUX",
+    }
+}
+
+/// Extracts the version from a `LATEST_*` file. They are served as UTF-16
+/// with a byte order mark, which shows up as NULs when read as UTF-8.
+fn parse_latest_release(body: &str) -> Option<String> {
+    let version: String = body.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Determines the msedgedriver platform identifier for a browser of the
+/// given architecture.
+fn edgedriver_platform_for(os: &str, arch: Arch) -> Result<&'static str, WebDriverError> {
+    match (os, arch) {
+        ("windows", Arch::X86_64) => Ok("win64"),
+        ("windows", Arch::X86) => Ok("win32"),
+        ("windows", Arch::Aarch64) => Ok("arm64"),
+        ("macos", Arch::X86_64) => Ok("mac64"),
+        ("macos", Arch::Aarch64) => Ok("mac64_m1"),
+        ("linux", Arch::X86_64) => Ok("linux64"),
+        _ => Err(WebDriverError::UnsupportedPlatform(format!("{}-{}", os, arch))),
+    }
+}
+
+/// The architecture of the msedgedriver builds of a platform identifier.
+fn platform_arch(platform: &str) -> Option<Arch> {
+    match platform {
+        "win32" => Some(Arch::X86),
+        "win64" | "mac64" | "linux64" => Some(Arch::X86_64),
+        "arm64" | "mac64_m1" => Some(Arch::Aarch64),
+        _ => None,
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_latest_release() {
+        assert_eq!(parse_latest_release("\u{feff}126.0.2592.87\r\n").as_deref(), Some("126.0.2592.87"));
+        assert_eq!(parse_latest_release("1\u{0}2\u{0}6\u{0}.\u{0}0\u{0}").as_deref(), Some("126.0"));
+        assert_eq!(parse_latest_release("  \n"), None);
+    }
+
+    #[test]
+    fn test_edgedriver_platform_for() {
+        assert_eq!(edgedriver_platform_for("windows", Arch::Aarch64).unwrap(), "arm64");
+        assert_eq!(edgedriver_platform_for("macos", Arch::Aarch64).unwrap(), "mac64_m1");
+        assert_eq!(edgedriver_platform_for("linux", Arch::X86_64).unwrap(), "linux64");
+        assert!(edgedriver_platform_for("linux", Arch::Aarch64).is_err());
+    }
+
+    #[test]
+    fn test_browser_arch_is_cached() {
+        let manager = EdgeDriver::new();
+        let _ = manager.platform();
+        assert!(manager.browser_arch.get().is_some());
+
+        // Clones share the inspected architecture, another channel doesn't.
+        assert!(Arc::ptr_eq(&manager.clone().browser_arch, &manager.browser_arch));
+        let beta = manager.with_channel(BrowserChannel::Beta);
+        assert!(beta.browser_arch.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_from_latest_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("LATEST_STABLE"), "\u{feff}128.0.2739.42\n").unwrap();
+        for (major, version) in [(128, "128.0.2739.42"), (127, "127.0.2651.105"), (126, "126.0.2592.113")] {
+            std::fs::write(dir.path().join(format!("LATEST_RELEASE_{}_{}", major, os_tag_for(std::env::consts::OS))), version).unwrap();
+        }
+
+        let base = dir.path().to_string_lossy().to_string();
+        let endpoints = crate::endpoints::Endpoints::new().edgedriver(base.clone());
+        let manager = EdgeDriver::new()
+            .with_arch(Arch::X86_64)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+        let platform = edgedriver_platform_for(std::env::consts::OS, Arch::X86_64).unwrap();
+
+        assert_eq!(manager.get_driver_version("127.0.2651.74").await.unwrap().to_string(), "127.0.2651.105");
+        assert_eq!(manager.get_driver_version("latest-stable").await.unwrap().to_string(), "128.0.2739.42");
+        assert_eq!(manager.get_driver_version("=126.0.2592.87").await.unwrap().to_string(), "126.0.2592.87");
+        assert_eq!(manager.get_driver_version("<128").await.unwrap().to_string(), "127.0.2651.105");
+        assert_eq!(
+            manager.get_download_url("126.0.2592.87").await.unwrap(),
+            format!("{}/126.0.2592.113/edgedriver_{}.zip", base, platform)
+        );
+        assert!(manager.get_driver_version("125.0.2535.92").await.is_err());
+        assert!(manager.get_driver_version(">=129").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wsl_windows_browser() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("LATEST_RELEASE_127_WINDOWS"), "127.0.2651.105").unwrap();
+
+        let base = dir.path().to_string_lossy().to_string();
+        let endpoints = crate::endpoints::Endpoints::new().edgedriver(base.clone());
+        let manager = EdgeDriver::new()
+            .with_wsl_windows_browser(true)
+            .with_download_options(DownloadOptions::new().endpoints(endpoints));
+
+        assert_eq!(manager.driver_platform(), "win64");
+        assert_eq!(manager.executable_name(), "msedgedriver.exe");
+        assert_eq!(
+            manager.get_download_url("127.0.2651.74").await.unwrap(),
+            format!("{}/127.0.2651.105/edgedriver_win64.zip", base)
+        );
+    }
+}
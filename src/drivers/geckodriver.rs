@@ -0,0 +1,466 @@
+//! Management of Mozilla's geckodriver for Firefox.
+//!
+//! Releases are resolved through the GitHub Releases API of
+//! `mozilla/geckodriver`. Mozilla ships `.tar.gz` archives for Linux and
+//! macOS and `.zip` archives for Windows.
+
+use crate::arch::Arch;
+use crate::error::WebDriverError;
+use crate::events::InstallEvent;
+use crate::browser::{channel_browser_name, get_browser_version, BrowserChannel};
+use crate::downloader::{download_and_unzip_with_options, DownloadOptions};
+use crate::drivers::github;
+use crate::source::DriverSource;
+use crate::version::{Version, VersionSpec};
+use crate::WebDriverManager;
+use crate::runtime;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// GitHub repository publishing the geckodriver releases.
+const GECKODRIVER_REPO: &str = "mozilla/geckodriver";
+
+// Prefix of the geckodriver release downloads.
+const GECKODRIVER_DOWNLOAD_BASE: &str = "https://github.com/mozilla/geckodriver/releases/download";
+
+/// The Firefox versions a geckodriver release supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FirefoxSupport {
+    driver: &'static str,
+    /// Oldest supported Firefox major version.
+    min_firefox: u32,
+    /// Newest supported Firefox major version, if the release has one.
+    max_firefox: Option<u32>,
+}
+
+impl FirefoxSupport {
+    const fn new(driver: &'static str, min_firefox: u32, max_firefox: Option<u32>) -> Self {
+        Self { driver, min_firefox, max_firefox }
+    }
+
+    fn supports(&self, firefox_major: u32) -> bool {
+        firefox_major >= self.min_firefox && self.max_firefox.is_none_or(|max| firefox_major <= max)
+    }
+}
+
+/// Geckodriver releases and the Firefox versions they support, newest first.
+/// Taken from Mozilla's "Supported platforms" documentation.
+const GECKODRIVER_FIREFOX_SUPPORT: &[FirefoxSupport] = &[
+    FirefoxSupport::new("0.36.0", 128, None),
+    FirefoxSupport::new("0.35.0", 115, None),
+    FirefoxSupport::new("0.34.0", 115, None),
+    FirefoxSupport::new("0.33.0", 102, Some(120)),
+    FirefoxSupport::new("0.32.2", 102, Some(120)),
+    FirefoxSupport::new("0.31.0", 91, Some(120)),
+    FirefoxSupport::new("0.30.0", 78, Some(90)),
+    FirefoxSupport::new("0.29.1", 60, Some(90)),
+    FirefoxSupport::new("0.26.0", 60, Some(90)),
+    FirefoxSupport::new("0.25.0", 57, Some(90)),
+    FirefoxSupport::new("0.24.0", 57, Some(79)),
+    FirefoxSupport::new("0.20.1", 55, Some(62)),
+];
+
+/// Public struct for managing geckodriver.
+#[derive(Debug, Clone, Default)]
+pub struct GeckoDriver {
+    channel: BrowserChannel,
+    source: DriverSource,
+    download_options: DownloadOptions,
+}
+
+impl GeckoDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Targets a Firefox release channel (ESR, Developer Edition or Nightly)
+    /// instead of the regular release. The geckodriver version still follows
+    /// from the detected Firefox version, so ESR gets an older driver when
+    /// the newest one no longer supports it.
+    pub fn with_channel(mut self, channel: BrowserChannel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Sets the source the driver archives are downloaded from.
+    pub fn with_source(mut self, source: DriverSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Sets the options used when downloading and extracting the driver.
+    pub fn with_download_options(mut self, options: DownloadOptions) -> Self {
+        self.download_options = options;
+        self
+    }
+
+    /// Accepts either a geckodriver version (`0.36.0`) or a Firefox version
+    /// (`128.0.3`), which is mapped to a compatible geckodriver version first.
+    /// This mirrors `ChromeDriver`, which takes the browser version. Other
+    /// [`VersionSpec`]s are accepted as well; every channel gets the newest
+    /// geckodriver. Ranges the bundled table has no release for, such as
+    /// `0.23.*`, are looked up in the GitHub releases.
+    async fn driver_version_for(&self, version: &str) -> Result<String, WebDriverError> {
+        match version.parse::<VersionSpec>()? {
+            VersionSpec::Exact(version) => Ok(version.trim_start_matches('v').to_string()),
+            VersionSpec::Browser(version) if version.starts_with("0.") => {
+                Ok(version.trim_start_matches('v').to_string())
+            }
+            VersionSpec::Browser(version) => geckodriver_version_for_firefox(&version),
+            VersionSpec::Range(req) => {
+                let known = GECKODRIVER_FIREFOX_SUPPORT
+                    .iter()
+                    .map(|support| support.driver)
+                    .find(|version| version.parse().is_ok_and(|v| req.matches(&v)));
+                if let Some(version) = known {
+                    return Ok(version.to_string());
+                }
+                github::find_in_releases(&self.download_options, GECKODRIVER_REPO, |release| {
+                    release
+                        .tag_name
+                        .parse::<Version>()
+                        .ok()
+                        .filter(|v| req.matches(v))
+                        .map(|v| v.to_string())
+                })
+                .await?
+                .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+                    browser_version: req.to_string(),
+                    platform: geckodriver_platform().map(|(p, _)| p).unwrap_or("unknown").to_string(),
+                })
+            }
+            _ => Ok(GECKODRIVER_FIREFOX_SUPPORT[0].driver.to_string()),
+        }
+    }
+
+    /// The download URL of an already resolved driver version.
+    async fn download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        match &self.source {
+            DriverSource::Upstream => get_geckodriver_download_url(&self.download_options, driver_version).await,
+            DriverSource::Repository(repository) => {
+                let (platform, ext) = geckodriver_platform()?;
+                Ok(repository.artifact_url(self.get_driver_name(), driver_version, platform, ext))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl WebDriverManager for GeckoDriver {
+    fn get_driver_name(&self) -> &str {
+        "geckodriver"
+    }
+
+    fn driver_platform(&self) -> String {
+        geckodriver_platform().map(|(platform, _)| platform).unwrap_or("unknown").to_string()
+    }
+
+    async fn get_browser_version(
+        &self, browser_path: Option<&Path>,
+    ) -> Result<String, WebDriverError> {
+        get_browser_version(&channel_browser_name("firefox", self.channel), browser_path).await
+    }
+
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        self.driver_version_for(browser_version).await?.parse()
+    }
+
+    async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        let driver_version = self.driver_version_for(driver_version).await?;
+        self.download_url(&driver_version).await
+    }
+
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        self.download_options.emit(InstallEvent::ResolvingVersion);
+        let driver_version = self.driver_version_for(browser_version).await?;
+        let url = self.download_url(&driver_version).await?;
+        Ok((driver_version.parse()?, url))
+    }
+
+    async fn download_and_install(
+        &self,
+        driver_version: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let (_driver_version, url) = self.resolve_download(driver_version).await?;
+        self.install_download(driver_version, &url, install_path).await
+    }
+
+    async fn install_download(
+        &self,
+        _driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let mut options = self.download_options.clone();
+        if let Some(credentials) = self.source.credentials() {
+            options.credentials = Some(credentials.clone());
+        }
+        if options.expected_arch.is_none() {
+            options.expected_arch = Arch::host();
+        }
+
+        let driver_name = self.get_driver_name();
+        let driver_path = download_and_unzip_with_options(url, install_path, driver_name, &options).await?;
+
+        options.emit(InstallEvent::Verifying);
+        self.verify_driver(&driver_path).await?;
+
+        options.emit(InstallEvent::Done { driver_path: driver_path.clone() });
+        Ok(driver_path)
+    }
+
+    /// The geckodriver releases on GitHub with an asset for the platform.
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        let (platform, ext) = geckodriver_platform()?;
+        let releases = github::fetch_all_releases(&self.download_options, GECKODRIVER_REPO).await?;
+
+        let mut versions: Vec<Version> = releases
+            .iter()
+            .filter(|release| {
+                let asset_name = format!("geckodriver-{}-{}.{}", release.tag_name, platform, ext);
+                release.assets.iter().any(|asset| asset.name == asset_name)
+            })
+            .filter_map(|release| release.tag_name.parse().ok())
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Any geckodriver release supporting the Firefox version is compatible,
+    /// not only the newest one.
+    async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+        browser_version
+            .parse::<Version>()
+            .is_ok_and(|firefox| firefox_support_for(driver_version).is_some_and(|s| s.supports(firefox.major)))
+    }
+
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+
+        let mut command = Command::new(driver_path);
+        command.arg("--version");
+
+        let output = runtime::output(&mut command)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: format!("{:?}", command),
+                source: e,
+            })?;
+
+        if !output.status.success() {
+            return Err(WebDriverError::VerificationError(
+                "Driver process exited with a non-zero status.".to_string(),
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|e| {
+            WebDriverError::CommandOutputParsingError {
+                command: format!("{:?}", command),
+                source: e,
+            }
+        })?;
+
+        if !stdout.contains("geckodriver") {
+            return Err(WebDriverError::VerificationError(format!(
+                "Unexpected output during verification: {}",
+                stdout
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Determines the platform identifier and archive extension used in the
+/// geckodriver asset names.
+fn geckodriver_platform() -> Result<(&'static str, &'static str), WebDriverError> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("windows", "x86_64") => Ok(("win64", "zip")),
+        ("windows", "x86") => Ok(("win32", "zip")),
+        ("windows", "aarch64") => Ok(("win-aarch64", "zip")),
+        ("macos", "x86_64") => Ok(("macos", "tar.gz")),
+        ("macos", "aarch64") => Ok(("macos-aarch64", "tar.gz")),
+        ("linux", "x86_64") => Ok(("linux64", "tar.gz")),
+        ("linux", "x86") => Ok(("linux32", "tar.gz")),
+        ("linux", "aarch64") => Ok(("linux-aarch64", "tar.gz")),
+        _ => Err(WebDriverError::UnsupportedPlatform(format!(
+            "{}-{}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))),
+    }
+}
+
+/// Maps a Firefox version to the newest geckodriver release supporting it,
+/// so e.g. Firefox ESR 115 doesn't get a release that dropped it.
+fn geckodriver_version_for_firefox(browser_version: &str) -> Result<String, WebDriverError> {
+    let major = browser_version
+        .parse::<Version>()
+        .map_err(|_| WebDriverError::BrowserVersionParsingError {
+            output: browser_version.to_string(),
+        })?
+        .major;
+
+    GECKODRIVER_FIREFOX_SUPPORT
+        .iter()
+        .find(|support| support.supports(major))
+        .map(|support| support.driver.to_string())
+        .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+            browser_version: browser_version.to_string(),
+            platform: geckodriver_platform().map(|(p, _)| p).unwrap_or("unknown").to_string(),
+        })
+}
+
+/// The Firefox versions a geckodriver release supports, taken from the
+/// newest table entry not newer than the release.
+fn firefox_support_for(driver_version: &Version) -> Option<FirefoxSupport> {
+    GECKODRIVER_FIREFOX_SUPPORT
+        .iter()
+        .find(|support| support.driver.parse::<Version>().is_ok_and(|v| v <= *driver_version))
+        .copied()
+}
+
+/// Fetches the download URL of a geckodriver release for the current platform.
+///
+/// With a download base override the URL is built from the release layout,
+/// so the GitHub API doesn't have to be reachable.
+async fn get_geckodriver_download_url(
+    options: &DownloadOptions,
+    driver_version: &str,
+) -> Result<String, WebDriverError> {
+    let (platform, ext) = geckodriver_platform()?;
+    if let Some(base) = options.endpoints.download_base_for("geckodriver") {
+        return Ok(format!("{0}/v{1}/geckodriver-v{1}-{2}.{3}", base, driver_version, platform, ext));
+    }
+
+    let release = match github::fetch_release(options, GECKODRIVER_REPO, &format!("v{}", driver_version)).await {
+        Ok(release) => release,
+        // Without the API (unreachable or rate limited), fall back to the
+        // release layout for the versions in the bundled table.
+        Err(e @ (WebDriverError::NetworkError(_) | WebDriverError::RateLimited { .. })) => {
+            if !GECKODRIVER_FIREFOX_SUPPORT.iter().any(|support| support.driver == driver_version) {
+                return Err(e);
+            }
+            return Ok(format!(
+                "{0}/v{1}/geckodriver-v{1}-{2}.{3}",
+                GECKODRIVER_DOWNLOAD_BASE, driver_version, platform, ext
+            ));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let asset_name = format!("geckodriver-{}-{}.{}", release.tag_name, platform, ext);
+    release
+        .assets
+        .into_iter()
+        .find(|asset| asset.name == asset_name)
+        .map(|asset| asset.browser_download_url)
+        .ok_or_else(|| WebDriverError::DriverUrlNotFound {
+            driver_version: driver_version.to_string(),
+            platform: platform.to_string(),
+        })
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "tokio")]
+    use crate::endpoints::Endpoints;
+    #[cfg(feature = "tokio")]
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn test_existing_driver_compatibility() {
+        let manager = GeckoDriver::new();
+        assert!(manager.is_compatible_driver(&"0.36.0".parse().unwrap(), "140.0.4").await);
+        assert!(manager.is_compatible_driver(&"0.34.0".parse().unwrap(), "128.2.0esr").await);
+        assert!(!manager.is_compatible_driver(&"0.30.0".parse().unwrap(), "60.0").await);
+        assert!(!manager.is_compatible_driver(&"0.19.0".parse().unwrap(), "128.0").await);
+        assert!(!manager.is_compatible_driver(&"0.33.0".parse().unwrap(), "128.0").await);
+    }
+
+    #[tokio::test]
+    async fn test_version_specs() {
+        let manager = GeckoDriver::new();
+        assert_eq!(manager.get_driver_version("=0.34.0").await.unwrap().to_string(), "0.34.0");
+        assert_eq!(manager.get_driver_version("latest").await.unwrap().to_string(), "0.36.0");
+        assert_eq!(manager.get_driver_version("115.13.0esr").await.unwrap().to_string(), "0.35.0");
+        assert_eq!(manager.get_driver_version(">=0.30, <0.34").await.unwrap().to_string(), "0.33.0");
+        assert!(manager.get_driver_version("0.40.*").await.is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_ranges_fall_back_to_github_releases() {
+        let server = TestServer::start(|request| {
+            if request.path.contains("page=2") {
+                return Response::ok(r#"[{"tag_name":"v0.23.0","assets":[]},{"tag_name":"v0.22.0","assets":[]}]"#);
+            }
+            let host = request.header("host").unwrap_or_default();
+            let next = format!("<http://{}/repos/mozilla/geckodriver/releases?per_page=100&page=2>; rel=\"next\"", host);
+            Response::ok(r#"[{"tag_name":"v0.36.0","assets":[]},{"tag_name":"v0.24.0","assets":[]}]"#).header("Link", next)
+        })
+        .await;
+        let options = DownloadOptions::new().endpoints(Endpoints::new().github_api(server.base()));
+        let manager = GeckoDriver::new().with_download_options(options);
+
+        assert_eq!(manager.get_driver_version("0.23.*").await.unwrap().to_string(), "0.23.0");
+        assert_eq!(manager.get_driver_version(">=0.21, <0.23").await.unwrap().to_string(), "0.22.0");
+        assert!(manager.get_driver_version("0.19.*").await.is_err());
+        assert!(server.requests().iter().any(|request| request.path.contains("page=2")));
+
+        // Ranges the bundled table covers don't hit the API.
+        let requests = server.requests().len();
+        assert_eq!(manager.get_driver_version("0.34.*").await.unwrap().to_string(), "0.34.0");
+        assert_eq!(server.requests().len(), requests);
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[tokio::test]
+    async fn test_ensure_installed() {
+        let (platform, _) = geckodriver_platform().unwrap();
+        let mut archive = Vec::new();
+        {
+            let encoder = flate2::write::GzEncoder::new(&mut archive, Default::default());
+            let mut builder = tar::Builder::new(encoder);
+            let script = b"#!/bin/sh\necho 'geckodriver 0.35.0'\n";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(script.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, "geckodriver", &script[..]).unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let server = TestServer::start(move |_| Response::ok(archive.clone())).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = crate::cache::Cache::new(dir.path());
+        let options = DownloadOptions::new().endpoints(Endpoints::new().download_base("geckodriver", server.base()));
+        let manager = GeckoDriver::new().with_download_options(options);
+
+        let driver_path = manager.ensure_installed(&cache, "115.13.0esr").await.unwrap();
+        assert!(driver_path.is_file());
+
+        let url = server.url(&format!("/v0.35.0/geckodriver-v0.35.0-{}.tar.gz", platform));
+        assert_eq!(server.requests().len(), 1);
+        let entry = cache.manifest().unwrap().installs.pop().unwrap();
+        assert_eq!(entry.version, "0.35.0");
+        assert_eq!(entry.source_url, Some(url));
+    }
+
+    #[test]
+    fn test_firefox_to_geckodriver_mapping() {
+        assert_eq!(geckodriver_version_for_firefox("140.0.4").unwrap(), "0.36.0");
+        assert_eq!(geckodriver_version_for_firefox("115.13.0esr").unwrap(), "0.35.0");
+        assert_eq!(geckodriver_version_for_firefox("128.2.0esr").unwrap(), "0.36.0");
+        assert_eq!(geckodriver_version_for_firefox("143.0a1").unwrap(), "0.36.0");
+        assert_eq!(geckodriver_version_for_firefox("91.0").unwrap(), "0.31.0");
+        assert_eq!(geckodriver_version_for_firefox("125.0.3").unwrap(), "0.35.0");
+        assert_eq!(geckodriver_version_for_firefox("62.0").unwrap(), "0.29.1");
+        assert!(geckodriver_version_for_firefox("40.0").is_err());
+        assert!(geckodriver_version_for_firefox("not-a-version").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Shared access to the GitHub Releases API, used by drivers released on GitHub.
+
+use crate::downloader::DownloadOptions;
+use crate::error::WebDriverError;
+use crate::http::HttpRequest;
+use crate::source::Credentials;
+use ::http::header::{HeaderMap, HeaderValue, ACCEPT, LINK, USER_AGENT};
+use ::http::StatusCode;
+use serde::Deserialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Upper bound on the release pages followed, in case of a `Link` loop.
+const MAX_RELEASE_PAGES: usize = 50;
+
+/// A single release asset in the GitHub API response.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GithubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// A single release in the GitHub API response.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GithubRelease {
+    pub tag_name: String,
+    pub assets: Vec<GithubAsset>,
+}
+
+/// Fetches the release with the given tag from `owner/repo`.
+pub(crate) async fn fetch_release(
+    options: &DownloadOptions,
+    repo: &str,
+    tag: &str,
+) -> Result<GithubRelease, WebDriverError> {
+    let url = format!("{}/repos/{}/releases/tags/{}", options.endpoints.github_api_url(), repo, tag);
+    let (release, _) = get_json(options, &url).await?;
+    Ok(release)
+}
+
+/// Fetches all releases of `owner/repo`, newest first, following the
+/// `Link` pagination.
+pub(crate) async fn fetch_all_releases(options: &DownloadOptions, repo: &str) -> Result<Vec<GithubRelease>, WebDriverError> {
+    let mut all = Vec::new();
+    find_in_releases(options, repo, |release| {
+        all.push(release.clone());
+        None::<()>
+    })
+    .await?;
+    Ok(all)
+}
+
+/// Walks the releases of `owner/repo`, newest first, until `find` returns a
+/// value. Further pages are only fetched while nothing matched, so older
+/// versions are found without always downloading the whole history.
+pub(crate) async fn find_in_releases<T>(
+    options: &DownloadOptions,
+    repo: &str,
+    mut find: impl FnMut(&GithubRelease) -> Option<T>,
+) -> Result<Option<T>, WebDriverError> {
+    let mut next = Some(releases_url(options, repo));
+    for _ in 0..MAX_RELEASE_PAGES {
+        let Some(url) = next.take() else { break };
+        let (releases, next_url) = get_json::<Vec<GithubRelease>>(options, &url).await?;
+        if let Some(found) = releases.iter().find_map(&mut find) {
+            return Ok(Some(found));
+        }
+        next = next_url;
+    }
+    Ok(None)
+}
+
+fn releases_url(options: &DownloadOptions, repo: &str) -> String {
+    format!("{}/repos/{}/releases?per_page=100", options.endpoints.github_api_url(), repo)
+}
+
+/// Fetches `url`, returning the body and the URL of the next page from the
+/// `Link` header.
+async fn get_json<T: serde::de::DeserializeOwned>(
+    options: &DownloadOptions,
+    url: &str,
+) -> Result<(T, Option<String>), WebDriverError> {
+    // The GitHub API rejects requests without a User-Agent.
+    let client = options.http_client_for(url)?;
+    let mut request = HttpRequest::get(url)
+        .header(USER_AGENT, HeaderValue::from_static("webdriver_manager"))
+        .header(ACCEPT, HeaderValue::from_static("application/vnd.github+json"));
+    if let Some(token) = options.github_token_or_env() {
+        request = Credentials::Bearer(token).apply(request)?;
+    }
+
+    let response = client.send(request).await?;
+    if let Some(error) = rate_limit_error(url, response.status(), response.headers()) {
+        return Err(error);
+    }
+    let response = response.error_for_status()?;
+    let next = response
+        .headers()
+        .get(LINK)
+        .and_then(|value| value.to_str().ok())
+        .and_then(next_page_url);
+    // Parsed separately from reading, so schema changes are reported with
+    // the URL and the start of the document.
+    let body = response.text().await?;
+    let value = serde_json::from_str(&body).map_err(|e| WebDriverError::json_parse(url, body.as_bytes(), e))?;
+    Ok((value, next))
+}
+
+/// The `rel="next"` URL in a `Link` header such as
+/// `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.trim().split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// The [`WebDriverError::RateLimited`] error for a rate limited response,
+/// with the reset time from `Retry-After` or `X-RateLimit-Reset`.
+fn rate_limit_error(url: &str, status: StatusCode, headers: &HeaderMap) -> Option<WebDriverError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim);
+    let retry_after = header("retry-after").and_then(|secs| secs.parse::<u64>().ok());
+    let exhausted = header("x-ratelimit-remaining") == Some("0");
+
+    // GitHub answers 403 for the primary rate limit, 403 or 429 with
+    // `Retry-After` for the secondary one.
+    let limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (exhausted || retry_after.is_some()));
+    if !limited {
+        return None;
+    }
+
+    let reset_at = match retry_after {
+        Some(secs) => Some(SystemTime::now() + Duration::from_secs(secs)),
+        None => header("x-ratelimit-reset")
+            .and_then(|epoch| epoch.parse::<u64>().ok())
+            .map(|epoch| UNIX_EPOCH + Duration::from_secs(epoch)),
+    };
+    Some(WebDriverError::RateLimited {
+        url: url.to_string(),
+        reset_at,
+    })
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "tokio")]
+    use crate::test_server::{Response, TestServer};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_rate_limit_error() {
+        let url = "https://api.github.com/repos/mozilla/geckodriver/releases";
+
+        let primary = headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1700000000")]);
+        match rate_limit_error(url, StatusCode::FORBIDDEN, &primary) {
+            Some(WebDriverError::RateLimited { reset_at, .. }) => {
+                assert_eq!(reset_at, Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+            }
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+
+        let secondary = headers(&[("retry-after", "60")]);
+        match rate_limit_error(url, StatusCode::TOO_MANY_REQUESTS, &secondary) {
+            Some(WebDriverError::RateLimited { reset_at: Some(reset_at), .. }) => {
+                assert!(reset_at > SystemTime::now() + Duration::from_secs(30));
+            }
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+
+        // A plain 403, e.g. a bad token, isn't a rate limit.
+        let remaining = headers(&[("x-ratelimit-remaining", "42")]);
+        assert!(rate_limit_error(url, StatusCode::FORBIDDEN, &remaining).is_none());
+        assert!(rate_limit_error(url, StatusCode::OK, &primary).is_none());
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let link = r#"<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=5>; rel="last""#;
+        assert_eq!(
+            next_page_url(link).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+        );
+        assert_eq!(next_page_url(r#"<https://api.github.com/x?page=1>; rel="prev""#), None);
+    }
+
+    /// Serves two pages of releases, linking the first to the second.
+    #[cfg(feature = "tokio")]
+    async fn serve_release_pages() -> String {
+        let server = TestServer::start(|request| {
+            if request.path.contains("page=2") {
+                return Response::ok(r#"[{"tag_name":"v0.20.1","assets":[]}]"#);
+            }
+            let host = request.header("host").unwrap_or_default();
+            let next = format!("<http://{}/repos/mozilla/geckodriver/releases?per_page=100&page=2>; rel=\"next\"", host);
+            Response::ok(r#"[{"tag_name":"v0.36.0","assets":[]}]"#).header("Link", next)
+        })
+        .await;
+        server.base().to_string()
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_releases_are_paginated() {
+        let base = serve_release_pages().await;
+        let options = DownloadOptions::new().endpoints(crate::endpoints::Endpoints::new().github_api(base));
+
+        let tags: Vec<_> = fetch_all_releases(&options, "mozilla/geckodriver")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|release| release.tag_name)
+            .collect();
+        assert_eq!(tags, ["v0.36.0", "v0.20.1"]);
+
+        let found = find_in_releases(&options, "mozilla/geckodriver", |release| {
+            (release.tag_name == "v0.20.1").then(|| release.tag_name.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(found.as_deref(), Some("v0.20.1"));
+    }
+}
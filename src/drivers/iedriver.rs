@@ -0,0 +1,310 @@
+//! Management of IEDriverServer for legacy Internet Explorer (IE mode) testing.
+//!
+//! IEDriverServer is published as an asset of the Selenium releases on
+//! GitHub, as `IEDriverServer_x64_<version>.zip` and
+//! `IEDriverServer_Win32_<version>.zip`.
+
+use crate::arch::Arch;
+use crate::error::WebDriverError;
+use crate::events::InstallEvent;
+use crate::downloader::{download_and_unzip_with_options, DownloadOptions};
+use crate::drivers::github;
+use crate::version::{Version, VersionSpec};
+use crate::WebDriverManager;
+use crate::runtime;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// GitHub repository publishing the IEDriverServer releases.
+const SELENIUM_REPO: &str = "SeleniumHQ/selenium";
+
+/// Which IEDriverServer build to install.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IEDriverArch {
+    /// The 32-bit build, often preferred because it types much faster.
+    Win32,
+    X64,
+}
+
+impl IEDriverArch {
+    /// The build matching the host architecture.
+    pub fn host() -> Self {
+        if std::env::consts::ARCH == "x86" {
+            IEDriverArch::Win32
+        } else {
+            IEDriverArch::X64
+        }
+    }
+
+    fn as_arch(&self) -> Arch {
+        match self {
+            IEDriverArch::Win32 => Arch::X86,
+            IEDriverArch::X64 => Arch::X86_64,
+        }
+    }
+
+    fn asset_tag(&self) -> &'static str {
+        match self {
+            IEDriverArch::Win32 => "Win32",
+            IEDriverArch::X64 => "x64",
+        }
+    }
+}
+
+/// Public struct for managing IEDriverServer.
+#[derive(Debug, Clone, Default)]
+pub struct IEDriver {
+    arch: Option<IEDriverArch>,
+    download_options: DownloadOptions,
+}
+
+impl IEDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forces the 32-bit or 64-bit build instead of the host architecture.
+    pub fn with_arch(mut self, arch: IEDriverArch) -> Self {
+        self.arch = Some(arch);
+        self
+    }
+
+    /// Sets the options used when downloading and extracting the driver.
+    pub fn with_download_options(mut self, options: DownloadOptions) -> Self {
+        self.download_options = options;
+        self
+    }
+
+    fn arch(&self) -> IEDriverArch {
+        self.arch.unwrap_or_else(IEDriverArch::host)
+    }
+
+    /// Internet Explorer versions (`11.x`) resolve to the latest
+    /// IEDriverServer; anything else, including an exact `=<version>` spec,
+    /// is treated as a driver version.
+    async fn driver_version_for(&self, version: &str) -> Result<String, WebDriverError> {
+        match version.parse::<VersionSpec>()? {
+            VersionSpec::Browser(version) if !version.starts_with("11.") => Ok(version),
+            _ => self.release_version(version).await,
+        }
+    }
+
+    /// The release [`WebDriverManager::get_driver_version`] resolves to, as
+    /// named in the asset.
+    async fn release_version(&self, browser_version: &str) -> Result<String, WebDriverError> {
+        let req = match browser_version.parse::<VersionSpec>()? {
+            VersionSpec::Exact(version) => return Ok(version),
+            VersionSpec::Range(req) => Some(req),
+            _ => None,
+        };
+        let prefix = format!("IEDriverServer_{}_", self.arch().asset_tag());
+
+        // Older releases are on later pages, only fetched for ranges that
+        // don't match a recent release.
+        github::find_in_releases(&self.download_options, SELENIUM_REPO, |release| {
+            release
+                .assets
+                .iter()
+                .filter_map(|asset| {
+                    asset
+                        .name
+                        .strip_prefix(&prefix)
+                        .and_then(|rest| rest.strip_suffix(".zip"))
+                })
+                .find(|version| req.as_ref().is_none_or(|req| version.parse().is_ok_and(|v| req.matches(&v))))
+                .map(str::to_string)
+        })
+        .await?
+        .ok_or_else(|| WebDriverError::DriverVersionNotFound {
+            browser_version: "Internet Explorer".to_string(),
+            platform: self.arch().asset_tag().to_string(),
+        })
+    }
+
+    /// The download URL of an already resolved driver version.
+    async fn download_url(&self, driver_version: String) -> Result<String, WebDriverError> {
+        let asset_name = ie_asset_name(self.arch(), &driver_version);
+
+        // IEDriverServer assets are attached to the Selenium release of the
+        // same version, search the release list for the asset by name.
+        github::find_in_releases(&self.download_options, SELENIUM_REPO, |release| {
+            release.assets.iter().find(|asset| asset.name == asset_name).cloned()
+        })
+        .await?
+        .map(|asset| {
+            self.download_options
+                .endpoints
+                .rewrite_download_url(self.get_driver_name(), &asset.browser_download_url)
+        })
+        .ok_or_else(|| WebDriverError::DriverUrlNotFound {
+            driver_version,
+            platform: self.arch().asset_tag().to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl WebDriverManager for IEDriver {
+    fn get_driver_name(&self) -> &str {
+        "IEDriverServer"
+    }
+
+    fn driver_platform(&self) -> String {
+        self.arch().asset_tag().to_string()
+    }
+
+    /// Reads the Internet Explorer version from the registry.
+    async fn get_browser_version(
+        &self, _browser_path: Option<&Path>,
+    ) -> Result<String, WebDriverError> {
+        if !cfg!(target_os = "windows") {
+            return Err(WebDriverError::BrowserNotFound);
+        }
+
+        let command_str = r"reg query HKLM\SOFTWARE\Microsoft\Internet Explorer /v svcVersion";
+        let mut command = Command::new("reg");
+        command.args(["query", r"HKLM\SOFTWARE\Microsoft\Internet Explorer", "/v", "svcVersion"]);
+        let output = runtime::output(&mut command)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: command_str.to_string(),
+                source: e,
+            })?;
+
+        if !output.status.success() {
+            return Err(WebDriverError::BrowserNotFound);
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|e| {
+            WebDriverError::CommandOutputParsingError {
+                command: command_str.to_string(),
+                source: e,
+            }
+        })?;
+
+        // "    svcVersion    REG_SZ    11.1.19041.0"
+        stdout
+            .lines()
+            .find(|line| line.contains("svcVersion"))
+            .and_then(|line| line.split_whitespace().last())
+            .map(str::to_string)
+            .ok_or(WebDriverError::BrowserVersionParsingError { output: stdout })
+    }
+
+    /// IEDriverServer is not tied to the IE version; the latest release (in
+    /// the range, if one is given) is used unless an exact version is pinned.
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        self.release_version(browser_version).await?.parse()
+    }
+
+    /// The IEDriverServer releases for the configured architecture.
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        let releases = github::fetch_all_releases(&self.download_options, SELENIUM_REPO).await?;
+        let prefix = format!("IEDriverServer_{}_", self.arch().asset_tag());
+
+        let mut versions: Vec<Version> = releases
+            .iter()
+            .flat_map(|release| &release.assets)
+            .filter_map(|asset| asset.name.strip_prefix(&prefix)?.strip_suffix(".zip")?.parse().ok())
+            .collect();
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        let driver_version = self.driver_version_for(driver_version).await?;
+        self.download_url(driver_version).await
+    }
+
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        self.download_options.emit(InstallEvent::ResolvingVersion);
+        let driver_version = self.driver_version_for(browser_version).await?;
+        let url = self.download_url(driver_version.clone()).await?;
+        Ok((driver_version.parse()?, url))
+    }
+
+    async fn download_and_install(
+        &self,
+        driver_version: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let (_driver_version, url) = self.resolve_download(driver_version).await?;
+        self.install_download(driver_version, &url, install_path).await
+    }
+
+    async fn install_download(
+        &self,
+        _driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let mut options = self.download_options.clone();
+        if options.expected_arch.is_none() {
+            options.expected_arch = Some(self.arch().as_arch());
+        }
+
+        let driver_name = self.get_driver_name();
+        let driver_path = download_and_unzip_with_options(url, install_path, driver_name, &options).await?;
+
+        options.emit(InstallEvent::Verifying);
+        self.verify_driver(&driver_path).await?;
+
+        options.emit(InstallEvent::Done { driver_path: driver_path.clone() });
+        Ok(driver_path)
+    }
+
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+
+        let mut command = Command::new(driver_path);
+        command.arg("--version");
+
+        let output = runtime::output(&mut command)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: format!("{:?}", command),
+                source: e,
+            })?;
+
+        if !output.status.success() {
+            return Err(WebDriverError::VerificationError(
+                "Driver process exited with a non-zero status.".to_string(),
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|e| {
+            WebDriverError::CommandOutputParsingError {
+                command: format!("{:?}", command),
+                source: e,
+            }
+        })?;
+
+        // Prints e.g. "IEDriverServer.exe 4.14.0.0 (64-bit)".
+        if !stdout.contains("IEDriverServer") {
+            return Err(WebDriverError::VerificationError(format!(
+                "Unexpected output during verification: {}",
+                stdout
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn ie_asset_name(arch: IEDriverArch, driver_version: &str) -> String {
+    format!("IEDriverServer_{}_{}.zip", arch.asset_tag(), driver_version)
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ie_asset_name() {
+        assert_eq!(ie_asset_name(IEDriverArch::Win32, "4.14.0"), "IEDriverServer_Win32_4.14.0.zip");
+        assert_eq!(ie_asset_name(IEDriverArch::X64, "4.14.0"), "IEDriverServer_x64_4.14.0.zip");
+    }
+}
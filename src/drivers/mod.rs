@@ -1,3 +1,233 @@
 //! Description [TODO]
 
-pub mod chromedriver;
\ No newline at end of file
+use crate::browser::Browser;
+use crate::cache::Cache;
+use crate::downloader::DownloadOptions;
+use crate::error::WebDriverError;
+use crate::version::Version;
+use crate::WebDriverManager;
+use crate::runtime;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub mod chromedriver;
+pub mod chromium_based;
+pub mod edgedriver;
+pub mod geckodriver;
+mod github;
+pub mod iedriver;
+pub mod safaridriver;
+
+use chromedriver::ChromeDriver;
+use edgedriver::EdgeDriver;
+use geckodriver::GeckoDriver;
+use iedriver::IEDriver;
+use safaridriver::SafariDriver;
+
+/// Any of the crate's driver managers, dispatching statically to the
+/// wrapped manager. This is the recommended entry point:
+///
+/// ```no_run
+/// # async fn run() -> Result<(), webdriver_manager::WebDriverError> {
+/// use webdriver_manager::{Browser, Driver, WebDriverManager};
+///
+/// let driver = Driver::from(Browser::Chrome);
+/// let browser_version = driver.get_browser_version(None).await?;
+/// let path = driver.download_and_install(&browser_version, "./drivers".as_ref()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub enum Driver {
+    Chrome(ChromeDriver),
+    Edge(EdgeDriver),
+    Firefox(GeckoDriver),
+    Safari(SafariDriver),
+    InternetExplorer(IEDriver),
+}
+
+impl From<Browser> for Driver {
+    fn from(browser: Browser) -> Self {
+        match browser {
+            Browser::Chrome => Driver::Chrome(ChromeDriver::new()),
+            Browser::Chromium => Driver::Chrome(ChromeDriver::for_browser(Browser::Chromium)),
+            Browser::Brave => Driver::Chrome(ChromeDriver::for_browser(Browser::Brave)),
+            Browser::Edge => Driver::Edge(EdgeDriver::new()),
+            Browser::Firefox => Driver::Firefox(GeckoDriver::new()),
+            Browser::Safari => Driver::Safari(SafariDriver::new()),
+            Browser::InternetExplorer => Driver::InternetExplorer(IEDriver::new()),
+        }
+    }
+}
+
+impl Driver {
+    /// Sets the options used when downloading and extracting the driver.
+    /// safaridriver isn't downloaded and ignores them.
+    pub fn with_download_options(self, options: DownloadOptions) -> Self {
+        match self {
+            Driver::Chrome(m) => Driver::Chrome(m.with_download_options(options)),
+            Driver::Edge(m) => Driver::Edge(m.with_download_options(options)),
+            Driver::Firefox(m) => Driver::Firefox(m.with_download_options(options)),
+            Driver::Safari(m) => Driver::Safari(m),
+            Driver::InternetExplorer(m) => Driver::InternetExplorer(m.with_download_options(options)),
+        }
+    }
+
+    /// Uses the browser at `path` to pick the platform of the driver. Only
+    /// chromedriver has builds for several architectures of one OS; the
+    /// other managers ignore it.
+    pub fn with_browser_path(self, path: impl Into<PathBuf>) -> Self {
+        match self {
+            Driver::Chrome(m) => Driver::Chrome(m.with_browser_path(path)),
+            other => other,
+        }
+    }
+}
+
+/// Forwards a call to the manager wrapped by a [`Driver`].
+macro_rules! dispatch {
+    ($driver:expr, $manager:ident => $call:expr) => {
+        match $driver {
+            Driver::Chrome($manager) => $call,
+            Driver::Edge($manager) => $call,
+            Driver::Firefox($manager) => $call,
+            Driver::Safari($manager) => $call,
+            Driver::InternetExplorer($manager) => $call,
+        }
+    };
+}
+
+#[async_trait]
+impl WebDriverManager for Driver {
+    fn get_driver_name(&self) -> &str {
+        dispatch!(self, m => m.get_driver_name())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), ret, err))]
+    async fn get_browser_version(&self, browser_path: Option<&Path>) -> Result<String, WebDriverError> {
+        dispatch!(self, m => m.get_browser_version(browser_path).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), ret, err))]
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        dispatch!(self, m => m.get_driver_version(browser_version).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), ret, err))]
+    async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError> {
+        dispatch!(self, m => m.get_download_url(driver_version).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), err))]
+    async fn download_and_install(
+        &self,
+        driver_version: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        dispatch!(self, m => m.download_and_install(driver_version, install_path).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), ret, err))]
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        dispatch!(self, m => m.resolve_download(browser_version).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), err))]
+    async fn install_download(
+        &self,
+        driver_version: &str,
+        url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        dispatch!(self, m => m.install_download(driver_version, url, install_path).await)
+    }
+
+    fn driver_platform(&self) -> String {
+        dispatch!(self, m => m.driver_platform())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self, cache), fields(driver = self.get_driver_name()), err))]
+    async fn install_in_cache(&self, cache: &Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        dispatch!(self, m => m.install_in_cache(cache, browser_version).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self, cache), fields(driver = self.get_driver_name()), err))]
+    async fn ensure_installed(&self, cache: &Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        dispatch!(self, m => m.ensure_installed(cache, browser_version).await)
+    }
+
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        dispatch!(self, m => m.list_available_versions().await)
+    }
+
+    async fn installed_driver_version(&self, driver_path: &Path) -> Result<Version, WebDriverError> {
+        dispatch!(self, m => m.installed_driver_version(driver_path).await)
+    }
+
+    async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+        dispatch!(self, m => m.is_compatible_driver(driver_version, browser_version).await)
+    }
+
+    async fn find_existing_driver(&self, browser_version: &str) -> Option<PathBuf> {
+        dispatch!(self, m => m.find_existing_driver(browser_version).await)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(driver = self.get_driver_name()), err))]
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+        dispatch!(self, m => m.verify_driver(driver_path).await)
+    }
+}
+
+/// Runs `<driver> --version` and extracts the version number from its output.
+///
+/// Works for drivers that print `<name> <version> ...`, such as
+/// `ChromeDriver 138.0.7204.157 (...)` or `geckodriver 0.36.0 (...)`, and
+/// for msedgedriver's `Microsoft Edge WebDriver 126.0.2592.87 (...)`.
+pub async fn driver_binary_version(driver_path: &Path) -> Result<String, WebDriverError> {
+    let command = format!("'{}' --version", driver_path.to_string_lossy());
+    let output = runtime::output(Command::new(driver_path).arg("--version"))
+        .await
+        .map_err(|e| WebDriverError::CommandExecutionError {
+            command: command.clone(),
+            source: e,
+        })?;
+
+    let stdout = String::from_utf8(output.stdout).map_err(|e| {
+        WebDriverError::CommandOutputParsingError { command, source: e }
+    })?;
+
+    parse_driver_version(&stdout).ok_or(WebDriverError::BrowserVersionParsingError { output: stdout })
+}
+
+/// Extracts the first dotted version number from `--version` output.
+fn parse_driver_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find(|s| s.chars().next().is_some_and(|c| c.is_ascii_digit()) && s.contains('.'))
+        .map(|s| s.trim_end_matches(|c: char| !c.is_ascii_digit()).to_string())
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_driver_version() {
+        assert_eq!(
+            parse_driver_version("ChromeDriver 138.0.7204.157 (b9c2a0bc4f2a3f1e-refs/branch-heads/7204@{#2106})\n").as_deref(),
+            Some("138.0.7204.157")
+        );
+        assert_eq!(
+            parse_driver_version("geckodriver 0.36.0 (a3d508507022 2025-02-24 15:57 +0000)").as_deref(),
+            Some("0.36.0")
+        );
+        assert_eq!(
+            parse_driver_version("Microsoft Edge WebDriver 126.0.2592.87 (a8e5d2a3b7c1)").as_deref(),
+            Some("126.0.2592.87")
+        );
+        assert_eq!(parse_driver_version("no version here"), None);
+    }
+}
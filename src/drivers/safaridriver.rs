@@ -0,0 +1,154 @@
+//! Management of Apple's safaridriver on macOS.
+//!
+//! `safaridriver` ships with macOS and cannot be downloaded, so
+//! `download_and_install` only validates the system binary.
+
+use crate::browser::plist_string_value;
+use crate::cache::Cache;
+use crate::error::WebDriverError;
+use crate::version::Version;
+use crate::WebDriverManager;
+use crate::runtime::{self, fs};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Location of the system safaridriver.
+const SAFARIDRIVER_PATH: &str = "/usr/bin/safaridriver";
+
+// Default location of the Safari application bundle.
+const SAFARI_APP_PATH: &str = "/Applications/Safari.app";
+
+/// Public struct for managing safaridriver.
+#[derive(Debug, Clone, Default)]
+pub struct SafariDriver;
+
+impl SafariDriver {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Returns the path of the system safaridriver if it exists.
+    fn system_driver_path(&self) -> Result<PathBuf, WebDriverError> {
+        if !cfg!(target_os = "macos") {
+            return Err(WebDriverError::UnsupportedPlatform(format!(
+                "safaridriver is only available on macOS, not {}",
+                std::env::consts::OS
+            )));
+        }
+
+        let path = PathBuf::from(SAFARIDRIVER_PATH);
+        if path.exists() {
+            Ok(path)
+        } else {
+            Err(WebDriverError::DriverExecutableNotFound { path })
+        }
+    }
+}
+
+#[async_trait]
+impl WebDriverManager for SafariDriver {
+    fn get_driver_name(&self) -> &str {
+        "safaridriver"
+    }
+
+    /// Reads `CFBundleShortVersionString` from Safari's `Info.plist`.
+    /// `browser_path` may point at `Safari.app` or at its executable.
+    async fn get_browser_version(
+        &self, browser_path: Option<&Path>,
+    ) -> Result<String, WebDriverError> {
+        let app_path = browser_path
+            .map(|p| {
+                p.ancestors()
+                    .find(|a| a.extension().is_some_and(|ext| ext == "app"))
+                    .unwrap_or(p)
+                    .to_path_buf()
+            })
+            .unwrap_or_else(|| PathBuf::from(SAFARI_APP_PATH));
+
+        let plist_path = app_path.join("Contents").join("Info.plist");
+        if !plist_path.exists() {
+            return Err(WebDriverError::BrowserNotFound);
+        }
+
+        let content = fs::read(&plist_path)
+            .await
+            .map_err(|e| WebDriverError::IoError {
+                path: plist_path.clone(),
+                source: e,
+            })?;
+        let content = String::from_utf8_lossy(&content);
+
+        plist_string_value(&content, "CFBundleShortVersionString")
+            .ok_or_else(|| WebDriverError::BrowserVersionParsingError {
+                output: plist_path.display().to_string(),
+            })
+    }
+
+    /// safaridriver is versioned together with Safari.
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+        browser_version.parse()
+    }
+
+    async fn get_download_url(&self, _driver_version: &str) -> Result<String, WebDriverError> {
+        Err(WebDriverError::DriverNotDownloadable(self.get_driver_name().to_string()))
+    }
+
+    /// Validates the system safaridriver instead of downloading anything.
+    /// Both arguments are ignored; the returned path is always the system binary.
+    async fn download_and_install(
+        &self,
+        _driver_version: &str,
+        _install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        let driver_path = self.system_driver_path()?;
+        self.verify_driver(&driver_path).await?;
+        Ok(driver_path)
+    }
+
+    /// There is nothing to cache; returns the validated system binary.
+    async fn install_in_cache(&self, _cache: &Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        self.download_and_install(browser_version, Path::new("")).await
+    }
+
+    /// There is nothing to cache; returns the validated system binary.
+    async fn ensure_installed(&self, _cache: &Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        self.download_and_install(browser_version, Path::new("")).await
+    }
+
+    async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError> {
+
+        let mut command = Command::new(driver_path);
+        command.arg("--version");
+
+        let output = runtime::output(&mut command)
+            .await
+            .map_err(|e| WebDriverError::CommandExecutionError {
+                command: format!("{:?}", command),
+                source: e,
+            })?;
+
+        if !output.status.success() {
+            return Err(WebDriverError::VerificationError(
+                "Driver process exited with a non-zero status.".to_string(),
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|e| {
+            WebDriverError::CommandOutputParsingError {
+                command: format!("{:?}", command),
+                source: e,
+            }
+        })?;
+
+        // Prints e.g. "Included with Safari 17.0 (19616.1.27.211.1)".
+        if !stdout.contains("Safari") {
+            return Err(WebDriverError::VerificationError(format!(
+                "Unexpected output during verification: {}",
+                stdout
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! Overrides of the upstream endpoints, for mirrored or air-gapped setups.
+//!
+//! Every endpoint can be set on [`Endpoints`]. [`crate::Config`] fills them in
+//! from `webdriver-manager.toml` and the `WDM_*` environment variables, and
+//! this module doesn't read the environment itself. Download base
+//! overrides replace the upstream prefix of a driver's download URLs, so
+//! `https://storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip`
+//! with the base `https://mirror.corp/cft` becomes
+//! `https://mirror.corp/cft/138.0.7204.157/linux64/chromedriver-linux64.zip`.
+//!
+//! A [`MirrorPreset`] fills in all endpoints of a well-known public mirror at
+//! once, for regions where the Google and GitHub endpoints are unreachable.
+//! Individual overrides still take precedence over the preset.
+
+use crate::drivers::chromedriver::{
+    CHROMEDRIVER_BUILDS_ENDPOINT, CHROMEDRIVER_CHANNELS_ENDPOINT, CHROMEDRIVER_LEGACY_ENDPOINT,
+    CHROMEDRIVER_MILESTONES_ENDPOINT, CHROMEDRIVER_URLS_ENDPOINT,
+};
+use crate::drivers::edgedriver::EDGEDRIVER_ENDPOINT;
+use crate::error::WebDriverError;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Upstream prefixes of the download URLs of each driver.
+const UPSTREAM_DOWNLOAD_BASES: &[(&str, &str)] = &[
+    ("chromedriver", "https://storage.googleapis.com/chrome-for-testing-public"),
+    ("chromedriver", "https://edgedl.me.gvt1.com/edgedl/chrome/chrome-for-testing"),
+    ("geckodriver", "https://github.com/mozilla/geckodriver/releases/download"),
+    ("msedgedriver", EDGEDRIVER_ENDPOINT),
+    ("IEDriverServer", "https://github.com/SeleniumHQ/selenium/releases/download"),
+];
+
+/// Well-known public mirrors of the driver downloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MirrorPreset {
+    /// The npmmirror.com binary mirror (formerly npm.taobao.org/mirrors).
+    Npmmirror,
+}
+
+impl MirrorPreset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MirrorPreset::Npmmirror => "npmmirror",
+        }
+    }
+
+    fn chromedriver_versions(&self) -> Option<&'static str> {
+        match self {
+            MirrorPreset::Npmmirror => {
+                Some("https://cdn.npmmirror.com/binaries/chrome-for-testing/known-good-versions-with-downloads.json")
+            }
+        }
+    }
+
+    fn chromedriver_channels(&self) -> Option<&'static str> {
+        match self {
+            MirrorPreset::Npmmirror => {
+                Some("https://cdn.npmmirror.com/binaries/chrome-for-testing/last-known-good-versions-with-downloads.json")
+            }
+        }
+    }
+
+    fn chromedriver_builds(&self) -> Option<&'static str> {
+        match self {
+            MirrorPreset::Npmmirror => Some(
+                "https://cdn.npmmirror.com/binaries/chrome-for-testing/latest-patch-versions-per-build-with-downloads.json",
+            ),
+        }
+    }
+
+    fn chromedriver_milestones(&self) -> Option<&'static str> {
+        match self {
+            MirrorPreset::Npmmirror => Some(
+                "https://cdn.npmmirror.com/binaries/chrome-for-testing/latest-versions-per-milestone-with-downloads.json",
+            ),
+        }
+    }
+
+    fn chromedriver_legacy(&self) -> Option<&'static str> {
+        match self {
+            MirrorPreset::Npmmirror => Some("https://cdn.npmmirror.com/binaries/chromedriver"),
+        }
+    }
+
+    /// The download base of `driver` on this mirror, for drivers it hosts.
+    fn download_base(&self, driver: &str) -> Option<&'static str> {
+        match (self, driver) {
+            (MirrorPreset::Npmmirror, "chromedriver") => Some("https://cdn.npmmirror.com/binaries/chrome-for-testing"),
+            (MirrorPreset::Npmmirror, "geckodriver") => Some("https://cdn.npmmirror.com/binaries/geckodriver"),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for MirrorPreset {
+    type Err = WebDriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "npmmirror" | "taobao" => Ok(MirrorPreset::Npmmirror),
+            other => Err(WebDriverError::Custom(format!("Unknown mirror preset: '{}'", other))),
+        }
+    }
+}
+
+/// Endpoint overrides. The defaults are the upstream endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Endpoints {
+    /// URL of `known-good-versions-with-downloads.json`.
+    pub chromedriver_versions: Option<String>,
+    /// URL of `last-known-good-versions-with-downloads.json`.
+    pub chromedriver_channels: Option<String>,
+    /// URL of `latest-patch-versions-per-build-with-downloads.json`.
+    pub chromedriver_builds: Option<String>,
+    /// URL of `latest-versions-per-milestone-with-downloads.json`.
+    pub chromedriver_milestones: Option<String>,
+    /// Base URL of the legacy chromedriver storage, holding the
+    /// `LATEST_RELEASE_<version>` files and the builds before Chrome 115.
+    pub chromedriver_legacy: Option<String>,
+    /// Base URL of the msedgedriver storage, holding the `LATEST_*` files and
+    /// the builds.
+    pub edgedriver: Option<String>,
+    /// Base URL of the GitHub API (e.g. of a GitHub Enterprise instance).
+    pub github_api: Option<String>,
+    /// Download base URL per driver name.
+    pub download_bases: HashMap<String, String>,
+    /// Public mirror providing the endpoints that are not set explicitly.
+    pub mirror: Option<MirrorPreset>,
+}
+
+impl Endpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the endpoints of a public mirror.
+    pub fn mirror(mut self, preset: MirrorPreset) -> Self {
+        self.mirror = Some(preset);
+        self
+    }
+
+    /// Sets the URL of the chromedriver known-good-versions JSON.
+    pub fn chromedriver_versions(mut self, url: impl Into<String>) -> Self {
+        self.chromedriver_versions = Some(url.into());
+        self
+    }
+
+    /// Sets the URL of the chromedriver last-known-good-versions JSON.
+    pub fn chromedriver_channels(mut self, url: impl Into<String>) -> Self {
+        self.chromedriver_channels = Some(url.into());
+        self
+    }
+
+    /// Sets the URL of the chromedriver latest-patch-versions-per-build JSON.
+    pub fn chromedriver_builds(mut self, url: impl Into<String>) -> Self {
+        self.chromedriver_builds = Some(url.into());
+        self
+    }
+
+    /// Sets the URL of the chromedriver latest-versions-per-milestone JSON.
+    pub fn chromedriver_milestones(mut self, url: impl Into<String>) -> Self {
+        self.chromedriver_milestones = Some(url.into());
+        self
+    }
+
+    /// Sets the base URL of the legacy chromedriver storage.
+    pub fn chromedriver_legacy(mut self, url: impl Into<String>) -> Self {
+        self.chromedriver_legacy = Some(url.into());
+        self
+    }
+
+    /// Sets the base URL of the msedgedriver storage.
+    pub fn edgedriver(mut self, url: impl Into<String>) -> Self {
+        self.edgedriver = Some(url.into());
+        self
+    }
+
+    /// Sets the base URL of the GitHub API.
+    pub fn github_api(mut self, url: impl Into<String>) -> Self {
+        self.github_api = Some(url.into());
+        self
+    }
+
+    /// Downloads the archives of `driver` (e.g. `"chromedriver"`) from `base`
+    /// instead of the upstream location.
+    pub fn download_base(mut self, driver: impl Into<String>, base: impl Into<String>) -> Self {
+        self.download_bases.insert(driver.into(), base.into());
+        self
+    }
+
+    pub(crate) fn chromedriver_versions_url(&self) -> String {
+        let preset = self.mirror.and_then(|preset| preset.chromedriver_versions());
+        resolve(&self.chromedriver_versions, preset.unwrap_or(CHROMEDRIVER_URLS_ENDPOINT))
+    }
+
+    pub(crate) fn chromedriver_channels_url(&self) -> String {
+        let preset = self.mirror.and_then(|preset| preset.chromedriver_channels());
+        resolve(&self.chromedriver_channels, preset.unwrap_or(CHROMEDRIVER_CHANNELS_ENDPOINT))
+    }
+
+    pub(crate) fn chromedriver_builds_url(&self) -> String {
+        let preset = self.mirror.and_then(|preset| preset.chromedriver_builds());
+        resolve(&self.chromedriver_builds, preset.unwrap_or(CHROMEDRIVER_BUILDS_ENDPOINT))
+    }
+
+    pub(crate) fn chromedriver_milestones_url(&self) -> String {
+        let preset = self.mirror.and_then(|preset| preset.chromedriver_milestones());
+        resolve(&self.chromedriver_milestones, preset.unwrap_or(CHROMEDRIVER_MILESTONES_ENDPOINT))
+    }
+
+    pub(crate) fn chromedriver_legacy_url(&self) -> String {
+        let preset = self.mirror.and_then(|preset| preset.chromedriver_legacy());
+        resolve(&self.chromedriver_legacy, preset.unwrap_or(CHROMEDRIVER_LEGACY_ENDPOINT))
+        .trim_end_matches('/')
+        .to_string()
+    }
+
+    pub(crate) fn edgedriver_url(&self) -> String {
+        resolve(&self.edgedriver, EDGEDRIVER_ENDPOINT)
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    pub(crate) fn github_api_url(&self) -> String {
+        resolve(&self.github_api, GITHUB_API)
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// The download base configured for `driver`, either explicitly or by
+    /// the mirror preset.
+    pub(crate) fn download_base_for(&self, driver: &str) -> Option<String> {
+        self.download_bases
+            .get(driver)
+            .cloned()
+            .or_else(|| Some(self.mirror?.download_base(driver)?.to_string()))
+            .map(|base| base.trim_end_matches('/').to_string())
+    }
+
+    /// Rewrites an upstream download URL of `driver` onto its configured
+    /// download base. URLs from other hosts are returned unchanged.
+    pub(crate) fn rewrite_download_url(&self, driver: &str, url: &str) -> String {
+        let Some(base) = self.download_base_for(driver) else {
+            return url.to_string();
+        };
+        UPSTREAM_DOWNLOAD_BASES
+            .iter()
+            .filter(|(name, _)| *name == driver)
+            .find_map(|(_, upstream)| url.strip_prefix(upstream))
+            .map(|rest| format!("{}{}", base, rest))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+/// The drivers with download URLs a download base can replace.
+pub(crate) fn downloaded_drivers() -> impl Iterator<Item = &'static str> {
+    let mut drivers: Vec<_> = UPSTREAM_DOWNLOAD_BASES.iter().map(|(driver, _)| *driver).collect();
+    drivers.dedup();
+    drivers.into_iter()
+}
+
+fn resolve(value: &Option<String>, default: &str) -> String {
+    value.clone().unwrap_or_else(|| default.to_string())
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_download_url() {
+        let endpoints = Endpoints::new()
+            .download_base("chromedriver", "https://mirror.corp/cft/")
+            .chromedriver_versions("https://mirror.corp/cft/known-good-versions-with-downloads.json");
+
+        assert_eq!(
+            endpoints.rewrite_download_url(
+                "chromedriver",
+                "https://storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip"
+            ),
+            "https://mirror.corp/cft/138.0.7204.157/linux64/chromedriver-linux64.zip"
+        );
+        assert_eq!(
+            endpoints.rewrite_download_url("chromedriver", "https://elsewhere.example/chromedriver.zip"),
+            "https://elsewhere.example/chromedriver.zip"
+        );
+        assert_eq!(
+            endpoints.chromedriver_versions_url(),
+            "https://mirror.corp/cft/known-good-versions-with-downloads.json"
+        );
+    }
+
+    #[test]
+    fn test_mirror_preset() {
+        assert_eq!("Taobao".parse::<MirrorPreset>().unwrap(), MirrorPreset::Npmmirror);
+        assert!("nowhere".parse::<MirrorPreset>().is_err());
+
+        let endpoints = Endpoints::new()
+            .mirror(MirrorPreset::Npmmirror)
+            .download_base("geckodriver", "https://mirror.corp/geckodriver");
+        assert_eq!(
+            endpoints.rewrite_download_url(
+                "chromedriver",
+                "https://storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip"
+            ),
+            "https://cdn.npmmirror.com/binaries/chrome-for-testing/138.0.7204.157/linux64/chromedriver-linux64.zip"
+        );
+        assert_eq!(endpoints.download_base_for("geckodriver").unwrap(), "https://mirror.corp/geckodriver");
+        assert_eq!(endpoints.download_base_for("IEDriverServer"), None);
+    }
+}
@@ -1,6 +1,7 @@
+use crate::http::HttpError;
+use ::http::StatusCode;
 use thiserror::Error;
 use std::path::PathBuf;
-use std::io;
 
 /// Error type for all possible failures in the library.
 #[derive(Error, Debug)]
@@ -27,14 +28,20 @@ pub enum WebDriverError {
         output: String,
     },
 
+    #[error("Invalid version: '{0}'")]
+    InvalidVersion(String),
+
     #[error("Network request failed: {0}")]
-    NetworkError(#[from] reqwest::Error),
+    NetworkError(#[from] HttpError),
 
-    #[error("Failed to parse JSON response from '{url}': {source}")]
+    #[error("Failed to parse JSON response from '{url}': {source}{}", snippet_suffix(.snippet))]
     JsonParseError {
         url: String,
         #[source]
         source: serde_json::Error,
+        /// The start of the document that failed to parse, empty when
+        /// serializing failed.
+        snippet: String,
     },
 
     #[error("Could not find a matching driver version for browser version '{browser_version}' on platform '{platform}'")]
@@ -63,11 +70,46 @@ pub enum WebDriverError {
         source: zip::result::ZipError,
     },
 
+    #[error("Checksum mismatch for '{path}': expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Not enough disk space at '{path}': {required} bytes required, {available} available")]
+    InsufficientDiskSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+
+    #[error("Timed out after {timeout:?} waiting for the install lock '{path}'")]
+    LockTimeout {
+        path: PathBuf,
+        timeout: std::time::Duration,
+    },
+
+    #[error("Unsupported archive format: {0}")]
+    UnsupportedArchiveFormat(String),
+
+    #[error("Refusing to extract archive entry '{path}': {reason}")]
+    UnsafeArchiveEntry {
+        path: PathBuf,
+        reason: String,
+    },
+
     #[error("Driver executable not found in the downloaded archive at '{path}'")]
     DriverExecutableNotFound {
         path: PathBuf,
     },
     
+    #[error("Driver binary is built for {found}, expected {expected}")]
+    ArchitectureMismatch {
+        expected: String,
+        found: String,
+    },
+
     #[error("Failed to start driver at '{path}': {source}")]
     DriverVerificationError {
         path: PathBuf,
@@ -75,12 +117,209 @@ pub enum WebDriverError {
         source: std::io::Error,
     },
 
+    #[error("The installation was cancelled")]
+    Cancelled,
+
+    #[error("'{url}' is not available offline")]
+    OfflineUnavailable {
+        url: String,
+    },
+
+    #[error("Invalid configuration file '{path}': {message}")]
+    InvalidConfig {
+        path: PathBuf,
+        message: String,
+    },
+
+    #[error("Rate limited by '{url}'; try again later or set a GitHub token")]
+    RateLimited {
+        url: String,
+        /// When the rate limit resets, if the server said so.
+        reset_at: Option<std::time::SystemTime>,
+    },
+
     #[error("An unknown error has occurred: {0}")]
     Custom(String),
 
+    #[error("Driver '{0}' cannot be downloaded; it ships with the operating system")]
+    DriverNotDownloadable(String),
+
+    #[error("Unsupported browser: {0}")]
+    UnsupportedBrowser(String),
+
     #[error("Unsupported platform: {0}")]
     UnsupportedPlatform(String),
 
     #[error("An error occurred while verifying the driver")]
     VerificationError(String),
 }
+
+/// How much of a document a [`WebDriverError::JsonParseError`] quotes.
+const SNIPPET_CHARS: usize = 200;
+
+impl WebDriverError {
+    /// A [`WebDriverError::JsonParseError`] for the document `body` read
+    /// from `url`, quoting its start.
+    pub(crate) fn json_parse(url: impl Into<String>, body: &[u8], source: serde_json::Error) -> Self {
+        let body = String::from_utf8_lossy(body);
+        let body = body.trim();
+        let mut snippet: String = body.chars().take(SNIPPET_CHARS).collect();
+        if snippet.len() < body.len() {
+            snippet.push('…');
+        }
+        WebDriverError::JsonParseError {
+            url: url.into(),
+            source,
+            snippet,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<reqwest::Error> for WebDriverError {
+    fn from(error: reqwest::Error) -> Self {
+        WebDriverError::NetworkError(error.into())
+    }
+}
+
+fn snippet_suffix(snippet: &str) -> String {
+    if snippet.is_empty() {
+        String::new()
+    } else {
+        format!(" (body: {})", snippet)
+    }
+}
+
+/// The broad class of a [`WebDriverError`], to decide whether retrying can
+/// help without matching on error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A transient network failure: a timeout, a connection error or a
+    /// server error (5xx).
+    Network,
+    /// The server rate limited the requests.
+    RateLimited,
+    /// Invalid input or configuration, such as an unknown browser or a bad
+    /// config file. Retrying won't help until the configuration changes.
+    Config,
+    /// The driver can't be resolved or installed from what the server
+    /// publishes: no matching version, a missing URL, an unexpected metadata
+    /// format or a broken archive.
+    Resolution,
+    /// A problem on this machine, such as a file system error or a full disk.
+    Local,
+    /// The installation was cancelled.
+    Cancelled,
+    Other,
+}
+
+impl ErrorKind {
+    /// Whether an operation failing with this kind of error may succeed
+    /// when retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorKind::Network | ErrorKind::RateLimited)
+    }
+}
+
+impl WebDriverError {
+    /// The class of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            WebDriverError::NetworkError(e) => network_error_kind(e),
+            WebDriverError::RateLimited { .. } => ErrorKind::RateLimited,
+
+            WebDriverError::BrowserNotFound
+            | WebDriverError::InvalidVersion(_)
+            | WebDriverError::OfflineUnavailable { .. }
+            | WebDriverError::InvalidConfig { .. }
+            | WebDriverError::DriverNotDownloadable(_)
+            | WebDriverError::UnsupportedBrowser(_)
+            | WebDriverError::UnsupportedPlatform(_) => ErrorKind::Config,
+
+            WebDriverError::JsonParseError { .. }
+            | WebDriverError::DriverVersionNotFound { .. }
+            | WebDriverError::DriverUrlNotFound { .. }
+            | WebDriverError::ZipError { .. }
+            | WebDriverError::ChecksumMismatch { .. }
+            | WebDriverError::UnsupportedArchiveFormat(_)
+            | WebDriverError::UnsafeArchiveEntry { .. }
+            | WebDriverError::DriverExecutableNotFound { .. }
+            | WebDriverError::ArchitectureMismatch { .. } => ErrorKind::Resolution,
+
+            WebDriverError::CommandExecutionError { .. }
+            | WebDriverError::CommandOutputParsingError { .. }
+            | WebDriverError::BrowserVersionParsingError { .. }
+            | WebDriverError::IoError { .. }
+            | WebDriverError::InsufficientDiskSpace { .. }
+            | WebDriverError::LockTimeout { .. }
+            | WebDriverError::DriverVerificationError { .. }
+            | WebDriverError::VerificationError(_) => ErrorKind::Local,
+
+            WebDriverError::Cancelled => ErrorKind::Cancelled,
+            WebDriverError::Custom(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the failed operation may succeed when retried, see
+    /// [`ErrorKind::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+}
+
+/// Classifies a request error by its status code, or by how it failed.
+fn network_error_kind(error: &HttpError) -> ErrorKind {
+    match error {
+        HttpError::Status { status, .. } => match *status {
+            StatusCode::TOO_MANY_REQUESTS => ErrorKind::RateLimited,
+            StatusCode::REQUEST_TIMEOUT => ErrorKind::Network,
+            status if status.is_server_error() => ErrorKind::Network,
+            // 404 and friends: the resource doesn't exist.
+            _ => ErrorKind::Resolution,
+        },
+        HttpError::Request(_) => ErrorKind::Config,
+        HttpError::Body(_) => ErrorKind::Resolution,
+        HttpError::Transport(_) => ErrorKind::Network,
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    #[allow(clippy::disallowed_methods)]
+    async fn test_error_kind() {
+        // Nothing listens on the port of a dropped listener.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let refused = WebDriverError::from(reqwest::get(&url).await.unwrap_err());
+        assert_eq!(refused.kind(), ErrorKind::Network);
+        assert!(refused.is_retryable());
+
+        let invalid_url = WebDriverError::from(reqwest::get("not a url").await.unwrap_err());
+        assert_eq!(invalid_url.kind(), ErrorKind::Config);
+
+        let rate_limited = WebDriverError::RateLimited { url, reset_at: None };
+        assert!(rate_limited.is_retryable());
+        assert!(!WebDriverError::UnsupportedBrowser("opera".into()).is_retryable());
+        assert_eq!(
+            WebDriverError::DriverVersionNotFound { browser_version: "1".into(), platform: "linux64".into() }.kind(),
+            ErrorKind::Resolution
+        );
+    }
+
+    #[test]
+    fn test_http_error_kind() {
+        let status = |status| WebDriverError::from(HttpError::Status { url: "https://example.com".into(), status });
+        assert_eq!(status(StatusCode::TOO_MANY_REQUESTS).kind(), ErrorKind::RateLimited);
+        assert_eq!(status(StatusCode::SERVICE_UNAVAILABLE).kind(), ErrorKind::Network);
+        assert_eq!(status(StatusCode::NOT_FOUND).kind(), ErrorKind::Resolution);
+        assert_eq!(WebDriverError::from(HttpError::Transport("connection reset".into())).kind(), ErrorKind::Network);
+        assert_eq!(WebDriverError::from(HttpError::Request("invalid header".into())).kind(), ErrorKind::Config);
+    }
+}
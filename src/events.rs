@@ -0,0 +1,38 @@
+//! Lifecycle events of a driver installation.
+//!
+//! Pass an [`InstallEventSender`] to a manager through
+//! [`DownloadOptions::events`](crate::downloader::DownloadOptions::events)
+//! to follow an installation from a UI or CI log without polling.
+
+use std::path::PathBuf;
+use futures_channel::mpsc;
+
+/// A step of the installation pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallEvent {
+    /// Resolving the driver version and download URL.
+    ResolvingVersion,
+    /// Downloading the archive. `pct` is `None` while the size is unknown.
+    Downloading { pct: Option<u8> },
+    Extracting,
+    Verifying,
+    Done { driver_path: PathBuf },
+}
+
+/// Sending half of an install event channel.
+pub type InstallEventSender = mpsc::UnboundedSender<InstallEvent>;
+
+/// Creates a channel for install events. The receiver is a
+/// [`Stream`](futures_util::Stream) and works on any executor.
+pub fn install_event_channel() -> (InstallEventSender, mpsc::UnboundedReceiver<InstallEvent>) {
+    mpsc::unbounded()
+}
+
+/// Percentage of `downloaded` in `total`, if the total is known.
+pub(crate) fn percentage(downloaded: u64, total: Option<u64>) -> Option<u8> {
+    match total {
+        Some(0) => Some(100),
+        Some(total) => Some((downloaded.min(total) * 100 / total) as u8),
+        None => None,
+    }
+}
@@ -0,0 +1,378 @@
+//! The HTTP client used for metadata and download requests.
+//!
+//! Requests go through an [`HttpClient`]. With the default `tokio` feature,
+//! that is a reqwest client built from the
+//! [`DownloadOptions`](crate::downloader::DownloadOptions); without it, pass
+//! a client of your own to
+//! [`DownloadOptions::client`](crate::downloader::DownloadOptions::client).
+//!
+//! Without explicit configuration the reqwest client honors the
+//! `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment
+//! variables. A [`ProxyConfig`] replaces them with an explicit proxy. Behind
+//! a TLS-intercepting proxy, add its certificate as a [`RootCertificate`].
+//!
+//! With [`NetworkPolicy::Offline`] no requests are made at all; installs then
+//! only use cached archives and drivers that are already installed.
+
+#[cfg(feature = "tokio")]
+use crate::error::WebDriverError;
+use ::http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH};
+use ::http::{Method, StatusCode};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use std::fmt::{self, Debug};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Sends the requests of an installation. Implement it to use an HTTP stack
+/// other than reqwest, e.g. one running on async-std or smol.
+#[async_trait]
+pub trait HttpClient: Debug + Send + Sync {
+    /// Sends `request`. Error statuses are returned as responses, not as
+    /// errors.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpError>;
+}
+
+#[async_trait]
+impl<T: HttpClient + ?Sized> HttpClient for std::sync::Arc<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        (**self).send(request).await
+    }
+}
+
+/// A request made by the crate. Only `GET` and `HEAD` are used.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    /// How long the whole request may take, if limited.
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequest {
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::GET, url.into())
+    }
+
+    pub fn head(url: impl Into<String>) -> Self {
+        Self::new(Method::HEAD, url.into())
+    }
+
+    fn new(method: Method, url: String) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            timeout: None,
+        }
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// The response to an [`HttpRequest`], with a streamed body.
+pub struct HttpResponse {
+    url: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: BoxStream<'static, Result<Bytes, HttpError>>,
+}
+
+impl HttpResponse {
+    /// A response from `url`, the URL the request ended up at.
+    pub fn new(
+        url: impl Into<String>,
+        status: StatusCode,
+        headers: HeaderMap,
+        body: impl Stream<Item = Result<Bytes, HttpError>> + Send + 'static,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            status,
+            headers,
+            body: body.boxed(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The size of the body according to `Content-Length`.
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+    }
+
+    /// Fails with [`HttpError::Status`] for client and server error statuses.
+    pub fn error_for_status(self) -> Result<Self, HttpError> {
+        if self.status.is_client_error() || self.status.is_server_error() {
+            return Err(HttpError::Status {
+                url: self.url,
+                status: self.status,
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn bytes_stream(self) -> BoxStream<'static, Result<Bytes, HttpError>> {
+        self.body
+    }
+
+    /// Reads the whole body as text, replacing invalid UTF-8.
+    pub async fn text(mut self) -> Result<String, HttpError> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.body.next().await {
+            body.extend_from_slice(&chunk?);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+impl Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The error of an underlying HTTP stack.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A failed HTTP request.
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    /// The server answered with a client or server error status.
+    #[error("HTTP status {status} for url ({url})")]
+    Status { url: String, status: StatusCode },
+    /// The request couldn't be built, e.g. because of an invalid URL, proxy
+    /// or header value.
+    #[error(transparent)]
+    Request(BoxError),
+    /// Connecting failed or timed out, or the connection broke.
+    #[error(transparent)]
+    Transport(BoxError),
+    /// The response body couldn't be decoded.
+    #[error(transparent)]
+    Body(BoxError),
+}
+
+impl HttpError {
+    /// The status of a [`HttpError::Status`] error.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            HttpError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) => HttpError::Status {
+                url: error.url().map(|url| url.to_string()).unwrap_or_default(),
+                status,
+            },
+            None if error.is_builder() => HttpError::Request(Box::new(error)),
+            // reqwest also reports a connection dropped mid-body as a decode error.
+            None if error.is_decode() && !caused_by_io(&error) => HttpError::Body(Box::new(error)),
+            None => HttpError::Transport(Box::new(error)),
+        }
+    }
+}
+
+/// Whether an I/O error is somewhere in the source chain of `error`.
+#[cfg(feature = "tokio")]
+fn caused_by_io(error: &(dyn std::error::Error + 'static)) -> bool {
+    std::iter::successors(Some(error), |error| error.source()).any(|error| error.is::<std::io::Error>())
+}
+
+#[cfg(feature = "tokio")]
+#[async_trait]
+impl HttpClient for reqwest::Client {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        let mut builder = self.request(request.method, &request.url).headers(request.headers);
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder.send().await?;
+        let (url, status, headers) = (response.url().to_string(), response.status(), response.headers().clone());
+        let body = response.bytes_stream().map(|chunk| chunk.map_err(HttpError::from));
+        Ok(HttpResponse::new(url, status, headers, body))
+    }
+}
+
+/// Whether network requests are allowed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NetworkPolicy {
+    #[default]
+    Online,
+    /// Every request fails with [`crate::error::WebDriverError::OfflineUnavailable`].
+    Offline,
+}
+
+/// An explicit HTTP, HTTPS or SOCKS5 proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.corp:3128` or `socks5://127.0.0.1:1080`.
+    pub url: String,
+    /// User name for proxies requiring basic authentication.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Comma separated hosts and domains that bypass the proxy, in the
+    /// format of the `NO_PROXY` environment variable.
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Sets the credentials sent to the proxy.
+    pub fn basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets the hosts that are connected to directly.
+    pub fn no_proxy(mut self, hosts: impl Into<String>) -> Self {
+        self.no_proxy = Some(hosts.into());
+        self
+    }
+
+    #[cfg(feature = "tokio")]
+    fn to_reqwest(&self) -> Result<reqwest::Proxy, WebDriverError> {
+        let mut proxy = reqwest::Proxy::all(&self.url)?;
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or_default());
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(no_proxy));
+        }
+        Ok(proxy)
+    }
+}
+
+/// An additional trusted root certificate, e.g. the one a corporate proxy
+/// signs intercepted TLS connections with. The system roots stay trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootCertificate {
+    /// A PEM file, which may hold several certificates.
+    PemFile(PathBuf),
+    /// PEM encoded certificates.
+    Pem(Vec<u8>),
+}
+
+impl RootCertificate {
+    #[cfg(feature = "tokio")]
+    fn load(&self) -> Result<Vec<reqwest::Certificate>, WebDriverError> {
+        let pem = match self {
+            RootCertificate::PemFile(path) => std::fs::read(path).map_err(|e| WebDriverError::IoError {
+                path: path.clone(),
+                source: e,
+            })?,
+            RootCertificate::Pem(pem) => pem.clone(),
+        };
+        Ok(reqwest::Certificate::from_pem_bundle(&pem)?)
+    }
+}
+
+/// Builds the reqwest client for all requests of an installation. `headers` are
+/// sent with every request. `timeout` limits how long connecting or a single
+/// read may take, not a whole download.
+#[cfg(feature = "tokio")]
+#[allow(clippy::disallowed_methods)]
+pub(crate) fn build_client(
+    proxy: Option<&ProxyConfig>,
+    root_certificates: &[RootCertificate],
+    headers: &HeaderMap,
+    timeout: Option<Duration>,
+) -> Result<reqwest::Client, WebDriverError> {
+    let mut builder = reqwest::Client::builder().default_headers(headers.clone());
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy.to_reqwest()?);
+    }
+    for root in root_certificates {
+        for certificate in root.load()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(timeout) = timeout {
+        builder = builder.connect_timeout(timeout).read_timeout(timeout);
+    }
+    Ok(builder.build()?)
+}
+
+// --- Tests ---
+
+// Only `build_client` is under test, which needs reqwest.
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        let proxy = ProxyConfig::new("socks5://127.0.0.1:1080")
+            .basic_auth("user", "secret")
+            .no_proxy("localhost,.corp.example");
+        assert!(build_client(Some(&proxy), &[], &HeaderMap::new(), Some(Duration::from_secs(30))).is_ok());
+
+        assert!(build_client(Some(&ProxyConfig::new("not a url")), &[], &HeaderMap::new(), None).is_err());
+    }
+
+    /// A self-signed CA certificate.
+    const TEST_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBnzCCAUWgAwIBAgIUURp573jQP99z7Rcv4Qr/VgHelbgwCgYIKoZIzj0EAwIw
+JDEiMCAGA1UEAwwZd2ViZHJpdmVyX21hbmFnZXIgdGVzdCBDQTAgFw0yNjEwMTYw
+MjU4MzBaGA8yMTI2MDkyMjAyNTgzMFowJDEiMCAGA1UEAwwZd2ViZHJpdmVyX21h
+bmFnZXIgdGVzdCBDQTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABEl8cggD31eC
+5fkswALeeaLJ3CVU1u3v9569bJbCRfTomgKvGYyEiUzhEwrwAIYJf5KQP1bAVccb
+bBpSwzzqlgmjUzBRMB0GA1UdDgQWBBR4ftSi8R855SJnmY80FLwacnZ0fDAfBgNV
+HSMEGDAWgBR4ftSi8R855SJnmY80FLwacnZ0fDAPBgNVHRMBAf8EBTADAQH/MAoG
+CCqGSM49BAMCA0gAMEUCIQCIiJFZtNqPD0u3rROJrptScjxQ0RYKv9pT5yOvwako
+bAIgEfWTVNG0K0gD7VYTf5FG5VD4b8RMjK6P2KXxekAxmrU=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_build_client_with_root_certificates() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("ca.pem");
+        std::fs::write(&bundle, TEST_CA.repeat(2)).unwrap();
+
+        let roots = [RootCertificate::Pem(TEST_CA.into()), RootCertificate::PemFile(bundle)];
+        assert!(build_client(None, &roots, &HeaderMap::new(), None).is_ok());
+
+        let missing = RootCertificate::PemFile(dir.path().join("missing.pem"));
+        assert!(matches!(build_client(None, &[missing], &HeaderMap::new(), None), Err(WebDriverError::IoError { .. })));
+    }
+}
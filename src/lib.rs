@@ -1,18 +1,56 @@
 
+// Internal macros, declared first so every module can use them.
+#[macro_use]
+mod trace;
+
 // Top-level public modules
 pub mod error;
+pub mod arch;
 pub mod browser;
+pub mod builder;
+pub mod bundle;
+pub mod cache;
+pub mod config;
 pub mod downloader;
+pub mod display;
+pub mod doctor;
 pub mod drivers;
+pub mod endpoints;
+pub mod events;
+pub mod http;
+pub mod lock;
+pub mod lockfile;
+pub mod logs;
+pub mod mirror;
+pub mod observer;
+#[cfg(feature = "progress-bar")]
+pub mod progress_bar;
+pub mod registry;
+pub mod report;
+pub mod runtime;
+pub mod selenium_manager;
+pub mod setup;
+pub mod source;
+pub mod version;
+
+// The test server only backs tests that download through reqwest.
+#[cfg(all(test, feature = "tokio"))]
+mod test_server;
 
-pub use error::WebDriverError;
+pub use browser::Browser;
+pub use config::Config;
+pub use drivers::Driver;
+pub use error::{ErrorKind, WebDriverError};
+pub use setup::{plan, plan_with, setup, setup_with, InstallPlan, SetupOptions};
+pub use version::{Version, VersionReq, VersionSpec};
 
 // Main public trait
 use async_trait::async_trait;
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
 #[async_trait]
-pub trait WebDriverManager {
+pub trait WebDriverManager: Send + Sync {
 
     /// Gets the name of the driver (e.g., "chromedriver").
     fn get_driver_name(&self) -> &str;
@@ -22,7 +60,7 @@ pub trait WebDriverManager {
     async fn get_browser_version(&self, browser_path: Option<&Path>) -> Result<String, WebDriverError>;
 
     /// Determines the correct driver version for a given browser version.
-    async fn get_driver_version(&self, browser_version: &str) -> Result<String, WebDriverError>;
+    async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError>;
 
     /// Gets the download URL for the specified driver version.
     async fn get_download_url(&self, driver_version: &str) -> Result<String, WebDriverError>;
@@ -35,6 +73,350 @@ pub trait WebDriverManager {
         install_path: &Path,
     ) -> Result<PathBuf, WebDriverError>;
 
+    /// Resolves the driver version and its download URL in one go, so both
+    /// belong to the same release even for `latest` or a range.
+    async fn resolve_download(&self, browser_version: &str) -> Result<(Version, String), WebDriverError> {
+        let driver_version = self.get_driver_version(browser_version).await?;
+        let url = self.get_download_url(browser_version).await?;
+        Ok((driver_version, url))
+    }
+
+    /// Like [`WebDriverManager::download_and_install`], downloading from a
+    /// URL returned by [`WebDriverManager::resolve_download`] instead of
+    /// resolving it again. The default ignores the URL.
+    async fn install_download(
+        &self,
+        driver_version: &str,
+        _url: &str,
+        install_path: &Path,
+    ) -> Result<PathBuf, WebDriverError> {
+        self.download_and_install(driver_version, install_path).await
+    }
+
+    /// The platform identifier used in the [`cache::Cache`] layout, such as
+    /// `linux64`. Managers override this with the naming of their downloads.
+    fn driver_platform(&self) -> String {
+        format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+    }
+
+    /// Installs the driver matching `browser_version` into its directory in
+    /// `cache` and returns the path to the executable.
+    async fn install_in_cache(&self, cache: &cache::Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        install_into_cache(self, cache, browser_version).await
+    }
+
+    /// Like [`WebDriverManager::install_in_cache`], but returns the driver
+    /// already installed in `cache` when it still runs and matches the
+    /// checksum in the cache manifest, without downloading.
+    async fn ensure_installed(&self, cache: &cache::Cache, browser_version: &str) -> Result<PathBuf, WebDriverError> {
+        let driver_version = match self.get_driver_version(browser_version).await {
+            Ok(driver_version) => driver_version,
+            // Offline the driver version can't be resolved; any compatible
+            // driver in the cache or on the system will do.
+            Err(WebDriverError::OfflineUnavailable { url }) => {
+                debug!(url = %url, "offline, looking for a compatible installed driver");
+                if let Some(driver_path) = find_cached_driver(self, cache, browser_version).await {
+                    return Ok(driver_path);
+                }
+                return self
+                    .find_existing_driver(browser_version)
+                    .await
+                    .ok_or(WebDriverError::OfflineUnavailable { url });
+            }
+            Err(e) => return Err(e),
+        };
+        let driver_version = driver_version.to_string();
+        let platform = self.driver_platform();
+        let install_dir = cache.install_dir(self.get_driver_name(), &driver_version, &platform);
+        if let Ok(driver_path) = downloader::find_driver_executable(&install_dir, self.get_driver_name())
+            && cache.is_intact(self.get_driver_name(), &driver_version, &platform, &driver_path)
+            && self.verify_driver(&driver_path).await.is_ok()
+        {
+            cache.touch(self.get_driver_name(), &driver_version, &platform);
+            debug!(driver_path = %driver_path.display(), "driver found in the cache");
+            return Ok(driver_path);
+        }
+        debug!(install_dir = %install_dir.display(), "driver not in the cache");
+        install_into_cache(self, cache, browser_version).await
+    }
+
+    /// The driver versions that can be installed for this platform, oldest
+    /// first. Managers without a remote version list return an empty list.
+    async fn list_available_versions(&self) -> Result<Vec<Version>, WebDriverError> {
+        Ok(Vec::new())
+    }
+
+    /// Gets the version of an existing driver binary, such as one installed
+    /// by a system package or an earlier install.
+    async fn installed_driver_version(&self, driver_path: &Path) -> Result<Version, WebDriverError> {
+        drivers::driver_binary_version(driver_path).await?.parse()
+    }
+
+    /// Whether a driver of `driver_version` works with the browser. By
+    /// default only the exact driver version resolved for the browser does.
+    async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+        self.get_driver_version(browser_version)
+            .await
+            .is_ok_and(|resolved| resolved == *driver_version)
+    }
+
+    /// Looks for a driver that is already installed outside the crate's
+    /// control: the path in the environment variable named after the driver
+    /// (`CHROMEDRIVER`, `GECKODRIVER`, ...), then `PATH`. The driver is
+    /// returned when its `--version` is compatible with `browser_version`, so
+    /// CI images with preinstalled drivers don't download them again.
+    /// [`SetupOptions::reuse_system_driver`] turns this on for
+    /// [`setup_with`] and the manager builders; offline,
+    /// [`WebDriverManager::ensure_installed`] falls back to it.
+    async fn find_existing_driver(&self, browser_version: &str) -> Option<PathBuf> {
+        let name = self.get_driver_name();
+        let candidates = system_driver_candidates(name, std::env::var_os(name.to_uppercase()), std::env::var_os("PATH"));
+        find_compatible_driver(self, candidates, browser_version).await
+    }
+
     /// Verifies the driver is working by attempting to start it.
     async fn verify_driver(&self, driver_path: &Path) -> Result<(), WebDriverError>;
-}
\ No newline at end of file
+}
+
+/// Returns the manager for a browser name such as `"chrome"`, `"edge"`,
+/// `"firefox"`, `"safari"` or `"ie"`, so the browser can come from a config file.
+/// Names registered in the global [`registry::DriverRegistry`] take precedence.
+pub fn manager_for(browser: &str) -> Result<Box<dyn WebDriverManager>, WebDriverError> {
+    let registry = registry::DriverRegistry::global();
+    if registry.contains(browser) {
+        return registry.get(browser);
+    }
+
+    let browser: Browser = browser.parse()?;
+    Ok(Box::new(Driver::from(browser)))
+}
+
+/// Where a preinstalled `driver` may be: `env_path`, the value of the
+/// environment variable named after the driver, then its first match on
+/// `path`, a `PATH`-style list of directories.
+fn system_driver_candidates(driver: &str, env_path: Option<OsString>, path: Option<OsString>) -> Vec<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    env_path
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(which::which_in(driver, path, cwd).ok())
+        .collect()
+}
+
+/// The first of `candidates` whose `--version` is compatible with
+/// `browser_version`.
+async fn find_compatible_driver<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    candidates: Vec<PathBuf>,
+    browser_version: &str,
+) -> Option<PathBuf> {
+    for candidate in candidates {
+        if let Ok(driver_version) = manager.installed_driver_version(&candidate).await
+            && manager.is_compatible_driver(&driver_version, browser_version).await
+        {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// The newest driver in `cache` compatible with `browser_version` that is
+/// intact and still runs.
+async fn find_cached_driver<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    cache: &cache::Cache,
+    browser_version: &str,
+) -> Option<PathBuf> {
+    let platform = manager.driver_platform();
+    let mut builds: Vec<_> = cache
+        .list_installed()
+        .ok()?
+        .into_iter()
+        .filter(|build| build.name == manager.get_driver_name() && build.platform == platform)
+        .filter_map(|build| Some((build.version.parse::<Version>().ok()?, build)))
+        .collect();
+    builds.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    for (driver_version, build) in builds {
+        if manager.is_compatible_driver(&driver_version, browser_version).await
+            && cache.is_intact(&build.name, &build.version, &build.platform, &build.path)
+            && manager.verify_driver(&build.path).await.is_ok()
+        {
+            return Some(build.path);
+        }
+    }
+    None
+}
+
+/// Installs the driver for `browser_version` into `cache`, records it in the
+/// manifest and runs the cache housekeeping. The version and URL are
+/// resolved once, so the manifest records the URL actually downloaded.
+async fn install_into_cache<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    cache: &cache::Cache,
+    browser_version: &str,
+) -> Result<PathBuf, WebDriverError> {
+    let (driver_version, url) = manager.resolve_download(browser_version).await?;
+    let driver_version = driver_version.to_string();
+    let name = manager.get_driver_name();
+    let platform = manager.driver_platform();
+    let install_dir = cache.install_dir(name, &driver_version, &platform);
+    let driver_path = manager.install_download(browser_version, &url, &install_dir).await?;
+
+    let installed_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    cache.record_install(cache::ManifestEntry {
+        driver: name.to_string(),
+        version: driver_version.clone(),
+        platform: platform.clone(),
+        source_url: Some(url),
+        sha256: downloader::sha256_file_async(&driver_path).await?,
+        installed_at,
+    })?;
+
+    // Pruning and eviction are housekeeping; the install itself succeeded.
+    let _ = cache.after_install(name, &driver_version, &platform);
+    info!(driver = name, version = %driver_version, driver_path = %driver_path.display(), "installed driver");
+    Ok(driver_path)
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A manager "installing" an empty file, counting the installs.
+    #[derive(Default)]
+    pub(crate) struct FakeManager {
+        pub(crate) installs: AtomicUsize,
+        pub(crate) offline: bool,
+        /// The `PATH` preinstalled drivers are looked up in, instead of the
+        /// one of the process.
+        pub(crate) path: Option<OsString>,
+    }
+
+    #[async_trait]
+    impl WebDriverManager for FakeManager {
+        fn get_driver_name(&self) -> &str {
+            "fakedriver"
+        }
+
+        async fn get_browser_version(&self, _browser_path: Option<&Path>) -> Result<String, WebDriverError> {
+            Ok("1.0".to_string())
+        }
+
+        async fn get_driver_version(&self, browser_version: &str) -> Result<Version, WebDriverError> {
+            if self.offline {
+                return Err(WebDriverError::OfflineUnavailable { url: "https://example.com/versions.json".to_string() });
+            }
+            if let Some(exact) = browser_version.strip_prefix('=') {
+                return exact.parse();
+            }
+            format!("{}.1", browser_version).parse()
+        }
+
+        async fn is_compatible_driver(&self, driver_version: &Version, browser_version: &str) -> bool {
+            browser_version.parse::<Version>().is_ok_and(|browser| browser.matches(driver_version))
+        }
+
+        async fn get_download_url(&self, _driver_version: &str) -> Result<String, WebDriverError> {
+            Ok("https://example.com/fakedriver.zip".to_string())
+        }
+
+        async fn download_and_install(&self, _version: &str, install_path: &Path) -> Result<PathBuf, WebDriverError> {
+            self.installs.fetch_add(1, Ordering::SeqCst);
+            let name = if cfg!(target_os = "windows") { "fakedriver.exe" } else { "fakedriver" };
+            std::fs::create_dir_all(install_path).unwrap();
+            std::fs::write(install_path.join(name), b"").unwrap();
+            Ok(install_path.join(name))
+        }
+
+        async fn find_existing_driver(&self, browser_version: &str) -> Option<PathBuf> {
+            let candidates = system_driver_candidates(self.get_driver_name(), None, self.path.clone());
+            find_compatible_driver(self, candidates, browser_version).await
+        }
+
+        async fn verify_driver(&self, _driver_path: &Path) -> Result<(), WebDriverError> {
+            Ok(())
+        }
+    }
+
+    /// Writes a `fakedriver` script into `dir` reporting `version`.
+    #[cfg(unix)]
+    pub(crate) fn write_fake_driver(dir: &Path, version: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let driver_path = dir.join("fakedriver");
+        std::fs::write(&driver_path, format!("#!/bin/sh\necho 'FakeDriver {} (a1b2c3)'\n", version)).unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        driver_path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_installed_driver_version_is_typed() {
+        let dir = tempfile::tempdir().unwrap();
+        let driver_path = write_fake_driver(dir.path(), "126.0.6478.182");
+
+        let version = FakeManager::default().installed_driver_version(&driver_path).await.unwrap();
+        assert_eq!(version, "126.0.6478.182".parse::<Version>().unwrap());
+        assert_eq!(version.major, 126);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_installed_reuses_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache::Cache::new(dir.path());
+        let manager = FakeManager::default();
+
+        let first = manager.ensure_installed(&cache, "1.0").await.unwrap();
+        let second = manager.ensure_installed(&cache, "1.0").await.unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with(dir.path().join("fakedriver").join("1.0.1")));
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 1);
+
+        let entry = cache.manifest().unwrap().installs.pop().unwrap();
+        assert_eq!(entry.driver, "fakedriver");
+        assert_eq!(entry.version, "1.0.1");
+        assert_eq!(entry.source_url.as_deref(), Some("https://example.com/fakedriver.zip"));
+
+        manager.install_in_cache(&cache, "1.0").await.unwrap();
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 2);
+
+        // A driver that no longer matches the manifest is installed again.
+        std::fs::write(&first, b"tampered").unwrap();
+        manager.ensure_installed(&cache, "1.0").await.unwrap();
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_installed_offline_uses_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache::Cache::new(dir.path());
+        let installed = FakeManager::default().ensure_installed(&cache, "1.0").await.unwrap();
+
+        let offline = FakeManager {
+            offline: true,
+            ..Default::default()
+        };
+        assert_eq!(offline.ensure_installed(&cache, "1.0").await.unwrap(), installed);
+        assert!(matches!(
+            offline.ensure_installed(&cache, "2.0").await,
+            Err(WebDriverError::OfflineUnavailable { .. })
+        ));
+        assert_eq!(offline.installs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_manager_for_browser_names() {
+        assert_eq!(manager_for("Chrome").unwrap().get_driver_name(), "chromedriver");
+        assert_eq!(manager_for(" firefox ").unwrap().get_driver_name(), "geckodriver");
+        assert_eq!(manager_for("ie").unwrap().get_driver_name(), "IEDriverServer");
+        assert_eq!(manager_for("edge").unwrap().get_driver_name(), "msedgedriver");
+        assert_eq!(manager_for("MSEdge").unwrap().get_driver_name(), "msedgedriver");
+        assert!(matches!(manager_for("netscape"), Err(WebDriverError::UnsupportedBrowser(_))));
+    }
+}
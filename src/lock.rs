@@ -0,0 +1,153 @@
+//! Cross-process locking of install directories.
+//!
+//! Parallel test binaries often install the same driver into the same
+//! directory. An advisory lock on `<install dir>.lock` serializes them; the
+//! lock is released when the [`InstallLock`] is dropped, or when the process
+//! dies.
+
+use crate::error::WebDriverError;
+use fs4::fs_std::FileExt;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long an install waits for a concurrent one by default.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Interval between attempts to take a held lock.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive lock on an install directory.
+#[derive(Debug)]
+pub struct InstallLock {
+    file: std::fs::File,
+    path: PathBuf,
+}
+
+impl InstallLock {
+    /// Locks `dir`, waiting up to `timeout` for another process to release it.
+    pub async fn acquire(dir: &Path, timeout: Duration) -> Result<Self, WebDriverError> {
+        let mut pending = PendingLock::open(dir)?;
+        while !pending.try_lock(timeout)? {
+            crate::runtime::sleep(POLL_INTERVAL).await;
+        }
+        Ok(pending.locked())
+    }
+
+    /// Like [`InstallLock::acquire`], but blocks the thread while waiting,
+    /// for synchronous callers such as [`crate::cache::Cache::remove`].
+    pub fn acquire_blocking(dir: &Path, timeout: Duration) -> Result<Self, WebDriverError> {
+        let mut pending = PendingLock::open(dir)?;
+        while !pending.try_lock(timeout)? {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        Ok(pending.locked())
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for InstallLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// A lock file that is open but not yet locked.
+struct PendingLock {
+    file: std::fs::File,
+    path: PathBuf,
+    started: Instant,
+    waited: bool,
+}
+
+impl PendingLock {
+    fn open(dir: &Path) -> Result<Self, WebDriverError> {
+        let path = lock_path(dir);
+        let io_err = |e| WebDriverError::IoError {
+            path: path.clone(),
+            source: e,
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        // The lock file is never removed; deleting it would let two processes
+        // lock different files under the same name.
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(io_err)?;
+        Ok(Self {
+            file,
+            path,
+            started: Instant::now(),
+            waited: false,
+        })
+    }
+
+    /// Tries to take the lock once. Returns `false` when the caller should
+    /// wait and try again, and an error once `timeout` has passed.
+    fn try_lock(&mut self, timeout: Duration) -> Result<bool, WebDriverError> {
+        let locked = self.file.try_lock_exclusive().map_err(|e| WebDriverError::IoError {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        if locked {
+            if self.waited {
+                debug!(path = %self.path.display(), waited_ms = self.started.elapsed().as_millis() as u64, "acquired the install lock");
+            }
+            return Ok(true);
+        }
+        if self.started.elapsed() >= timeout {
+            return Err(WebDriverError::LockTimeout { path: self.path.clone(), timeout });
+        }
+        if !self.waited {
+            debug!(path = %self.path.display(), "waiting for a concurrent install");
+            self.waited = true;
+        }
+        Ok(false)
+    }
+
+    fn locked(self) -> InstallLock {
+        InstallLock { file: self.file, path: self.path }
+    }
+}
+
+/// The lock file of a directory: a sibling named `<dir>.lock`.
+fn lock_path(dir: &Path) -> PathBuf {
+    let mut name = OsString::from(dir.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_install_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let install_dir = dir.path().join("chromedriver").join("138.0.7204.157");
+
+        let lock = InstallLock::acquire(&install_dir, Duration::ZERO).await.unwrap();
+        assert_eq!(lock.path(), dir.path().join("chromedriver").join("138.0.7204.157.lock"));
+
+        let second = InstallLock::acquire(&install_dir, Duration::from_millis(200)).await;
+        assert!(matches!(second, Err(WebDriverError::LockTimeout { .. })));
+
+        let blocking = InstallLock::acquire_blocking(&install_dir, Duration::ZERO);
+        assert!(matches!(blocking, Err(WebDriverError::LockTimeout { .. })));
+
+        drop(lock);
+        assert!(InstallLock::acquire(&install_dir, Duration::ZERO).await.is_ok());
+        assert!(InstallLock::acquire_blocking(&install_dir, Duration::ZERO).is_ok());
+    }
+}
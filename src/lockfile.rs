@@ -0,0 +1,205 @@
+//! Lockfile for reproducible driver versions, `webdriver-lock.json`.
+//!
+//! The first install through [`install_locked`] records the driver version,
+//! download URL and SHA-256 of the driver executable per driver and platform.
+//! Later installs, on any machine, install exactly that version and fail if
+//! the executable doesn't match the recorded checksum, until the entry is
+//! updated. Commit the file next to `Cargo.toml` to pin the drivers of CI.
+//!
+//! ```json
+//! {
+//!   "drivers": [
+//!     {
+//!       "driver": "chromedriver",
+//!       "platform": "linux64",
+//!       "version": "126.0.6478.182",
+//!       "url": "https://storage.googleapis.com/chrome-for-testing-public/126.0.6478.182/linux64/chromedriver-linux64.zip",
+//!       "sha256": "..."
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::cache::{project_root, Cache};
+use crate::downloader::sha256_file_async;
+use crate::error::WebDriverError;
+use crate::WebDriverManager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the lockfile.
+pub const LOCKFILE: &str = "webdriver-lock.json";
+
+/// A driver pinned by the lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedDriver {
+    pub driver: String,
+    pub platform: String,
+    pub version: String,
+    /// The URL the driver was first downloaded from.
+    pub url: String,
+    /// Hex encoded SHA-256 digest of the driver executable.
+    pub sha256: String,
+}
+
+/// The contents of a lockfile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub drivers: Vec<LockedDriver>,
+}
+
+impl Lockfile {
+    /// Reads the lockfile at `path`. A missing file is an empty lockfile.
+    pub fn load(path: &Path) -> Result<Self, WebDriverError> {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(WebDriverError::IoError {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+            }
+        };
+        serde_json::from_slice(&contents).map_err(|e| WebDriverError::json_parse(path.display().to_string(), &contents, e))
+    }
+
+    /// Writes the lockfile to `path`, replacing it atomically.
+    pub fn save(&self, path: &Path) -> Result<(), WebDriverError> {
+        let io_err = |e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let mut json = serde_json::to_vec_pretty(self).map_err(|e| WebDriverError::JsonParseError {
+            url: path.display().to_string(),
+            source: e,
+            snippet: String::new(),
+        })?;
+        json.push(b'\n');
+
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+        let mut file = tempfile::NamedTempFile::new_in(parent).map_err(io_err)?;
+        std::io::Write::write_all(&mut file, &json).map_err(io_err)?;
+        file.persist(path).map_err(|e| io_err(e.error))?;
+        Ok(())
+    }
+
+    /// The lockfile of the current project: `webdriver-lock.json` next to
+    /// its `Cargo.toml`, or in the current directory outside a project.
+    pub fn project_path() -> PathBuf {
+        project_root()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default()
+            .join(LOCKFILE)
+    }
+
+    /// The pinned build of `driver` for `platform`.
+    pub fn get(&self, driver: &str, platform: &str) -> Option<&LockedDriver> {
+        self.drivers.iter().find(|d| d.driver == driver && d.platform == platform)
+    }
+
+    /// Pins `entry`, replacing the earlier pin of the same driver and platform.
+    pub fn insert(&mut self, entry: LockedDriver) {
+        self.drivers.retain(|d| (&d.driver, &d.platform) != (&entry.driver, &entry.platform));
+        self.drivers.push(entry);
+        self.drivers.sort_by(|a, b| (&a.driver, &a.platform).cmp(&(&b.driver, &b.platform)));
+    }
+}
+
+/// Installs the driver pinned in the lockfile at `lockfile_path` into
+/// `cache`. Without a pin, or with `update`, the driver for
+/// `browser_version` is installed and pinned. Drivers that aren't downloaded,
+/// like safaridriver, are installed without a pin.
+pub async fn install_locked<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    cache: &Cache,
+    lockfile_path: &Path,
+    browser_version: &str,
+    update: bool,
+) -> Result<PathBuf, WebDriverError> {
+    let mut lockfile = Lockfile::load(lockfile_path)?;
+    let name = manager.get_driver_name().to_string();
+    let platform = manager.driver_platform();
+
+    if !update && let Some(locked) = lockfile.get(&name, &platform) {
+        let driver_path = manager.ensure_installed(cache, &format!("={}", locked.version)).await?;
+        let actual = sha256_file_async(&driver_path).await?;
+        if actual != locked.sha256 {
+            let _ = cache.remove(&name, &locked.version);
+            return Err(WebDriverError::ChecksumMismatch {
+                path: driver_path,
+                expected: locked.sha256.clone(),
+                actual,
+            });
+        }
+        return Ok(driver_path);
+    }
+
+    let driver_version = manager.get_driver_version(browser_version).await?.to_string();
+    let driver_path = manager.ensure_installed(cache, browser_version).await?;
+    let manifest = cache.manifest()?;
+    let Some(installed) = manifest.entry(&name, &driver_version, &platform) else {
+        return Ok(driver_path);
+    };
+    let url = match &installed.source_url {
+        Some(url) => url.clone(),
+        None => manager.get_download_url(browser_version).await?,
+    };
+    lockfile.insert(LockedDriver {
+        driver: name,
+        platform,
+        version: driver_version,
+        url,
+        sha256: installed.sha256.clone(),
+    });
+    lockfile.save(lockfile_path)?;
+    Ok(driver_path)
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::FakeManager;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn test_install_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+        let lockfile_path = dir.path().join(LOCKFILE);
+        let manager = FakeManager::default();
+
+        // The first install pins the resolved version.
+        install_locked(&manager, &cache, &lockfile_path, "1.0", false).await.unwrap();
+        let locked = Lockfile::load(&lockfile_path).unwrap().drivers.pop().unwrap();
+        assert_eq!(locked.driver, "fakedriver");
+        assert_eq!(locked.version, "1.0.1");
+        assert_eq!(locked.url, "https://example.com/fakedriver.zip");
+
+        // Later installs keep to the pin, whatever the browser version.
+        let other_cache = Cache::new(dir.path().join("other"));
+        let path = install_locked(&manager, &other_cache, &lockfile_path, "2.0", false).await.unwrap();
+        assert!(path.starts_with(other_cache.install_dir("fakedriver", "1.0.1", &locked.platform)));
+
+        // An update pins the new version.
+        install_locked(&manager, &cache, &lockfile_path, "2.0", true).await.unwrap();
+        assert_eq!(Lockfile::load(&lockfile_path).unwrap().drivers[0].version, "2.0.1");
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 3);
+
+        // A driver not matching the pinned checksum is rejected.
+        let mut lockfile = Lockfile::load(&lockfile_path).unwrap();
+        lockfile.drivers[0].sha256 = "0".repeat(64);
+        lockfile.save(&lockfile_path).unwrap();
+        assert!(matches!(
+            install_locked(&manager, &cache, &lockfile_path, "2.0", false).await,
+            Err(WebDriverError::ChecksumMismatch { .. })
+        ));
+    }
+}
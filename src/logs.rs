@@ -0,0 +1,281 @@
+//! Parsing of chromedriver and geckodriver log output.
+//!
+//! Chromedriver writes lines like
+//! `[1752412345.678][INFO]: [5f6c1a...] COMMAND Navigate {...}`, geckodriver
+//! writes tab separated lines like
+//! `1752412345678\tgeckodriver\tINFO\tListening on 127.0.0.1:4444`.
+//! Both are parsed into a [`DriverLogLine`] so harnesses can filter on level
+//! or session instead of matching raw text. [`DriverLog`] reads them from a
+//! started driver's output or its `--log-path` file.
+
+use std::io::BufRead;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Severity of a driver log line, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl FromStr for LogLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "TRACE" | "ALL" => Ok(LogLevel::Trace),
+            "DEBUG" | "CONFIG" => Ok(LogLevel::Debug),
+            "INFO" => Ok(LogLevel::Info),
+            "WARN" | "WARNING" => Ok(LogLevel::Warning),
+            "ERROR" | "SEVERE" => Ok(LogLevel::Error),
+            "FATAL" => Ok(LogLevel::Fatal),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single structured driver log line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverLogLine {
+    pub level: LogLevel,
+    pub timestamp: Option<SystemTime>,
+    /// The emitting module, when the driver reports one (geckodriver only).
+    pub module: Option<String>,
+    pub session_id: Option<String>,
+    pub message: String,
+}
+
+/// Parses a single chromedriver or geckodriver log line.
+///
+/// Returns `None` for lines that don't follow either format, such as
+/// continuation lines of multi-line messages.
+pub fn parse_log_line(line: &str) -> Option<DriverLogLine> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.starts_with('[') {
+        parse_chromedriver_line(line)
+    } else {
+        parse_geckodriver_line(line)
+    }
+}
+
+/// Parses every recognised line of a log, skipping the others.
+pub fn parse_log(text: &str) -> impl Iterator<Item = DriverLogLine> + '_ {
+    text.lines().filter_map(parse_log_line)
+}
+
+fn parse_chromedriver_line(line: &str) -> Option<DriverLogLine> {
+    // [timestamp][LEVEL]: message
+    let rest = line.strip_prefix('[')?;
+    let (timestamp, rest) = rest.split_once(']')?;
+    let rest = rest.strip_prefix('[')?;
+    let (level, rest) = rest.split_once(']')?;
+    let message = rest.strip_prefix(':').unwrap_or(rest).trim_start();
+
+    // Session scoped lines are prefixed with the session id in brackets.
+    let (session_id, message) = match message.strip_prefix('[').and_then(|m| m.split_once(']')) {
+        Some((id, rest)) if is_session_id(id) => (Some(id.to_string()), rest.trim_start()),
+        _ => (None, message),
+    };
+
+    Some(DriverLogLine {
+        level: level.parse().ok()?,
+        timestamp: timestamp
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs_f64(secs)),
+        module: None,
+        session_id,
+        message: message.to_string(),
+    })
+}
+
+fn parse_geckodriver_line(line: &str) -> Option<DriverLogLine> {
+    // timestamp_ms \t module \t LEVEL \t message
+    let mut parts = line.splitn(4, '\t');
+    let timestamp = parts.next()?.trim().parse::<u64>().ok()?;
+    let module = parts.next()?.trim();
+    let level = parts.next()?.parse().ok()?;
+    let message = parts.next().unwrap_or("").trim();
+
+    let session_id = message
+        .split("/session/")
+        .nth(1)
+        .and_then(|rest| rest.split(['/', ' ', '"']).next())
+        .filter(|id| is_session_id(id))
+        .map(str::to_string);
+
+    Some(DriverLogLine {
+        level,
+        timestamp: Some(UNIX_EPOCH + Duration::from_millis(timestamp)),
+        module: Some(module.to_string()),
+        session_id,
+        message: message.to_string(),
+    })
+}
+
+/// Structured lines read from a driver's log stream, such as the stderr of a
+/// driver started by the harness or the file passed to its `--log-path`.
+///
+/// Lines that don't start a new entry, like the rest of a multi-line stack
+/// trace, are appended to the message of the entry before them.
+#[derive(Debug)]
+pub struct DriverLog<R> {
+    reader: R,
+    min_level: LogLevel,
+    pending: Option<DriverLogLine>,
+    buf: String,
+}
+
+impl<R> DriverLog<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            min_level: LogLevel::Trace,
+            pending: None,
+            buf: String::new(),
+        }
+    }
+
+    /// Skips entries less severe than `level`.
+    pub fn min_level(mut self, level: LogLevel) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    /// Handles the line in `buf`, returning the entry it completes, if any.
+    /// At the end of the stream (`eof`) the pending entry is complete.
+    fn feed(&mut self, eof: bool) -> Option<DriverLogLine> {
+        let completed = if eof {
+            self.pending.take()
+        } else {
+            match parse_log_line(&self.buf) {
+                Some(line) => self.pending.replace(line),
+                None => {
+                    if let Some(pending) = &mut self.pending {
+                        pending.message.push('\n');
+                        pending.message.push_str(self.buf.trim_end_matches(['\r', '\n']));
+                    }
+                    None
+                }
+            }
+        };
+        self.buf.clear();
+        completed.filter(|line| line.level >= self.min_level)
+    }
+}
+
+impl<R: BufRead> Iterator for DriverLog<R> {
+    type Item = std::io::Result<DriverLogLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let eof = match self.reader.read_line(&mut self.buf) {
+                Ok(read) => read == 0,
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some(line) = self.feed(eof) {
+                return Some(Ok(line));
+            }
+            if eof && self.pending.is_none() {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin> DriverLog<R> {
+    /// Reads the next entry, or `None` at the end of the stream. Wrap a
+    /// `tokio::process::ChildStderr` in a `tokio::io::BufReader` to follow a
+    /// running driver.
+    pub async fn next_line(&mut self) -> std::io::Result<Option<DriverLogLine>> {
+        use tokio::io::AsyncBufReadExt;
+        loop {
+            let eof = self.reader.read_line(&mut self.buf).await? == 0;
+            if let Some(line) = self.feed(eof) {
+                return Ok(Some(line));
+            }
+            if eof && self.pending.is_none() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// Session ids are hex strings, optionally in UUID form (geckodriver).
+fn is_session_id(candidate: &str) -> bool {
+    candidate.len() >= 16 && candidate.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chromedriver_line() {
+        let line = "[1752412345.678][WARNING]: [5f6c1a2b3c4d5e6f7a8b9c0d1e2f3a4b] COMMAND Navigate {\"url\": \"about:blank\"}";
+        let parsed = parse_log_line(line).unwrap();
+
+        assert_eq!(parsed.level, LogLevel::Warning);
+        assert_eq!(parsed.session_id.as_deref(), Some("5f6c1a2b3c4d5e6f7a8b9c0d1e2f3a4b"));
+        assert!(parsed.message.starts_with("COMMAND Navigate"));
+        assert_eq!(
+            parsed.timestamp.unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            1752412345
+        );
+    }
+
+    #[test]
+    fn test_parse_geckodriver_line() {
+        let log = "1752412345678\tgeckodriver\tINFO\tListening on 127.0.0.1:4444\n\
+                   not a log line\n\
+                   1752412345999\twebdriver::server\tDEBUG\t-> POST /session/3c0d7e0a-1b2c-4d5e-8f90-123456789abc/url {}";
+        let lines: Vec<_> = parse_log(log).collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level, LogLevel::Info);
+        assert_eq!(lines[0].module.as_deref(), Some("geckodriver"));
+        assert_eq!(lines[1].session_id.as_deref(), Some("3c0d7e0a-1b2c-4d5e-8f90-123456789abc"));
+    }
+
+    #[test]
+    fn test_driver_log() {
+        let log = "[1752412345.678][INFO]: Starting ChromeDriver\n\
+                   [1752412345.700][SEVERE]: Unable to receive message from renderer\n\
+                   #0 0x55d1c0ffee00 <unknown>\n\
+                   #1 0x55d1c0ffee01 <unknown>\n\
+                   [1752412345.800][WARNING]: This version of ChromeDriver has not been tested\n";
+
+        let lines: Vec<_> = DriverLog::new(log.as_bytes())
+            .min_level(LogLevel::Warning)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level, LogLevel::Error);
+        assert!(lines[0].message.ends_with("renderer\n#0 0x55d1c0ffee00 <unknown>\n#1 0x55d1c0ffee01 <unknown>"));
+        assert_eq!(lines[1].level, LogLevel::Warning);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_driver_log_next_line() {
+        let log = "[1752412345.678][INFO]: Starting ChromeDriver\n\
+                   [1752412345.700][SEVERE]: Unable to receive message from renderer\n\
+                   [1752412345.800][WARNING]: This version of ChromeDriver has not been tested\n";
+
+        let mut log = DriverLog::new(tokio::io::BufReader::new(log.as_bytes()));
+        let mut levels = Vec::new();
+        while let Some(line) = log.next_line().await.unwrap() {
+            levels.push(line.level);
+        }
+        assert_eq!(levels, [LogLevel::Info, LogLevel::Error, LogLevel::Warning]);
+    }
+}
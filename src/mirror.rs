@@ -0,0 +1,335 @@
+//! Synchronization of an internal driver mirror.
+//!
+//! [`sync_mirror`] downloads a selection of chromedriver versions/platforms
+//! plus the Chrome for Testing metadata JSON and writes them into a directory
+//! layout that mirrors the upstream URLs. The directory can be served by any
+//! static web server; the metadata is rewritten to point at the mirror.
+//!
+//! The legacy `LATEST_RELEASE_*` files and archives of chromedriver 114 and
+//! older are not mirrored.
+
+use crate::downloader::{download_file_with_options, fetch_text, DownloadOptions};
+use crate::drivers::chromedriver::{
+    CHROMEDRIVER_BUILDS_ENDPOINT, CHROMEDRIVER_CHANNELS_ENDPOINT, CHROMEDRIVER_MILESTONES_ENDPOINT,
+    CHROMEDRIVER_URLS_ENDPOINT,
+};
+use crate::error::WebDriverError;
+use crate::runtime::fs;
+use std::path::{Path, PathBuf};
+
+/// What to mirror and where.
+#[derive(Debug, Clone)]
+pub struct MirrorConfig {
+    /// Directory the mirror is written to.
+    pub output_dir: PathBuf,
+    /// Public URL the output directory will be served from.
+    pub base_url: String,
+    /// Versions to mirror. Entries match exactly or as a prefix
+    /// (`"138"` mirrors every `138.x.y.z`). Empty mirrors every version.
+    pub versions: Vec<String>,
+    /// Platforms to mirror, e.g. `linux64`, `win64`. Empty mirrors every platform.
+    pub platforms: Vec<String>,
+    /// Endpoints, proxy, timeouts and retries used to fetch the metadata and
+    /// the archives.
+    pub download_options: DownloadOptions,
+}
+
+/// Summary of a mirror synchronization run.
+#[derive(Debug, Clone, Default)]
+pub struct MirrorReport {
+    /// Archives downloaded during this run.
+    pub downloaded: Vec<PathBuf>,
+    /// Archives that were already present in the mirror.
+    pub skipped: Vec<PathBuf>,
+    /// The rewritten known-good-versions metadata file.
+    pub metadata_path: PathBuf,
+    /// The rewritten last-known-good-versions (release channel) metadata file.
+    pub channels_path: PathBuf,
+    /// The rewritten latest-patch-versions-per-build metadata file.
+    pub builds_path: PathBuf,
+    /// The rewritten latest-versions-per-milestone metadata file.
+    pub milestones_path: PathBuf,
+}
+
+/// Downloads the configured chromedriver archives and metadata into the
+/// mirror directory. The known-good-versions, release channel, per-build
+/// and per-milestone documents are mirrored, so every Chrome for Testing
+/// lookup of [`crate::drivers::chromedriver::ChromeDriver`] can be served.
+/// Chrome 114 and older, which use the legacy endpoint, cannot.
+pub async fn sync_mirror(config: &MirrorConfig) -> Result<MirrorReport, WebDriverError> {
+    let endpoints = &config.download_options.endpoints;
+    let mut report = MirrorReport::default();
+
+    report.metadata_path = mirror_document(
+        config,
+        &endpoints.chromedriver_versions_url(),
+        CHROMEDRIVER_URLS_ENDPOINT,
+        "versions",
+        &mut report,
+    )
+    .await?;
+    report.channels_path = mirror_document(
+        config,
+        &endpoints.chromedriver_channels_url(),
+        CHROMEDRIVER_CHANNELS_ENDPOINT,
+        "channels",
+        &mut report,
+    )
+    .await?;
+    report.builds_path = mirror_document(
+        config,
+        &endpoints.chromedriver_builds_url(),
+        CHROMEDRIVER_BUILDS_ENDPOINT,
+        "builds",
+        &mut report,
+    )
+    .await?;
+    report.milestones_path = mirror_document(
+        config,
+        &endpoints.chromedriver_milestones_url(),
+        CHROMEDRIVER_MILESTONES_ENDPOINT,
+        "milestones",
+        &mut report,
+    )
+    .await?;
+
+    Ok(report)
+}
+
+/// Mirrors the metadata document at `url`, whose entries are listed under
+/// `key` as an array or an object, and the selected archives it refers to.
+/// It is written where `upstream` (the default URL of the document) maps to
+/// in the mirror.
+async fn mirror_document(
+    config: &MirrorConfig,
+    url: &str,
+    upstream: &str,
+    key: &str,
+    report: &mut MirrorReport,
+) -> Result<PathBuf, WebDriverError> {
+    let mut metadata = fetch_metadata(url, &config.download_options).await?;
+    let entries: Vec<&mut serde_json::Value> = match metadata.get_mut(key) {
+        Some(serde_json::Value::Array(entries)) => entries.iter_mut().collect(),
+        Some(serde_json::Value::Object(entries)) => entries.values_mut().collect(),
+        _ => {
+            return Err(WebDriverError::Custom(format!(
+                "Unexpected metadata format: missing '{}'",
+                key
+            )))
+        }
+    };
+    for entry in entries {
+        mirror_version(config, entry, report).await?;
+    }
+    let path = mirror_path(&config.output_dir, upstream);
+    write_metadata(&path, &metadata).await?;
+    Ok(path)
+}
+
+async fn fetch_metadata(url: &str, options: &DownloadOptions) -> Result<serde_json::Value, WebDriverError> {
+    let body = fetch_text(url, options).await?;
+    serde_json::from_str(&body).map_err(|e| WebDriverError::json_parse(url, body.as_bytes(), e))
+}
+
+/// Downloads the selected chromedriver archives of a metadata entry having
+/// `version` and `downloads`, and points their URLs at the mirror.
+async fn mirror_version(
+    config: &MirrorConfig,
+    version: &mut serde_json::Value,
+    report: &mut MirrorReport,
+) -> Result<(), WebDriverError> {
+    let version_str = version["version"].as_str().unwrap_or_default().to_string();
+    if !matches_selection(&version_str, &config.versions) {
+        return Ok(());
+    }
+
+    let Some(downloads) = version["downloads"]["chromedriver"].as_array_mut() else {
+        return Ok(());
+    };
+    for download in downloads.iter_mut() {
+        let platform = download["platform"].as_str().unwrap_or_default();
+        if !config.platforms.is_empty() && !config.platforms.iter().any(|p| p == platform) {
+            continue;
+        }
+        let Some(url) = download["url"].as_str().map(str::to_string) else {
+            continue;
+        };
+
+        // The documents list many of the same versions.
+        let target = mirror_path(&config.output_dir, &url);
+        let seen = report.downloaded.contains(&target) || report.skipped.contains(&target);
+        if !seen && fs::exists(&target).await.unwrap_or(false) {
+            report.skipped.push(target);
+        } else if !seen {
+            let source = config.download_options.endpoints.rewrite_download_url("chromedriver", &url);
+            download_file_with_options(&source, &target, &config.download_options).await?;
+            report.downloaded.push(target);
+        }
+
+        download["url"] = serde_json::Value::String(rewrite_url(&url, &config.base_url));
+    }
+    Ok(())
+}
+
+async fn write_metadata(path: &Path, metadata: &serde_json::Value) -> Result<(), WebDriverError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| WebDriverError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+    }
+    let json = serde_json::to_vec_pretty(metadata).map_err(|e| WebDriverError::JsonParseError {
+        url: path.display().to_string(),
+        source: e,
+        snippet: String::new(),
+    })?;
+    fs::write(path, json)
+        .await
+        .map_err(|e| WebDriverError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })
+}
+
+fn matches_selection(version: &str, selection: &[String]) -> bool {
+    selection.is_empty()
+        || selection
+            .iter()
+            .any(|s| version == s || version.starts_with(&format!("{}.", s)))
+}
+
+/// The URL path without scheme and host, e.g. `chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip`.
+fn url_path(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split_once('/')
+        .map_or("", |(_, path)| path)
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+}
+
+/// Where an upstream URL is stored inside the mirror directory.
+fn mirror_path(output_dir: &Path, url: &str) -> PathBuf {
+    url_path(url)
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .fold(output_dir.to_path_buf(), |path, segment| path.join(segment))
+}
+
+/// Rewrites an upstream URL to its location on the mirror.
+fn rewrite_url(url: &str, base_url: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), url_path(url))
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "tokio")]
+    use crate::endpoints::Endpoints;
+    #[cfg(feature = "tokio")]
+    use crate::test_server::{Response, TestServer};
+
+    #[test]
+    fn test_mirror_layout_and_url_rewrite() {
+        let url = "https://storage.googleapis.com/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip";
+
+        assert_eq!(
+            mirror_path(Path::new("/srv/mirror"), url),
+            Path::new("/srv/mirror/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip")
+        );
+        assert_eq!(
+            rewrite_url(url, "https://mirror.internal/drivers/"),
+            "https://mirror.internal/drivers/chrome-for-testing-public/138.0.7204.157/linux64/chromedriver-linux64.zip"
+        );
+        assert!(matches_selection("138.0.7204.157", &["138".to_string()]));
+        assert!(!matches_selection("13.0.1.2", &["138".to_string()]));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sync_mirror() {
+        let server = TestServer::start(|request| {
+            let host = request.header("host").unwrap_or_default();
+            let download = |version: &str, platform: &str| {
+                format!(
+                    r#"{{"platform":"{1}","url":"http://{2}/cft/{0}/{1}/chromedriver-{1}.zip"}}"#,
+                    version, platform, host
+                )
+            };
+            let entry = |version: &str| {
+                format!(
+                    r#""version":"{}","downloads":{{"chromedriver":[{},{}]}}"#,
+                    version,
+                    download(version, "linux64"),
+                    download(version, "win64")
+                )
+            };
+            match request.path.as_str() {
+                "/known-good.json" => Response::ok(format!(
+                    r#"{{"versions":[{{{}}},{{{}}}]}}"#,
+                    entry("137.0.7151.119"),
+                    entry("138.0.7204.157")
+                )),
+                "/last-known-good.json" => Response::ok(format!(
+                    r#"{{"channels":{{"Stable":{{"channel":"Stable",{}}},"Beta":{{"channel":"Beta",{}}}}}}}"#,
+                    entry("138.0.7204.157"),
+                    entry("139.0.7258.5")
+                )),
+                "/builds.json" => Response::ok(format!(
+                    r#"{{"builds":{{"138.0.7204":{{{}}}}}}}"#,
+                    entry("138.0.7204.157")
+                )),
+                "/milestones.json" => Response::ok(format!(
+                    r#"{{"milestones":{{"138":{{"milestone":"138",{}}}}}}}"#,
+                    entry("138.0.7204.157")
+                )),
+                path => Response::ok(path.as_bytes().to_vec()),
+            }
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let endpoints = Endpoints::new()
+            .chromedriver_versions(server.url("/known-good.json"))
+            .chromedriver_channels(server.url("/last-known-good.json"))
+            .chromedriver_builds(server.url("/builds.json"))
+            .chromedriver_milestones(server.url("/milestones.json"));
+        let config = MirrorConfig {
+            output_dir: dir.path().to_path_buf(),
+            base_url: "https://mirror.internal/drivers".to_string(),
+            versions: vec!["138".to_string(), "139".to_string()],
+            platforms: vec!["linux64".to_string()],
+            download_options: DownloadOptions::new().endpoints(endpoints),
+        };
+
+        let report = sync_mirror(&config).await.unwrap();
+        let archive = dir.path().join("cft/138.0.7204.157/linux64/chromedriver-linux64.zip");
+        assert_eq!(report.downloaded, [archive.clone(), dir.path().join("cft/139.0.7258.5/linux64/chromedriver-linux64.zip")]);
+        assert_eq!(std::fs::read(&archive).unwrap(), b"/cft/138.0.7204.157/linux64/chromedriver-linux64.zip");
+
+        let channels: serde_json::Value = serde_json::from_slice(&std::fs::read(&report.channels_path).unwrap()).unwrap();
+        assert_eq!(
+            channels["channels"]["Beta"]["downloads"]["chromedriver"][0]["url"],
+            "https://mirror.internal/drivers/cft/139.0.7258.5/linux64/chromedriver-linux64.zip"
+        );
+        let metadata: serde_json::Value = serde_json::from_slice(&std::fs::read(&report.metadata_path).unwrap()).unwrap();
+        assert!(metadata["versions"][0]["downloads"]["chromedriver"][0]["url"].as_str().unwrap().starts_with("http://127.0.0.1"));
+        assert!(report.metadata_path.ends_with("chrome-for-testing/known-good-versions-with-downloads.json"));
+        for (path, key, entry) in [(&report.builds_path, "builds", "138.0.7204"), (&report.milestones_path, "milestones", "138")] {
+            let document: serde_json::Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+            assert_eq!(
+                document[key][entry]["downloads"]["chromedriver"][0]["url"],
+                "https://mirror.internal/drivers/cft/138.0.7204.157/linux64/chromedriver-linux64.zip"
+            );
+        }
+        assert!(report.milestones_path.ends_with("chrome-for-testing/latest-versions-per-milestone-with-downloads.json"));
+
+        let report = sync_mirror(&config).await.unwrap();
+        assert!(report.downloaded.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+    }
+}
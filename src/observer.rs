@@ -0,0 +1,93 @@
+//! Metrics hooks for installs.
+//!
+//! Set an [`InstallObserver`] with
+//! [`DownloadOptions::observer`](crate::downloader::DownloadOptions::observer)
+//! to feed timings, byte counts, retries and cache hits into CI telemetry,
+//! e.g. to spot a slow endpoint.
+
+use crate::report::InstallReport;
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// The caches an install consults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    /// Metadata documents such as version lists, see
+    /// [`MetadataCache`](crate::cache::MetadataCache).
+    Metadata,
+    /// Downloaded archives, see [`CacheStore`](crate::cache::CacheStore).
+    Artifact,
+    /// Installed drivers, see [`Cache`](crate::cache::Cache).
+    Driver,
+}
+
+/// Receives measurements of an install as it happens. Every method does
+/// nothing by default.
+pub trait InstallObserver: Debug + Send + Sync {
+    /// A metadata document such as a version list was fetched, possibly from
+    /// the metadata cache.
+    fn metadata_fetched(&self, _url: &str, _duration: Duration) {}
+
+    /// An archive was downloaded. `bytes` doesn't include the bytes of a
+    /// resumed partial download.
+    fn downloaded(&self, _url: &str, _bytes: u64, _duration: Duration) {}
+
+    /// A request is sent again; `attempt` counts the retries, starting at 1.
+    fn retried(&self, _url: &str, _attempt: u32) {}
+
+    /// A cache was consulted.
+    fn cache_lookup(&self, _cache: CacheKind, _hit: bool) {}
+
+    /// An install made through [`crate::setup_with`] or
+    /// [`crate::builder::ConfiguredManager::install`] finished, with the
+    /// durations of its phases.
+    fn installed(&self, _report: &InstallReport) {}
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::cache::Cache;
+    use crate::downloader::DownloadOptions;
+    use crate::report::InstallRecorder;
+    use crate::setup::{install_with_report, SetupOptions};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the measurements as strings.
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingObserver(pub(crate) Mutex<Vec<String>>);
+
+    impl InstallObserver for RecordingObserver {
+        fn downloaded(&self, url: &str, bytes: u64, _duration: Duration) {
+            self.0.lock().unwrap().push(format!("downloaded {} {}", url, bytes));
+        }
+        fn retried(&self, url: &str, attempt: u32) {
+            self.0.lock().unwrap().push(format!("retried {} {}", url, attempt));
+        }
+        fn cache_lookup(&self, cache: CacheKind, hit: bool) {
+            self.0.lock().unwrap().push(format!("cache {:?} {}", cache, hit));
+        }
+        fn installed(&self, report: &InstallReport) {
+            self.0.lock().unwrap().push(format!("installed {}", report.driver_version));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_receives_install_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let observer = Arc::new(RecordingObserver::default());
+        let options = SetupOptions::new()
+            .version("1.0")
+            .cache(Cache::new(dir.path()))
+            .download_options(DownloadOptions::new().observer(observer.clone()));
+
+        let manager = crate::tests::FakeManager::default();
+        install_with_report(&manager, &options, &InstallRecorder::default()).await.unwrap();
+        let measurements = observer.0.lock().unwrap();
+        assert_eq!(measurements.len(), 2);
+        assert!(measurements[0].starts_with("cache Driver "));
+        assert_eq!(measurements[1], "installed 1.0.1");
+    }
+}
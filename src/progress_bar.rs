@@ -0,0 +1,48 @@
+//! A ready-made [`indicatif`] progress bar for downloads.
+//!
+//! Enabled by the `progress-bar` feature:
+//!
+//! ```no_run
+//! use webdriver_manager::downloader::DownloadOptions;
+//!
+//! let options = DownloadOptions::new().progress_bar();
+//! ```
+
+use crate::downloader::{DownloadPhase, DownloadProgress, ProgressCallback};
+use indicatif::{ProgressBar, ProgressStyle};
+
+const DOWNLOAD_TEMPLATE: &str = "{msg:12} [{bar:40}] {bytes}/{total_bytes} ({eta})";
+const SPINNER_TEMPLATE: &str = "{spinner} {msg:12} {bytes}";
+
+/// Creates a progress callback drawing an indicatif bar on stderr.
+pub fn progress_bar_callback() -> ProgressCallback {
+    let bar = ProgressBar::no_length();
+    ProgressCallback::new(move |progress| update(&bar, progress))
+}
+
+fn update(bar: &ProgressBar, progress: DownloadProgress) {
+    match progress.phase {
+        DownloadPhase::Downloading => {
+            // The size is known from the first report on, set up the bar once.
+            if bar.length().is_none() {
+                match progress.total {
+                    Some(total) => {
+                        bar.set_length(total);
+                        bar.set_style(style(DOWNLOAD_TEMPLATE));
+                    }
+                    None => bar.set_style(style(SPINNER_TEMPLATE)),
+                }
+                bar.set_message("downloading");
+            }
+            bar.set_position(progress.downloaded);
+        }
+        DownloadPhase::Extracting => bar.set_message("extracting"),
+        DownloadPhase::Finished => bar.finish_with_message("installed"),
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
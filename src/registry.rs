@@ -0,0 +1,112 @@
+//! Registry for third-party driver managers.
+//!
+//! External crates can register their own [`WebDriverManager`]
+//! implementations under a name (e.g. `"yandexdriver"`). Registered names
+//! are resolved by [`crate::manager_for`] before the built-in browsers, so
+//! custom drivers plug into the same high-level flow.
+
+use crate::error::WebDriverError;
+use crate::WebDriverManager;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Creates a new instance of a registered manager.
+pub type ManagerFactory = Arc<dyn Fn() -> Box<dyn WebDriverManager> + Send + Sync>;
+
+/// A name → manager factory registry.
+#[derive(Default)]
+pub struct DriverRegistry {
+    factories: RwLock<HashMap<String, ManagerFactory>>,
+}
+
+impl DriverRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry consulted by [`crate::manager_for`].
+    pub fn global() -> &'static DriverRegistry {
+        static GLOBAL: OnceLock<DriverRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(DriverRegistry::new)
+    }
+
+    /// Registers a manager factory under `name` (case-insensitive),
+    /// replacing any earlier registration with the same name.
+    pub fn register<F>(&self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn WebDriverManager> + Send + Sync + 'static,
+    {
+        self.factories
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(normalize(name), Arc::new(factory));
+    }
+
+    /// Removes the registration for `name`. Returns whether it existed.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.factories
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&normalize(name))
+            .is_some()
+    }
+
+    /// Creates the manager registered under `name`.
+    pub fn get(&self, name: &str) -> Result<Box<dyn WebDriverManager>, WebDriverError> {
+        let factories = self.factories.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        factories
+            .get(&normalize(name))
+            .map(|factory| factory())
+            .ok_or_else(|| WebDriverError::UnsupportedBrowser(name.to_string()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains_key(&normalize(name))
+    }
+
+    /// The registered names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .factories
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl std::fmt::Debug for DriverRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DriverRegistry").field("names", &self.names()).finish()
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_ascii_lowercase()
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::chromedriver::ChromeDriver;
+
+    #[test]
+    fn test_register_and_resolve() {
+        let registry = DriverRegistry::new();
+        registry.register("YandexDriver", || Box::new(ChromeDriver::new()));
+
+        assert!(registry.contains("yandexdriver"));
+        assert_eq!(registry.get("yandexdriver").unwrap().get_driver_name(), "chromedriver");
+        assert!(registry.get("operadriver").is_err());
+        assert!(registry.unregister("yandexdriver"));
+        assert!(registry.names().is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+//! Install reports and exporters for provisioning results.
+//!
+//! [`InstallReport`] describes a single installation: what was installed,
+//! whether the cache was hit and how long each phase took. The exporters turn
+//! the outcome of installing/verifying drivers into JUnit XML or a Markdown
+//! summary, one entry per browser/driver, so CI systems can attach them to a
+//! run.
+
+use crate::downloader::{DownloadOptions, DownloadPhase, ProgressCallback};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The outcome of an installation, as returned by
+/// [`crate::builder::ConfiguredManager::install`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallReport {
+    /// Path to the driver executable.
+    pub driver_path: PathBuf,
+    pub browser_version: String,
+    pub driver_version: String,
+    /// Whether the driver was already installed, so nothing was downloaded.
+    pub cache_hit: bool,
+    /// Bytes downloaded for the driver archive.
+    pub bytes_downloaded: u64,
+    pub durations: PhaseDurations,
+    /// Hex encoded SHA-256 digest of the driver executable.
+    pub sha256: String,
+    /// Digest over the installed tree in deterministic mode (see
+    /// [`DownloadOptions::deterministic`]), the same for a fresh install and
+    /// a cache hit. `None` otherwise, or when installed outside the cache.
+    pub digest: Option<String>,
+}
+
+/// How long each phase of an installation took. Skipped phases are zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseDurations {
+    /// Detecting the browser and resolving the driver version and URL.
+    pub resolve: Duration,
+    pub download: Duration,
+    pub extract: Duration,
+    /// Verifying and recording the installed driver.
+    pub verify: Duration,
+    pub total: Duration,
+}
+
+/// The progress reports of an installation, with the time they arrived.
+#[derive(Debug, Default)]
+struct Recording {
+    bytes: u64,
+    downloading: Option<Instant>,
+    extracting: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+/// Records the progress of the installs made with the download options it
+/// is attached to, to build an [`InstallReport`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InstallRecorder(Arc<Mutex<Recording>>);
+
+impl InstallRecorder {
+    /// Adds the recorder to `options`, keeping any progress callback.
+    pub(crate) fn attach(&self, mut options: DownloadOptions) -> DownloadOptions {
+        let recording = self.0.clone();
+        let inner = options.progress.take();
+        options.progress = Some(ProgressCallback::new(move |progress| {
+            {
+                let mut recording = recording.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                let phase_start = match progress.phase {
+                    DownloadPhase::Downloading => &mut recording.downloading,
+                    DownloadPhase::Extracting => &mut recording.extracting,
+                    DownloadPhase::Finished => &mut recording.finished,
+                };
+                phase_start.get_or_insert(now);
+                if progress.phase == DownloadPhase::Downloading {
+                    recording.bytes = progress.downloaded;
+                }
+            }
+            if let Some(inner) = &inner {
+                inner.report(progress.phase, progress.downloaded, progress.total);
+            }
+        }));
+        options
+    }
+
+    /// Forgets the progress of an earlier install.
+    pub(crate) fn reset(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Recording::default();
+    }
+
+    /// Bytes downloaded, whether anything was installed, and the phase
+    /// durations of an install that started at `start`.
+    pub(crate) fn finish(&self, start: Instant) -> (u64, bool, PhaseDurations) {
+        let recording = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let end = Instant::now();
+        let between = |from: Option<Instant>, to: Option<Instant>| match (from, to) {
+            (Some(from), Some(to)) => to.saturating_duration_since(from),
+            _ => Duration::ZERO,
+        };
+
+        let first_progress = recording.downloading.or(recording.extracting);
+        let durations = PhaseDurations {
+            resolve: first_progress.unwrap_or(end).saturating_duration_since(start),
+            download: between(recording.downloading, recording.extracting),
+            extract: between(recording.extracting, recording.finished),
+            verify: between(recording.finished, Some(end)),
+            total: end.saturating_duration_since(start),
+        };
+        (recording.bytes, recording.extracting.is_some(), durations)
+    }
+}
+
+/// The outcome of provisioning a single driver.
+#[derive(Debug, Clone, Default)]
+pub struct ProvisioningResult {
+    pub browser: String,
+    pub browser_version: Option<String>,
+    pub driver: String,
+    pub driver_version: Option<String>,
+    pub driver_path: Option<PathBuf>,
+    pub duration: Option<Duration>,
+    /// The error message when provisioning failed.
+    pub error: Option<String>,
+}
+
+impl ProvisioningResult {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Renders the results as a JUnit XML report with one test case per driver.
+pub fn to_junit_xml(results: &[ProvisioningResult]) -> String {
+    let failures = results.iter().filter(|r| !r.is_success()).count();
+    let total_time: f64 = results
+        .iter()
+        .filter_map(|r| r.duration)
+        .map(|d| d.as_secs_f64())
+        .sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuite name=\"webdriver_manager\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+        results.len(),
+        failures,
+        total_time
+    );
+
+    for result in results {
+        let _ = write!(
+            xml,
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            escape_xml(&result.browser),
+            escape_xml(&result.driver),
+            result.duration.map_or(0.0, |d| d.as_secs_f64())
+        );
+
+        let properties = [
+            ("browser_version", result.browser_version.clone()),
+            ("driver_version", result.driver_version.clone()),
+            ("driver_path", result.driver_path.as_ref().map(|p| p.display().to_string())),
+        ];
+
+        xml.push_str(">\n    <properties>\n");
+        for (name, value) in properties {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    xml,
+                    "      <property name=\"{}\" value=\"{}\"/>",
+                    name,
+                    escape_xml(&value)
+                );
+            }
+        }
+        xml.push_str("    </properties>\n");
+
+        if let Some(error) = &result.error {
+            let _ = writeln!(
+                xml,
+                "    <failure message=\"{}\">{}</failure>",
+                escape_xml(error),
+                escape_xml(error)
+            );
+        }
+        xml.push_str("  </testcase>\n");
+    }
+
+    xml.push_str("</testsuite>\n");
+    xml
+}
+
+/// Renders the results as a Markdown table.
+pub fn to_markdown(results: &[ProvisioningResult]) -> String {
+    let mut md = String::from("| Browser | Browser version | Driver | Driver version | Status |\n");
+    md.push_str("|---|---|---|---|---|\n");
+
+    for result in results {
+        let status = match &result.error {
+            None => "✅ ok".to_string(),
+            Some(error) => format!("❌ {}", escape_markdown(error)),
+        };
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} | {} |",
+            escape_markdown(&result.browser),
+            result.browser_version.as_deref().unwrap_or("-"),
+            escape_markdown(&result.driver),
+            result.driver_version.as_deref().unwrap_or("-"),
+            status
+        );
+    }
+    md
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_markdown(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_recorder() {
+        let recorder = InstallRecorder::default();
+        let reports = Arc::new(Mutex::new(0));
+        let counter = reports.clone();
+        let options = recorder.attach(DownloadOptions::new().on_progress(move |_| *counter.lock().unwrap() += 1));
+        let progress = options.progress.unwrap();
+
+        let start = Instant::now();
+        progress.report(DownloadPhase::Downloading, 512, Some(1024));
+        progress.report(DownloadPhase::Downloading, 1024, Some(1024));
+        progress.report(DownloadPhase::Extracting, 1024, Some(1024));
+        progress.report(DownloadPhase::Finished, 1024, Some(1024));
+        let (bytes, installed, durations) = recorder.finish(start);
+        assert_eq!(bytes, 1024);
+        assert!(installed);
+        assert!(durations.total >= durations.resolve + durations.download + durations.extract);
+        // The callback set before is still called.
+        assert_eq!(*reports.lock().unwrap(), 4);
+
+        recorder.reset();
+        let (bytes, installed, durations) = recorder.finish(Instant::now());
+        assert_eq!((bytes, installed), (0, false));
+        assert_eq!(durations.resolve, durations.total);
+    }
+
+    fn sample() -> Vec<ProvisioningResult> {
+        vec![
+            ProvisioningResult {
+                browser: "chrome".into(),
+                browser_version: Some("138.0.7204.158".into()),
+                driver: "chromedriver".into(),
+                driver_version: Some("138.0.7204.157".into()),
+                driver_path: Some(PathBuf::from("/drivers/chromedriver")),
+                duration: Some(Duration::from_millis(1500)),
+                error: None,
+            },
+            ProvisioningResult {
+                browser: "firefox".into(),
+                driver: "geckodriver".into(),
+                error: Some("Browser not found <firefox>".into()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_junit_and_markdown_reports() {
+        let xml = to_junit_xml(&sample());
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<property name=\"driver_version\" value=\"138.0.7204.157\"/>"));
+        assert!(xml.contains("Browser not found &lt;firefox&gt;"));
+
+        let md = to_markdown(&sample());
+        assert_eq!(md.lines().count(), 4);
+        assert!(md.contains("| chrome | 138.0.7204.158 | chromedriver | 138.0.7204.157 | ✅ ok |"));
+    }
+}
@@ -0,0 +1,365 @@
+//! The async runtime the crate runs on.
+//!
+//! Resolving and installing drivers needs little from a runtime: somewhere
+//! to run blocking work (file system access, processes, archive extraction)
+//! and a timer. [`Runtime`] abstracts both, and everything else here is
+//! built on top of it, so the core works on any executor. HTTP requests go
+//! through [`crate::http::HttpClient`].
+//!
+//! With the default `tokio` feature, blocking work runs on tokio's blocking
+//! pool whenever a tokio runtime is running. Otherwise it runs on a thread
+//! of its own; async-std or smol users can install their executor's
+//! blocking pool with [`set_runtime`].
+
+use futures_channel::oneshot;
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Runs blocking work and timers for the crate.
+pub trait Runtime: Send + Sync {
+    /// Runs `job` where blocking is fine, e.g. on a blocking thread pool.
+    fn spawn_blocking(&self, job: Box<dyn FnOnce() + Send>);
+
+    /// A future completing after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Runs every blocking job and timer on a thread of its own. Works on any
+/// executor; the default without the `tokio` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadRuntime;
+
+impl Runtime for ThreadRuntime {
+    fn spawn_blocking(&self, job: Box<dyn FnOnce() + Send>) {
+        std::thread::spawn(job);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = sender.send(());
+        });
+        receiver.map(|_| ()).boxed()
+    }
+}
+
+/// Uses the blocking pool and timer of the current tokio runtime. Outside
+/// of one, falls back to [`ThreadRuntime`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+#[cfg(feature = "tokio")]
+impl Runtime for TokioRuntime {
+    fn spawn_blocking(&self, job: Box<dyn FnOnce() + Send>) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => drop(handle.spawn_blocking(job)),
+            Err(_) => ThreadRuntime.spawn_blocking(job),
+        }
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(_) => tokio::time::sleep(duration).boxed(),
+            Err(_) => ThreadRuntime.sleep(duration),
+        }
+    }
+}
+
+static RUNTIME: OnceLock<Box<dyn Runtime>> = OnceLock::new();
+
+/// Installs the runtime used for all blocking work and timers. Has to be
+/// called before the first installation; returns `false` when a runtime
+/// was already in use.
+pub fn set_runtime(runtime: impl Runtime + 'static) -> bool {
+    RUNTIME.set(Box::new(runtime)).is_ok()
+}
+
+#[cfg(feature = "tokio")]
+type DefaultRuntime = TokioRuntime;
+#[cfg(not(feature = "tokio"))]
+type DefaultRuntime = ThreadRuntime;
+
+fn runtime() -> &'static dyn Runtime {
+    RUNTIME.get_or_init(|| Box::new(DefaultRuntime::default())).as_ref()
+}
+
+/// Runs `f` on the runtime's blocking pool. A panic in `f` is resumed in
+/// the caller.
+pub(crate) async fn spawn_blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    let (sender, receiver) = oneshot::channel();
+    runtime().spawn_blocking(Box::new(move || {
+        let _ = sender.send(catch_unwind(AssertUnwindSafe(f)));
+    }));
+    match receiver.await {
+        Ok(Ok(output)) => output,
+        Ok(Err(panic)) => resume_unwind(panic),
+        Err(_) => panic!("the runtime dropped a blocking job"),
+    }
+}
+
+/// Completes after `duration`.
+pub(crate) async fn sleep(duration: Duration) {
+    runtime().sleep(duration).await
+}
+
+/// Runs `command` to completion and collects its output. The command is
+/// left as it was, so it can still be formatted into an error.
+pub(crate) async fn output(command: &mut Command) -> io::Result<Output> {
+    let taken = std::mem::replace(command, Command::new(""));
+    let (taken, output) = spawn_blocking(move || {
+        let mut command = taken;
+        let output = command.output();
+        (command, output)
+    })
+    .await;
+    *command = taken;
+    output
+}
+
+/// Async wrappers of the [`std::fs`] functions the crate uses, run through
+/// [`spawn_blocking`].
+pub(crate) mod fs {
+    use super::spawn_blocking;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    macro_rules! blocking_fs {
+        ($(fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {$(
+            pub(crate) async fn $name($($arg: $ty),*) -> io::Result<$ret> {
+                $(let $arg = $arg.as_ref().to_owned();)*
+                spawn_blocking(move || std::fs::$name($(&$arg),*)).await
+            }
+        )*};
+    }
+
+    blocking_fs! {
+        fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> u64;
+        fn create_dir_all(path: impl AsRef<Path>) -> ();
+        fn exists(path: impl AsRef<Path>) -> bool;
+        fn metadata(path: impl AsRef<Path>) -> std::fs::Metadata;
+        fn read(path: impl AsRef<Path>) -> Vec<u8>;
+        fn read_to_string(path: impl AsRef<Path>) -> String;
+        fn remove_dir_all(path: impl AsRef<Path>) -> ();
+        fn remove_file(path: impl AsRef<Path>) -> ();
+        fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> ();
+        fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> ();
+    }
+
+    pub(crate) async fn set_permissions(path: impl AsRef<Path>, permissions: std::fs::Permissions) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking(move || std::fs::set_permissions(path, permissions)).await
+    }
+
+    /// A file written from async code, one blocking write per chunk.
+    pub(crate) struct File {
+        path: PathBuf,
+        // Taken while a write is in flight.
+        file: Option<std::fs::File>,
+    }
+
+    impl File {
+        pub(crate) async fn create(path: impl AsRef<Path>) -> io::Result<File> {
+            let path = path.as_ref().to_owned();
+            let file = spawn_blocking({
+                let path = path.clone();
+                move || std::fs::File::create(path)
+            })
+            .await?;
+            Ok(File { path, file: Some(file) })
+        }
+
+        /// Opens an existing file for appending.
+        pub(crate) async fn append(path: impl AsRef<Path>) -> io::Result<File> {
+            let path = path.as_ref().to_owned();
+            let file = spawn_blocking({
+                let path = path.clone();
+                move || std::fs::OpenOptions::new().append(true).open(path)
+            })
+            .await?;
+            Ok(File { path, file: Some(file) })
+        }
+
+        pub(crate) async fn write_all(&mut self, chunk: &[u8]) -> io::Result<()> {
+            use std::io::Write;
+            let mut file = self.take()?;
+            let chunk = chunk.to_vec();
+            let (file, result) = spawn_blocking(move || {
+                let result = file.write_all(&chunk);
+                (file, result)
+            })
+            .await;
+            self.file = Some(file);
+            result
+        }
+
+        pub(crate) async fn flush(&mut self) -> io::Result<()> {
+            use std::io::Write;
+            let mut file = self.take()?;
+            let (file, result) = spawn_blocking(move || {
+                let result = file.flush();
+                (file, result)
+            })
+            .await;
+            self.file = Some(file);
+            result
+        }
+
+        fn take(&mut self) -> io::Result<std::fs::File> {
+            self.file.take().ok_or_else(|| {
+                io::Error::other(format!("'{}' was left behind by an interrupted write", self.path.display()))
+            })
+        }
+    }
+}
+
+/// Aborts an installation when cancelled, e.g. on Ctrl+C. Clones share
+/// their state; cancelling one cancels all of them.
+#[derive(Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    // Dropped on cancellation, which completes `signal`.
+    trigger: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    signal: Shared<oneshot::Receiver<()>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (trigger, signal) = oneshot::channel();
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            trigger: Arc::new(Mutex::new(Some(trigger))),
+            signal: signal.shared(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.trigger.lock().unwrap().take();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.signal.clone().map(|_| ())
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken").field("is_cancelled", &self.is_cancelled()).finish()
+    }
+}
+
+/// A value computed by the first caller of
+/// [`OnceCell::get_or_try_init`]; concurrent callers wait for it instead of
+/// computing it again.
+pub(crate) struct OnceCell<T> {
+    value: OnceLock<T>,
+    init: futures_util::lock::Mutex<()>,
+}
+
+impl<T> OnceCell<T> {
+    pub(crate) async fn get_or_try_init<E, F>(&self, init: impl FnOnce() -> F) -> Result<&T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let _guard = self.init.lock().await;
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = init().await?;
+        Ok(self.value.get_or_init(|| value))
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self {
+            value: OnceLock::new(),
+            init: futures_util::lock::Mutex::new(()),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnceCell").field(&self.value.get()).finish()
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_spawn_blocking() {
+        assert_eq!(spawn_blocking(|| 6 * 7).await, 42);
+
+        let panicked = std::panic::AssertUnwindSafe(spawn_blocking(|| panic!("boom"))).catch_unwind().await;
+        assert_eq!(panicked.unwrap_err().downcast_ref::<&str>(), Some(&"boom"));
+    }
+
+    #[tokio::test]
+    async fn test_thread_runtime() {
+        let (sender, receiver) = oneshot::channel();
+        ThreadRuntime.spawn_blocking(Box::new(move || sender.send(42).unwrap()));
+        assert_eq!(receiver.await.unwrap(), 42);
+
+        let started = Instant::now();
+        ThreadRuntime.sleep(Duration::from_millis(20)).await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_output_keeps_the_command() {
+        let mut command = Command::new("definitely-not-a-command");
+        command.arg("--version");
+        assert!(output(&mut command).await.is_err());
+        assert!(format!("{:?}", command).contains("--version"));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        let waiter = tokio::spawn(token.cancelled());
+        assert!(!clone.is_cancelled());
+
+        clone.cancel();
+        waiter.await.unwrap();
+        assert!(token.is_cancelled());
+        token.cancelled().await;
+    }
+
+    #[tokio::test]
+    async fn test_once_cell() {
+        let cell = OnceCell::default();
+        assert_eq!(cell.get_or_try_init(|| async { Err::<u32, _>("offline") }).await, Err("offline"));
+        assert_eq!(cell.get_or_try_init(|| async { Ok::<_, ()>(1) }).await, Ok(&1));
+        assert_eq!(cell.get_or_try_init(|| async { Ok::<_, ()>(2) }).await, Ok(&1));
+    }
+}
@@ -0,0 +1,159 @@
+//! Output compatible with Selenium Manager's JSON contract.
+//!
+//! Selenium clients invoke `selenium-manager --output json` and read a
+//! document of the form
+//! `{"logs": [...], "result": {"code": 0, "message": ..., "driver_path": ..., "browser_path": ...}}`.
+//! [`SeleniumManagerOutput`] produces the same document, so this crate can be
+//! used as a drop-in backend for tools that consume it; [`resolve`] installs a
+//! driver and reports the outcome in it, as `webdriver-manager install
+//! --output json` does.
+
+use crate::browser::{locate_browser, Browser};
+use crate::error::WebDriverError;
+use crate::logs::LogLevel;
+use crate::setup::{setup_with, SetupOptions};
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A log entry in Selenium Manager's output.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeleniumManagerLog {
+    pub level: String,
+    pub timestamp: u64,
+    pub message: String,
+}
+
+/// The `result` object in Selenium Manager's output.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeleniumManagerResult {
+    /// `0` on success, non-zero on failure.
+    pub code: i32,
+    /// The driver path on success, the error message on failure.
+    pub message: String,
+    pub driver_path: String,
+    pub browser_path: String,
+}
+
+/// The full Selenium Manager JSON document.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeleniumManagerOutput {
+    pub logs: Vec<SeleniumManagerLog>,
+    pub result: SeleniumManagerResult,
+}
+
+impl SeleniumManagerOutput {
+    /// Output for a successful resolution.
+    pub fn success(driver_path: &Path, browser_path: Option<&Path>) -> Self {
+        let driver_path = driver_path.display().to_string();
+        Self {
+            logs: Vec::new(),
+            result: SeleniumManagerResult {
+                code: 0,
+                message: driver_path.clone(),
+                driver_path,
+                browser_path: browser_path.map(|p| p.display().to_string()).unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Output for a failed resolution. The error is also added as a log entry.
+    pub fn failure(error: &WebDriverError) -> Self {
+        let mut output = Self {
+            logs: Vec::new(),
+            result: SeleniumManagerResult {
+                code: 65,
+                message: error.to_string(),
+                driver_path: String::new(),
+                browser_path: String::new(),
+            },
+        };
+        output.log(LogLevel::Error, error.to_string());
+        output
+    }
+
+    /// Appends a log entry.
+    pub fn log(&mut self, level: LogLevel, message: impl Into<String>) -> &mut Self {
+        let level = match level {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARN",
+            LogLevel::Error | LogLevel::Fatal => "ERROR",
+        };
+        self.logs.push(SeleniumManagerLog {
+            level: level.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Serializes the document to a JSON string.
+    pub fn to_json(&self) -> String {
+        // Serializing plain strings and numbers cannot fail.
+        serde_json::to_string_pretty(self).expect("Selenium Manager output is always serializable")
+    }
+}
+
+/// Installs the driver for `browser` like [`setup_with`] and reports the
+/// driver and browser paths, or the error, as Selenium Manager would.
+pub async fn resolve(browser: Browser, options: SetupOptions) -> SeleniumManagerOutput {
+    let browser_path = match options.browser_path.clone() {
+        Some(path) => Some(path),
+        None => locate_browser(browser.as_str()).await,
+    };
+    match setup_with(browser, options).await {
+        Ok(driver_path) => {
+            let mut output = SeleniumManagerOutput::success(&driver_path, browser_path.as_deref());
+            output.log(LogLevel::Info, format!("Driver path: {}", driver_path.display()));
+            if let Some(browser_path) = &browser_path {
+                output.log(LogLevel::Info, format!("Browser path: {}", browser_path.display()));
+            }
+            output
+        }
+        Err(e) => SeleniumManagerOutput::failure(&e),
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selenium_manager_json_shape() {
+        let mut output = SeleniumManagerOutput::success(
+            Path::new("/drivers/chromedriver"),
+            Some(Path::new("/usr/bin/google-chrome")),
+        );
+        output.log(LogLevel::Info, "Driver path: /drivers/chromedriver");
+
+        let json: serde_json::Value = serde_json::from_str(&output.to_json()).unwrap();
+        assert_eq!(json["result"]["code"], 0);
+        assert_eq!(json["result"]["driver_path"], "/drivers/chromedriver");
+        assert_eq!(json["result"]["browser_path"], "/usr/bin/google-chrome");
+        assert_eq!(json["logs"][0]["level"], "INFO");
+
+        let failure = SeleniumManagerOutput::failure(&WebDriverError::BrowserNotFound);
+        assert_ne!(failure.result.code, 0);
+        assert_eq!(failure.logs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SetupOptions::new()
+            .browser_path(dir.path().join("no-such-browser"))
+            .cache(crate::cache::Cache::new(dir.path()));
+
+        let output = resolve(Browser::Firefox, options).await;
+        assert_ne!(output.result.code, 0);
+        assert!(output.result.driver_path.is_empty());
+        assert_eq!(output.logs[0].level, "ERROR");
+    }
+}
@@ -0,0 +1,375 @@
+//! One-call driver setup.
+//!
+//! [`setup`] covers the common case: detect the browser, resolve the matching
+//! driver, install it into the user cache unless it is already there, and
+//! return the path to the executable. [`crate::builder::ManagerBuilder`]
+//! does the same for a configured manager.
+//!
+//! [`plan`] does the detection and resolution only, without downloading or
+//! writing anything, e.g. for a dry run or to compute a CI cache key.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), webdriver_manager::WebDriverError> {
+//! use webdriver_manager::Browser;
+//!
+//! let chromedriver = webdriver_manager::setup(Browser::Chrome).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::browser::Browser;
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::downloader::DownloadOptions;
+use crate::drivers::Driver;
+use crate::downloader::find_driver_executable;
+use crate::error::WebDriverError;
+use crate::downloader::{sha256_file_async, tree_digest};
+use crate::lockfile::{install_locked, Lockfile};
+use crate::observer::CacheKind;
+use crate::report::{InstallRecorder, InstallReport};
+use crate::WebDriverManager;
+use serde::Serialize;
+use std::path::{Component, PathBuf};
+use std::time::Instant;
+
+/// Options for [`setup_with`].
+#[derive(Debug, Clone, Default)]
+pub struct SetupOptions {
+    /// The browser executable. Without one, the browser is detected. Its
+    /// architecture also picks the chromedriver build.
+    pub browser_path: Option<PathBuf>,
+    /// The browser version or [`crate::VersionSpec`] to install the driver
+    /// for, such as `"126"` or `"stable"`. Skips the browser detection.
+    pub version: Option<String>,
+    /// The cache the driver is installed into. Defaults to [`Cache::user`].
+    pub cache: Option<Cache>,
+    /// Options for resolving and downloading the driver.
+    pub download_options: DownloadOptions,
+    /// Lockfile pinning the driver, see [`crate::lockfile`].
+    pub lockfile: Option<PathBuf>,
+    /// Resolves the driver again and updates its pin in the lockfile.
+    pub update_lockfile: bool,
+    /// Uses a compatible driver that is already installed, named by the
+    /// environment variable of the driver (`CHROMEDRIVER`, `GECKODRIVER`,
+    /// ...) or found on `PATH`, before resolving or downloading one. See
+    /// [`WebDriverManager::find_existing_driver`]. A lockfile takes
+    /// precedence.
+    pub reuse_system_driver: bool,
+}
+
+impl SetupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the browser at `path` instead of detecting it.
+    pub fn browser_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.browser_path = Some(path.into());
+        self
+    }
+
+    /// Installs the driver for `version` instead of the installed browser.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Installs the driver into `cache`.
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets the options used when resolving and downloading the driver.
+    pub fn download_options(mut self, options: DownloadOptions) -> Self {
+        self.download_options = options;
+        self
+    }
+
+    /// Pins the driver in the lockfile at `path`.
+    pub fn lockfile(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lockfile = Some(path.into());
+        self
+    }
+
+    /// Updates the pin in the lockfile instead of installing the pinned driver.
+    pub fn update_lockfile(mut self, update: bool) -> Self {
+        self.update_lockfile = update;
+        self
+    }
+
+    /// Uses a compatible driver from the environment or `PATH` when there is one.
+    pub fn reuse_system_driver(mut self, reuse: bool) -> Self {
+        self.reuse_system_driver = reuse;
+        self
+    }
+}
+
+/// Installs the driver for the installed `browser` into the user cache,
+/// unless a working one is already there, and returns its path. Settings
+/// from a [`Config`] file apply.
+pub async fn setup(browser: Browser) -> Result<PathBuf, WebDriverError> {
+    let config = Config::load()?;
+    config.register_browser_paths();
+    setup_with(browser, config.setup_options(browser)?).await
+}
+
+/// Like [`setup`], with the browser, version, cache and download settings
+/// taken from `options`.
+pub async fn setup_with(browser: Browser, options: SetupOptions) -> Result<PathBuf, WebDriverError> {
+    // The observer gets the phase durations of an InstallReport.
+    if options.download_options.observer.is_some() {
+        let recorder = InstallRecorder::default();
+        let manager = driver_for(browser, &options).with_download_options(recorder.attach(options.download_options.clone()));
+        return install_with_report(&manager, &options, &recorder)
+            .await
+            .map(|report| report.driver_path);
+    }
+    let manager = driver_for(browser, &options).with_download_options(options.download_options.clone());
+    install_with(&manager, &options).await
+}
+
+/// Installs the driver of `manager` as configured by `options`. The download
+/// options are expected to be applied to `manager` already.
+pub(crate) async fn install_with<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    options: &SetupOptions,
+) -> Result<PathBuf, WebDriverError> {
+    let browser_version = browser_version(manager, options).await?;
+    let cache = cache(options)?;
+    match &options.lockfile {
+        Some(path) => install_locked(manager, &cache, path, &browser_version, options.update_lockfile).await,
+        None => {
+            if options.reuse_system_driver
+                && let Some(driver_path) = manager.find_existing_driver(&browser_version).await
+            {
+                return Ok(driver_path);
+            }
+            manager.ensure_installed(&cache, &browser_version).await
+        }
+    }
+}
+
+/// Like [`install_with`], returning an [`InstallReport`]. `recorder` has to
+/// be attached to the download options of `manager`.
+pub(crate) async fn install_with_report<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    options: &SetupOptions,
+    recorder: &InstallRecorder,
+) -> Result<InstallReport, WebDriverError> {
+    let start = Instant::now();
+    recorder.reset();
+    let browser_version = browser_version(manager, options).await?;
+    let cache = cache(options)?;
+    let options = SetupOptions {
+        version: Some(browser_version.clone()),
+        cache: Some(cache.clone()),
+        ..options.clone()
+    };
+    let driver_path = install_with(manager, &options).await?;
+    let (bytes_downloaded, installed, durations) = recorder.finish(start);
+
+    // Cached installs live in `<root>/<driver>/<version>/<platform>/...`;
+    // otherwise, e.g. for safaridriver, the driver knows its version.
+    let cached = driver_path.strip_prefix(cache.root()).ok();
+    let cached_version = cached.and_then(|rest| match rest.components().nth(1) {
+        Some(Component::Normal(version)) => version.to_str().map(str::to_string),
+        _ => None,
+    });
+    let driver_version = match cached_version {
+        Some(version) => version,
+        None => manager.installed_driver_version(&driver_path).await?.to_string(),
+    };
+
+    // The installed tree was normalized when it was extracted, so digesting
+    // it again gives the install's digest, also on a cache hit.
+    let digest = match cached {
+        Some(rest) if options.download_options.deterministic => {
+            let install_dir = cache.root().join(rest.components().take(3).collect::<PathBuf>());
+            Some(tree_digest(&install_dir).await?)
+        }
+        _ => None,
+    };
+
+    let report = InstallReport {
+        sha256: sha256_file_async(&driver_path).await?,
+        driver_path,
+        browser_version,
+        driver_version,
+        cache_hit: !installed,
+        bytes_downloaded,
+        durations,
+        digest,
+    };
+    options.download_options.observe(|observer| {
+        observer.cache_lookup(CacheKind::Driver, report.cache_hit);
+        observer.installed(&report);
+    });
+    Ok(report)
+}
+
+/// What [`setup`] would do, as returned by [`plan`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstallPlan {
+    pub browser_version: String,
+    pub driver_version: String,
+    /// The URL the driver is downloaded from.
+    pub url: String,
+    /// The directory the driver is, or would be, installed into.
+    pub target_path: PathBuf,
+    /// Whether an intact install of the driver is already in the cache, so
+    /// nothing would be downloaded.
+    pub cached: bool,
+}
+
+/// Detects the browser and resolves its driver like [`setup`], without
+/// downloading or installing anything.
+pub async fn plan(browser: Browser) -> Result<InstallPlan, WebDriverError> {
+    let config = Config::load()?;
+    config.register_browser_paths();
+    plan_with(browser, config.setup_options(browser)?).await
+}
+
+/// Like [`plan`], with the settings taken from `options`.
+pub async fn plan_with(browser: Browser, options: SetupOptions) -> Result<InstallPlan, WebDriverError> {
+    let manager = driver_for(browser, &options).with_download_options(options.download_options.clone());
+    plan_install(&manager, &options).await
+}
+
+/// The manager of `browser`, using the configured browser to pick the
+/// driver's platform.
+fn driver_for(browser: Browser, options: &SetupOptions) -> Driver {
+    let driver = Driver::from(browser);
+    match &options.browser_path {
+        Some(path) => driver.with_browser_path(path),
+        None => driver,
+    }
+}
+
+/// Plans the install of the driver of `manager` as configured by `options`.
+pub(crate) async fn plan_install<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    options: &SetupOptions,
+) -> Result<InstallPlan, WebDriverError> {
+    let browser_version = browser_version(manager, options).await?;
+    let cache = cache(options)?;
+    let name = manager.get_driver_name();
+    let platform = manager.driver_platform();
+
+    let locked = match (&options.lockfile, options.update_lockfile) {
+        (Some(path), false) => Lockfile::load(path)?.get(name, &platform).cloned(),
+        _ => None,
+    };
+    let (driver_version, url) = match locked {
+        Some(locked) => (locked.version, locked.url),
+        None => (
+            manager.get_driver_version(&browser_version).await?.to_string(),
+            manager.get_download_url(&browser_version).await?,
+        ),
+    };
+
+    let target_path = cache.install_dir(name, &driver_version, &platform);
+    let cached = find_driver_executable(&target_path, name)
+        .is_ok_and(|driver_path| cache.is_intact(name, &driver_version, &platform, &driver_path));
+    Ok(InstallPlan {
+        browser_version,
+        driver_version,
+        url,
+        target_path,
+        cached,
+    })
+}
+
+/// The configured browser version, or the one of the installed browser.
+async fn browser_version<M: WebDriverManager + ?Sized>(
+    manager: &M,
+    options: &SetupOptions,
+) -> Result<String, WebDriverError> {
+    match &options.version {
+        Some(version) => Ok(version.clone()),
+        None => manager.get_browser_version(options.browser_path.as_deref()).await,
+    }
+}
+
+fn cache(options: &SetupOptions) -> Result<Cache, WebDriverError> {
+    match &options.cache {
+        Some(cache) => Ok(cache.clone()),
+        None => Cache::user(),
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::NetworkPolicy;
+    use crate::tests::FakeManager;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn test_setup_with_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SetupOptions::new()
+            .version("128.0")
+            .cache(Cache::new(dir.path()))
+            .download_options(DownloadOptions::new().network(NetworkPolicy::Offline));
+
+        // The version skips the browser detection and the download options
+        // reach the manager, which can't download offline.
+        assert!(matches!(
+            setup_with(Browser::Firefox, options).await,
+            Err(WebDriverError::OfflineUnavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plan_has_no_side_effects() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+        let options = SetupOptions::new().version("1.0").cache(cache.clone());
+        let manager = FakeManager::default();
+
+        let plan = plan_install(&manager, &options).await.unwrap();
+        assert_eq!(plan.driver_version, "1.0.1");
+        assert_eq!(plan.url, "https://example.com/fakedriver.zip");
+        assert_eq!(plan.target_path, cache.install_dir("fakedriver", "1.0.1", &manager.driver_platform()));
+        assert!(!plan.cached);
+        assert!(!dir.path().join("cache").exists());
+
+        install_with(&manager, &options).await.unwrap();
+        assert!(plan_install(&manager, &options).await.unwrap().cached);
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reuse_system_driver() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let system_driver = crate::tests::write_fake_driver(&bin, "1.0.5");
+        let manager = FakeManager {
+            path: Some(bin.into_os_string()),
+            ..Default::default()
+        };
+        let options = SetupOptions::new()
+            .version("1.0")
+            .cache(Cache::new(dir.path().join("cache")))
+            .reuse_system_driver(true);
+
+        // The compatible driver on PATH is used without downloading.
+        assert_eq!(install_with(&manager, &options).await.unwrap(), system_driver);
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 0);
+        assert!(!dir.path().join("cache").exists());
+
+        // An incompatible one, or the default setting, downloads.
+        let options = options.version("2.0");
+        assert_ne!(install_with(&manager, &options).await.unwrap(), system_driver);
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 1);
+        let options = options.version("1.0").reuse_system_driver(false);
+        assert_ne!(install_with(&manager, &options).await.unwrap(), system_driver);
+        assert_eq!(manager.installs.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,121 @@
+//! Download sources for driver artifacts.
+//!
+//! By default drivers are downloaded from their upstream locations. A
+//! [`DriverSource::Repository`] points the managers at a generic artifact
+//! repository (Artifactory, Nexus, ...) that mirrors the driver archives.
+
+use crate::http::{HttpError, HttpRequest};
+use ::http::header::{HeaderValue, AUTHORIZATION};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+
+/// Credentials used to authenticate against a download source.
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    /// HTTP basic authentication.
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// A bearer token sent in the `Authorization` header.
+    Bearer(String),
+}
+
+impl Credentials {
+    /// Applies the credentials to an outgoing request.
+    pub(crate) fn apply(&self, request: HttpRequest) -> Result<HttpRequest, HttpError> {
+        let value = match self {
+            Credentials::Basic { username, password } => {
+                let pair = format!("{}:{}", username, password.as_deref().unwrap_or_default());
+                format!("Basic {}", BASE64_STANDARD.encode(pair))
+            }
+            Credentials::Bearer(token) => format!("Bearer {}", token),
+        };
+        let mut value = HeaderValue::try_from(value).map_err(|e| HttpError::Request(Box::new(e)))?;
+        value.set_sensitive(true);
+        Ok(request.header(AUTHORIZATION, value))
+    }
+}
+
+/// A generic artifact repository hosting mirrored driver archives.
+///
+/// The `layout` is a path template appended to `base_url`. It supports the
+/// placeholders `{driver}`, `{version}`, `{platform}` and `{ext}`, e.g.
+/// `"{driver}/{version}/{driver}-{platform}.{ext}"`.
+#[derive(Debug, Clone)]
+pub struct ArtifactRepository {
+    pub base_url: String,
+    pub layout: String,
+    pub credentials: Option<Credentials>,
+}
+
+impl ArtifactRepository {
+    pub fn new(base_url: impl Into<String>, layout: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            layout: layout.into(),
+            credentials: None,
+        }
+    }
+
+    /// Sets the credentials used for every request to this repository.
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Builds the URL of an artifact by filling in the layout template.
+    pub fn artifact_url(&self, driver: &str, version: &str, platform: &str, ext: &str) -> String {
+        let path = self
+            .layout
+            .replace("{driver}", driver)
+            .replace("{version}", version)
+            .replace("{platform}", platform)
+            .replace("{ext}", ext);
+
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+/// Where driver archives are downloaded from.
+#[derive(Debug, Clone, Default)]
+pub enum DriverSource {
+    /// The official upstream download locations.
+    #[default]
+    Upstream,
+    /// A generic artifact repository mirroring the upstream archives.
+    Repository(ArtifactRepository),
+}
+
+impl DriverSource {
+    /// Returns the credentials that should be sent along with downloads.
+    pub fn credentials(&self) -> Option<&Credentials> {
+        match self {
+            DriverSource::Upstream => None,
+            DriverSource::Repository(repository) => repository.credentials.as_ref(),
+        }
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_url_from_layout() {
+        let repository = ArtifactRepository::new(
+            "https://artifactory.example.com/artifactory/drivers/",
+            "/{driver}/{version}/{driver}-{platform}.{ext}",
+        );
+
+        assert_eq!(
+            repository.artifact_url("chromedriver", "138.0.7204.157", "linux64", "zip"),
+            "https://artifactory.example.com/artifactory/drivers/chromedriver/138.0.7204.157/chromedriver-linux64.zip"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! A minimal HTTP/1.1 server for tests, answering every request through a
+//! handler closure. Each connection serves a single request.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A request as received by a [`TestServer`].
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    /// The path and query of the request line, e.g. `/driver.zip?page=2`.
+    pub(crate) path: String,
+    /// The request head, lowercased so headers can be matched easily.
+    pub(crate) head: String,
+}
+
+impl Request {
+    /// The value of a header, looked up case-insensitively.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        let prefix = format!("{}:", name.to_ascii_lowercase());
+        self.head
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .map(str::trim)
+    }
+}
+
+/// The response a handler sends back.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    status: &'static str,
+    headers: Vec<String>,
+    body: Vec<u8>,
+    content_length: Option<u64>,
+}
+
+impl Response {
+    /// A `200 OK` with `body`.
+    pub(crate) fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            headers: Vec::new(),
+            body: body.into(),
+            content_length: None,
+        }
+    }
+
+    /// An empty response with `status`, such as `"404 Not Found"`.
+    pub(crate) fn status(status: &'static str) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            content_length: None,
+        }
+    }
+
+    /// Replaces the status line, keeping the body.
+    pub(crate) fn with_status(mut self, status: &'static str) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Announces `len` bytes regardless of the body, to simulate huge files.
+    pub(crate) fn content_length(mut self, len: u64) -> Self {
+        self.content_length = Some(len);
+        self
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.headers.push(format!("{}: {}", name, value));
+        self
+    }
+}
+
+/// A server on a local port, running until the test's runtime shuts down.
+#[derive(Debug, Clone)]
+pub(crate) struct TestServer {
+    base: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    pub(crate) async fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.ends_with(b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let head = String::from_utf8_lossy(&head).into_owned();
+                let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let request = Request {
+                    path,
+                    head: head.to_ascii_lowercase(),
+                };
+                seen.lock().unwrap().push(request.clone());
+
+                let response = handler(&request);
+                let mut bytes = format!("HTTP/1.1 {}\r\n", response.status);
+                for header in &response.headers {
+                    bytes.push_str(header);
+                    bytes.push_str("\r\n");
+                }
+                let content_length = response.content_length.unwrap_or(response.body.len() as u64);
+                bytes.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", content_length));
+                let mut bytes = bytes.into_bytes();
+                bytes.extend_from_slice(&response.body);
+                // The client may hang up early, e.g. when a download is cancelled.
+                let _ = socket.write_all(&bytes).await;
+            }
+        });
+        Self { base, requests }
+    }
+
+    /// The server's base URL, `http://127.0.0.1:<port>`.
+    pub(crate) fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// The URL of `path` on the server.
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    /// The requests received so far.
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,72 @@
+//! Internal logging macros on top of `tracing`.
+//!
+//! With the `tracing` feature (on by default) [`debug!`], [`info!`] and
+//! [`warn!`] forward to the macros of the `tracing` crate; without it they
+//! compile to nothing. Spans are added with
+//! `#[cfg_attr(feature = "tracing", tracing::instrument(...))]`.
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    }};
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::info!($($arg)*);
+    }};
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    }};
+}
+
+// --- Tests ---
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::{Browser, Driver, WebDriverManager};
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the names of the spans created.
+    #[derive(Default)]
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            Id::from_u64(names.len() as u64)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_manager_calls_are_instrumented() {
+        let subscriber = SpanNames::default();
+        let names = subscriber.0.clone();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir().unwrap();
+        let driver = Driver::from(Browser::Firefox);
+        assert!(driver.get_browser_version(Some(&dir.path().join("firefox"))).await.is_err());
+
+        // The span of `Driver` and the one of `browser::get_browser_version`.
+        let names = names.lock().unwrap();
+        assert_eq!(names.iter().filter(|name| **name == "get_browser_version").count(), 2);
+    }
+}
@@ -0,0 +1,391 @@
+//! Typed browser and driver versions.
+//!
+//! Versions are compared numerically, component by component, so "13" never
+//! matches "130" the way string prefixes do.
+//!
+//! A [`VersionSpec`] describes what to install. Its string form is accepted
+//! wherever the [`WebDriverManager`](crate::WebDriverManager) methods take a
+//! browser version, e.g. `"latest-stable"` to install the newest stable
+//! chromedriver without a browser.
+
+use crate::error::WebDriverError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// A dotted version of up to four components, e.g. `126.0.6478.127` or
+/// `0.36.0`.
+///
+/// The number of components that were given is kept as the *precision*, so a
+/// version displays the way it was written and a partial version such as
+/// `127` can be used to [`match`](Version::matches) every `127.x` release.
+/// Missing components are zero for comparisons.
+#[derive(Debug, Clone, Copy)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub patch: u32,
+    precision: u8,
+}
+
+impl Version {
+    pub fn new(major: u32, minor: u32, build: u32, patch: u32) -> Self {
+        Self { major, minor, build, patch, precision: 4 }
+    }
+
+    /// The number of components the version was written with (1 to 4).
+    pub fn precision(&self) -> usize {
+        self.precision as usize
+    }
+
+    /// The version with only its first `precision` components, e.g. the
+    /// `MAJOR.MINOR.BUILD` prefix of a Chrome version.
+    pub fn truncate(&self, precision: usize) -> Self {
+        let precision = precision.clamp(1, self.precision());
+        let mut components = self.components();
+        components[precision..].fill(0);
+        let [major, minor, build, patch] = components;
+        Self { major, minor, build, patch, precision: precision as u8 }
+    }
+
+    /// Whether `other` starts with the components of this version, e.g.
+    /// `127` and `127.0.6533` both match `127.0.6533.88`.
+    pub fn matches(&self, other: &Version) -> bool {
+        self.components()[..self.precision()] == other.components()[..self.precision()]
+    }
+
+    fn components(&self) -> [u32; 4] {
+        [self.major, self.minor, self.build, self.patch]
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.components() == other.components()
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.components().hash(state);
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.components().cmp(&other.components())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components = self.components();
+        for (i, component) in components[..self.precision()].iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", component)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = WebDriverError;
+
+    /// Parses the leading dotted number of `s`. A leading `v` and trailing
+    /// qualifiers such as `esr` or `a1` are ignored: `115.13.0esr` parses as
+    /// `115.13.0`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_prefix(['v', 'V']).unwrap_or(trimmed);
+
+        let mut components = [0u32; 4];
+        let mut precision = 0;
+        for part in trimmed.split('.').take(4) {
+            let digits_len = part.bytes().take_while(u8::is_ascii_digit).count();
+            if digits_len == 0 {
+                break;
+            }
+            components[precision] = part[..digits_len]
+                .parse()
+                .map_err(|_| WebDriverError::InvalidVersion(s.to_string()))?;
+            precision += 1;
+
+            // A qualifier ends the version.
+            if digits_len < part.len() {
+                break;
+            }
+        }
+
+        if precision == 0 {
+            return Err(WebDriverError::InvalidVersion(s.to_string()));
+        }
+
+        let [major, minor, build, patch] = components;
+        Ok(Self { major, minor, build, patch, precision: precision as u8 })
+    }
+}
+
+/// How a [`Comparator`] compares versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    /// `126.*`: every version starting with the given components.
+    Wildcard,
+}
+
+/// A single condition of a [`VersionReq`], e.g. `>=126`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Comparator {
+    pub op: Op,
+    pub version: Version,
+}
+
+impl Comparator {
+    /// Partial versions cover all their releases: `<=127` includes
+    /// `127.0.6533.88` and `>126` excludes every `126.x`.
+    pub fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Greater => *version > self.version && !self.version.matches(version),
+            Op::GreaterEq => *version >= self.version,
+            Op::Less => *version < self.version,
+            Op::LessEq => *version <= self.version || self.version.matches(version),
+            Op::Wildcard => self.version.matches(version),
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            Op::Greater => write!(f, ">{}", self.version),
+            Op::GreaterEq => write!(f, ">={}", self.version),
+            Op::Less => write!(f, "<{}", self.version),
+            Op::LessEq => write!(f, "<={}", self.version),
+            Op::Wildcard => write!(f, "{}.*", self.version),
+        }
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = WebDriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (op, version) = if let Some(rest) = s.strip_prefix(">=") {
+            (Op::GreaterEq, rest)
+        } else if let Some(rest) = s.strip_prefix("<=") {
+            (Op::LessEq, rest)
+        } else if let Some(rest) = s.strip_prefix('>') {
+            (Op::Greater, rest)
+        } else if let Some(rest) = s.strip_prefix('<') {
+            (Op::Less, rest)
+        } else if let Some(rest) = s.strip_suffix(".*").or_else(|| s.strip_suffix('*')) {
+            (Op::Wildcard, rest)
+        } else {
+            return Err(WebDriverError::InvalidVersion(s.to_string()));
+        };
+
+        let version = version.trim();
+        // The version must be purely numeric; "126a" is not a valid bound.
+        if version.is_empty() || !version.split('.').all(|c| !c.is_empty() && c.bytes().all(|b| b.is_ascii_digit())) {
+            return Err(WebDriverError::InvalidVersion(s.to_string()));
+        }
+        Ok(Comparator { op, version: version.parse()? })
+    }
+}
+
+/// A set of version conditions that all have to hold, written comma
+/// separated: `>=126, <128` or `126.*`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionReq {
+    pub comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|comparator| comparator.matches(version))
+    }
+
+    /// The newest of `versions` matching the requirement.
+    pub fn select_newest<'a>(&self, versions: impl IntoIterator<Item = &'a Version>) -> Option<&'a Version> {
+        versions.into_iter().filter(|version| self.matches(version)).max()
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, comparator) in self.comparators.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", comparator)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = WebDriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let comparators = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Comparator>, _>>()?;
+        Ok(VersionReq { comparators })
+    }
+}
+
+/// What to resolve a driver for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSpec {
+    /// An installed browser's version, mapped to the closest driver.
+    Browser(String),
+    /// Exactly this driver version, without browser detection or fuzzy
+    /// matching. Written as `=126.0.6478.126`.
+    Exact(String),
+    /// The newest driver version in a range, e.g. `>=126, <128` or `126.*`.
+    Range(VersionReq),
+    /// The driver of the newest stable browser release.
+    LatestStable,
+    LatestBeta,
+    LatestDev,
+    LatestCanary,
+}
+
+impl fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionSpec::Browser(version) => write!(f, "{}", version),
+            VersionSpec::Exact(version) => write!(f, "={}", version),
+            VersionSpec::Range(req) => write!(f, "{}", req),
+            VersionSpec::LatestStable => write!(f, "latest-stable"),
+            VersionSpec::LatestBeta => write!(f, "latest-beta"),
+            VersionSpec::LatestDev => write!(f, "latest-dev"),
+            VersionSpec::LatestCanary => write!(f, "latest-canary"),
+        }
+    }
+}
+
+impl FromStr for VersionSpec {
+    type Err = WebDriverError;
+
+    /// Parses the string form: `latest`, `stable`, `beta`, `dev` and
+    /// `canary` (optionally prefixed with `latest-`) select a channel,
+    /// `=<version>` pins a driver version, specs containing `<`, `>`, `*` or
+    /// `,` are ranges, anything else is a browser version.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if let Some(exact) = trimmed.strip_prefix('=') {
+            let exact = exact.trim();
+            exact.parse::<Version>()?;
+            return Ok(VersionSpec::Exact(exact.to_string()));
+        }
+        if trimmed.contains(['<', '>', '*', ',']) {
+            return Ok(VersionSpec::Range(trimmed.parse()?));
+        }
+        let lower = trimmed.to_lowercase();
+        match lower.strip_prefix("latest-").unwrap_or(&lower) {
+            "latest" | "stable" => Ok(VersionSpec::LatestStable),
+            "beta" => Ok(VersionSpec::LatestBeta),
+            "dev" => Ok(VersionSpec::LatestDev),
+            "canary" => Ok(VersionSpec::LatestCanary),
+            _ => Ok(VersionSpec::Browser(trimmed.to_string())),
+        }
+    }
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_and_order() {
+        let chrome: Version = "126.0.6478.127".parse().unwrap();
+        assert_eq!(chrome, Version::new(126, 0, 6478, 127));
+        assert_eq!(chrome.to_string(), "126.0.6478.127");
+
+        let esr: Version = "115.13.0esr".parse().unwrap();
+        assert_eq!((esr.major, esr.minor, esr.build, esr.precision()), (115, 13, 0, 3));
+        assert_eq!("v0.36.0".parse::<Version>().unwrap().to_string(), "0.36.0");
+
+        assert!("13.0.1".parse::<Version>().unwrap() < "130.0.0".parse().unwrap());
+        assert!("126.0.6478.9".parse::<Version>().unwrap() < "126.0.6478.10".parse().unwrap());
+        assert!("not-a-version".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let driver: Version = "130.0.6723.58".parse().unwrap();
+        assert!("130".parse::<Version>().unwrap().matches(&driver));
+        assert!(!"13".parse::<Version>().unwrap().matches(&driver));
+
+        let browser: Version = "130.0.6723.91".parse().unwrap();
+        assert!(browser.truncate(3).matches(&driver));
+        assert_eq!(browser.truncate(3).to_string(), "130.0.6723");
+        assert!(!browser.matches(&driver));
+    }
+
+    #[test]
+    fn test_version_req() {
+        let versions: Vec<Version> = ["125.0.6422.141", "126.0.6478.126", "127.0.6533.88", "128.0.6613.84"]
+            .iter()
+            .map(|v| v.parse().unwrap())
+            .collect();
+        let newest = |req: &str| {
+            let req: VersionReq = req.parse().unwrap();
+            req.select_newest(&versions).map(ToString::to_string)
+        };
+
+        assert_eq!(newest(">=126, <128").as_deref(), Some("127.0.6533.88"));
+        assert_eq!(newest("126.*").as_deref(), Some("126.0.6478.126"));
+        assert_eq!(newest("<=126").as_deref(), Some("126.0.6478.126"));
+        assert_eq!(newest(">125, <127").as_deref(), Some("126.0.6478.126"));
+        assert_eq!(newest(">128"), None);
+        assert_eq!(">=126, <128".parse::<VersionReq>().unwrap().to_string(), ">=126, <128");
+        assert!("~126".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn test_version_spec() {
+        assert_eq!("latest".parse::<VersionSpec>().unwrap(), VersionSpec::LatestStable);
+        assert_eq!(" Latest-Canary ".parse::<VersionSpec>().unwrap(), VersionSpec::LatestCanary);
+        assert_eq!("beta".parse::<VersionSpec>().unwrap(), VersionSpec::LatestBeta);
+        assert_eq!(
+            "126.0.6478.127".parse::<VersionSpec>().unwrap(),
+            VersionSpec::Browser("126.0.6478.127".to_string())
+        );
+        assert_eq!(
+            "= 126.0.6478.126".parse::<VersionSpec>().unwrap(),
+            VersionSpec::Exact("126.0.6478.126".to_string())
+        );
+        assert!("=stable".parse::<VersionSpec>().is_err());
+        assert!(matches!(">=126, <128".parse::<VersionSpec>().unwrap(), VersionSpec::Range(_)));
+        assert!(">=latest".parse::<VersionSpec>().is_err());
+        for spec in [
+            VersionSpec::LatestDev,
+            VersionSpec::Browser("127".to_string()),
+            VersionSpec::Exact("0.36.0".to_string()),
+            VersionSpec::Range("126.*".parse().unwrap()),
+        ] {
+            assert_eq!(spec.to_string().parse::<VersionSpec>().unwrap(), spec);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use webdriver_manager::{drivers::chromedriver::ChromeDriver, WebDriverError, Web
 #[tokio::test]
 async fn test_full_chromedriver_install_flow() {
     // 1. Instantiate the manager.
-    let manager = ChromeDriver;
+    let manager = ChromeDriver::new();
 
     // 2. Define a temporary installation directory within the project's target folder.
     let install_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))